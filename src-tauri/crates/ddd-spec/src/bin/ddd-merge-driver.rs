@@ -0,0 +1,72 @@
+//! `git merge` driver for flow and domain specs. The app's `register_merge_driver`
+//! command sets it up; by hand it is
+//!
+//! ```text
+//! git config merge.ddd-spec.name "DDD spec merge"
+//! git config merge.ddd-spec.driver "ddd-merge-driver %O %A %B %P"
+//! echo 'specs/domains/*/flows/*.yaml merge=ddd-spec' >> .gitattributes
+//! ```
+//!
+//! Git passes the ancestor, ours and theirs as temporary files plus the path being
+//! merged, and expects the result in ours' file with exit status 0 when the merge is
+//! clean. Conflicts keep ours and are listed in a comment at the top of the file.
+//! Anything the driver can't merge by value falls back to `git merge-file`.
+
+use ddd_spec::merge::{conflict_header, merge_specs, MergeKind};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, ExitCode};
+
+fn merge(base: &str, ours: &str, theirs: &str, path: &str) -> Result<bool, String> {
+    let kind = MergeKind::for_path(Path::new(path))
+        .ok_or_else(|| format!("{} is not a flow or domain spec", path))?;
+    let read = |file: &str| {
+        fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file, e))
+    };
+    let outcome = merge_specs(kind, &read(base)?, &read(ours)?, &read(theirs)?)?;
+    for conflict in &outcome.conflicts {
+        eprintln!(
+            "CONFLICT ({}): {} {}",
+            path, conflict.path, conflict.message
+        );
+    }
+    let content = if outcome.clean {
+        outcome.content
+    } else {
+        conflict_header(&outcome.conflicts) + &outcome.content
+    };
+    fs::write(ours, content).map_err(|e| format!("Failed to write {}: {}", ours, e))?;
+    Ok(outcome.clean)
+}
+
+/// Git's own line merge, leaving conflict markers in ours.
+fn line_merge(base: &str, ours: &str, theirs: &str) -> ExitCode {
+    let status = Command::new("git")
+        .args(["merge-file", "-L", "ours", "-L", "base", "-L", "theirs"])
+        .args([ours, base, theirs])
+        .status();
+    match status {
+        Ok(status) if status.success() => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("ddd-merge-driver: failed to run git merge-file: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [base, ours, theirs, path] = args.as_slice() else {
+        eprintln!("usage: ddd-merge-driver <base> <ours> <theirs> <path>");
+        return ExitCode::from(2);
+    };
+    match merge(base, ours, theirs, path) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("ddd-merge-driver: {}; falling back to a line merge", e);
+            line_merge(base, ours, theirs)
+        }
+    }
+}
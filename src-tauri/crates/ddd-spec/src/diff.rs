@@ -0,0 +1,616 @@
+//! Semantic diff between two versions of a flow or domain spec, over the typed model
+//! rather than the YAML text: nodes added, removed or retyped, connections rewired
+//! and fields changed. Layout positions are reported apart from everything else so
+//! a review can leave them out.
+//!
+//! Field paths use the patch path syntax of [`super::patch`], with sequence items
+//! matched by `id` (or `event`, `name`) where they have one, so reordering a list is
+//! not a change.
+
+use super::{DomainConfig, FlowDocument, FlowNode, Position};
+use indexmap::{IndexMap, IndexSet};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    /// Node the field belongs to; `None` outside nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    /// Path from the document root, e.g. `nodes[id=process-1].spec.retry.max_attempts`.
+    pub path: String,
+    /// Absent when the field was added.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    /// Absent when the field was removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeSummary {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub label: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeRetype {
+    pub id: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// A connection added (`before` absent), removed (`after` absent) or rewired from
+/// one target to another.
+#[derive(Serialize, Clone, Debug)]
+pub struct ConnectionChange {
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_handle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+/// A node, flow or portal that moved on the canvas. Only items present in both
+/// versions are listed.
+#[derive(Serialize, Clone, Debug)]
+pub struct PositionChange {
+    pub id: String,
+    pub before: Position,
+    pub after: Position,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct FlowDiff {
+    pub nodes_added: Vec<NodeSummary>,
+    pub nodes_removed: Vec<NodeSummary>,
+    pub nodes_retyped: Vec<NodeRetype>,
+    pub connections: Vec<ConnectionChange>,
+    pub fields: Vec<FieldChange>,
+    /// Empty when positions are ignored.
+    pub positions: Vec<PositionChange>,
+    /// Anything other than positions changed.
+    pub has_changes: bool,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct DomainDiff {
+    pub fields: Vec<FieldChange>,
+    /// Flow and portal positions from `layout`. Empty when positions are ignored.
+    pub positions: Vec<PositionChange>,
+    pub has_changes: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpecDiff {
+    Flow(FlowDiff),
+    Domain(DomainDiff),
+}
+
+/// Keys that identify an item of a sequence, in order of preference.
+pub(super) const ITEM_KEYS: [&str; 3] = ["id", "event", "name"];
+
+pub(super) fn push_key(path: &str, key: &str) -> String {
+    let plain = !key.is_empty() && !key.contains(['.', '[', ']', '=', '"', '\'']);
+    match (path.is_empty(), plain) {
+        (true, true) => key.to_string(),
+        (false, true) => format!("{}.{}", path, key),
+        (_, false) => format!("{}[\"{}\"]", path, key),
+    }
+}
+
+/// The key every item of both sequences can be matched by, if any.
+fn item_key(before: &[Value], after: &[Value]) -> Option<&'static str> {
+    ITEM_KEYS.into_iter().find(|key| {
+        [before, after].iter().all(|items| {
+            let mut seen = IndexSet::new();
+            items.iter().all(|item| {
+                item.get(key)
+                    .and_then(Value::as_str)
+                    .is_some_and(|id| seen.insert(id))
+            })
+        })
+    })
+}
+
+fn value_changes(
+    node: Option<&str>,
+    path: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+    out: &mut Vec<FieldChange>,
+) {
+    let (before, after) = match (before, after) {
+        (Some(b), Some(a)) if b == a => return,
+        (None, None) => return,
+        (Some(b), Some(a)) => (b, a),
+        (b, a) => {
+            out.push(FieldChange {
+                node: node.map(str::to_string),
+                path: path.to_string(),
+                before: b.cloned(),
+                after: a.cloned(),
+            });
+            return;
+        }
+    };
+    match (before, after) {
+        (Value::Object(b), Value::Object(a)) => {
+            let keys: IndexSet<&String> = b.keys().chain(a.keys()).collect();
+            for key in keys {
+                value_changes(node, &push_key(path, key), b.get(key), a.get(key), out);
+            }
+        }
+        (Value::Array(b), Value::Array(a)) => {
+            if let Some(key) = item_key(b, a) {
+                let id = |item: &Value| item[key].as_str().unwrap_or_default().to_string();
+                let b: IndexMap<String, &Value> = b.iter().map(|v| (id(v), v)).collect();
+                let a: IndexMap<String, &Value> = a.iter().map(|v| (id(v), v)).collect();
+                let ids: IndexSet<&String> = b.keys().chain(a.keys()).collect();
+                for id in ids {
+                    let item_path = format!("{}[{}={}]", path, key, id);
+                    value_changes(
+                        node,
+                        &item_path,
+                        b.get(id).copied(),
+                        a.get(id).copied(),
+                        out,
+                    );
+                }
+            } else if b.len() == a.len() {
+                for (i, (b, a)) in b.iter().zip(a).enumerate() {
+                    value_changes(node, &format!("{}[{}]", path, i), Some(b), Some(a), out);
+                }
+            } else {
+                out.push(FieldChange {
+                    node: node.map(str::to_string),
+                    path: path.to_string(),
+                    before: Some(before.clone()),
+                    after: Some(after.clone()),
+                });
+            }
+        }
+        _ => out.push(FieldChange {
+            node: node.map(str::to_string),
+            path: path.to_string(),
+            before: Some(before.clone()),
+            after: Some(after.clone()),
+        }),
+    }
+}
+
+/// `value` as an object without `keys`, for diffing what's left of it.
+fn without(value: Value, keys: &[&str]) -> Value {
+    match value {
+        Value::Object(mut map) => {
+            for key in keys {
+                map.remove(*key);
+            }
+            Value::Object(map)
+        }
+        other => other,
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn summary(node: &FlowNode) -> NodeSummary {
+    NodeSummary {
+        id: node.id.clone(),
+        node_type: node.node_type.as_str().to_string(),
+        label: node.label.clone(),
+    }
+}
+
+/// Targets of a node's connections, grouped by source handle.
+fn targets(node: &FlowNode) -> IndexMap<Option<&str>, IndexSet<&str>> {
+    let mut targets: IndexMap<Option<&str>, IndexSet<&str>> = IndexMap::new();
+    for connection in &node.connections {
+        targets
+            .entry(connection.source_handle.as_deref())
+            .or_default()
+            .insert(&connection.target_node_id);
+    }
+    targets
+}
+
+fn connection_changes(
+    source: &str,
+    before: Option<&FlowNode>,
+    after: Option<&FlowNode>,
+    out: &mut Vec<ConnectionChange>,
+) {
+    let before = before.map(targets).unwrap_or_default();
+    let after = after.map(targets).unwrap_or_default();
+    let handles: IndexSet<Option<&str>> = before.keys().chain(after.keys()).copied().collect();
+    for handle in handles {
+        let empty = IndexSet::new();
+        let b = before.get(&handle).unwrap_or(&empty);
+        let a = after.get(&handle).unwrap_or(&empty);
+        let removed: Vec<&str> = b.difference(a).copied().collect();
+        let added: Vec<&str> = a.difference(b).copied().collect();
+        let change = |before: Option<&str>, after: Option<&str>| ConnectionChange {
+            source: source.to_string(),
+            source_handle: handle.map(str::to_string),
+            before: before.map(str::to_string),
+            after: after.map(str::to_string),
+        };
+        // A handle that points somewhere else now is one rewiring, not a removal
+        // and an addition.
+        if let ([from], [to]) = (removed.as_slice(), added.as_slice()) {
+            out.push(change(Some(from), Some(to)));
+            continue;
+        }
+        out.extend(removed.iter().map(|from| change(Some(from), None)));
+        out.extend(added.iter().map(|to| change(None, Some(to))));
+    }
+}
+
+/// Path of a node in the flow document.
+fn node_path(doc: &FlowDocument, id: &str) -> String {
+    if doc.trigger.id == id {
+        "trigger".to_string()
+    } else {
+        format!("nodes[id={}]", id)
+    }
+}
+
+/// Compare two versions of a flow. `None` stands for a flow that doesn't exist on
+/// that side, so everything in the other one is added or removed.
+pub fn diff_flows(
+    before: Option<&FlowDocument>,
+    after: Option<&FlowDocument>,
+    ignore_positions: bool,
+) -> FlowDiff {
+    let mut diff = FlowDiff::default();
+    let nodes = |doc: Option<&FlowDocument>| -> IndexMap<String, FlowNode> {
+        doc.into_iter()
+            .flat_map(|d| d.all_nodes())
+            .map(|n| (n.id.clone(), n.clone()))
+            .collect()
+    };
+    let before_nodes = nodes(before);
+    let after_nodes = nodes(after);
+
+    let rest =
+        |doc: Option<&FlowDocument>| doc.map(|d| without(to_value(d), &["trigger", "nodes"]));
+    value_changes(
+        None,
+        "",
+        rest(before).as_ref(),
+        rest(after).as_ref(),
+        &mut diff.fields,
+    );
+
+    let ids: IndexSet<&String> = before_nodes.keys().chain(after_nodes.keys()).collect();
+    for id in ids {
+        let b = before_nodes.get(id);
+        let a = after_nodes.get(id);
+        match (b, a) {
+            (Some(b), None) => diff.nodes_removed.push(summary(b)),
+            (None, Some(a)) => diff.nodes_added.push(summary(a)),
+            (Some(b), Some(a)) => {
+                if b.node_type != a.node_type {
+                    diff.nodes_retyped.push(NodeRetype {
+                        id: id.clone(),
+                        before: b.node_type.as_str().to_string(),
+                        after: a.node_type.as_str().to_string(),
+                    });
+                }
+                if b.position != a.position && !ignore_positions {
+                    diff.positions.push(PositionChange {
+                        id: id.clone(),
+                        before: b.position.clone(),
+                        after: a.position.clone(),
+                    });
+                }
+                let fields =
+                    |n: &FlowNode| without(to_value(n), &["id", "type", "position", "connections"]);
+                // Both sides exist, so the after document is there.
+                let path = node_path(after.expect("node in after"), id);
+                value_changes(
+                    Some(id),
+                    &path,
+                    Some(&fields(b)),
+                    Some(&fields(a)),
+                    &mut diff.fields,
+                );
+            }
+            (None, None) => {}
+        }
+        connection_changes(id, b, a, &mut diff.connections);
+    }
+
+    diff.has_changes = !diff.nodes_added.is_empty()
+        || !diff.nodes_removed.is_empty()
+        || !diff.nodes_retyped.is_empty()
+        || !diff.connections.is_empty()
+        || !diff.fields.is_empty();
+    diff
+}
+
+fn position_changes(
+    before: &IndexMap<String, Position>,
+    after: &IndexMap<String, Position>,
+    out: &mut Vec<PositionChange>,
+) {
+    for (id, b) in before {
+        if let Some(a) = after.get(id).filter(|a| *a != b) {
+            out.push(PositionChange {
+                id: id.clone(),
+                before: b.clone(),
+                after: a.clone(),
+            });
+        }
+    }
+}
+
+/// Everything but the layout positions. Event wirings read from bare names serialize
+/// back as plain strings; they are spelled out so both versions' items can be
+/// matched by `event`.
+fn domain_value(config: &DomainConfig) -> Value {
+    let mut config = config.clone();
+    for wiring in config
+        .publishes_events
+        .iter_mut()
+        .chain(config.consumes_events.iter_mut())
+    {
+        wiring.shorthand = false;
+    }
+    let mut value = to_value(&config);
+    if let Some(layout) = value.get_mut("layout") {
+        *layout = without(layout.take(), &["flows", "portals"]);
+    }
+    value
+}
+
+/// Compare two versions of a `domain.yaml`. `None` stands for a missing file.
+pub fn diff_domains(
+    before: Option<&DomainConfig>,
+    after: Option<&DomainConfig>,
+    ignore_positions: bool,
+) -> DomainDiff {
+    let mut diff = DomainDiff::default();
+    value_changes(
+        None,
+        "",
+        before.map(domain_value).as_ref(),
+        after.map(domain_value).as_ref(),
+        &mut diff.fields,
+    );
+    if let (Some(b), Some(a), false) = (before, after, ignore_positions) {
+        position_changes(&b.layout.flows, &a.layout.flows, &mut diff.positions);
+        position_changes(&b.layout.portals, &a.layout.portals, &mut diff.positions);
+    }
+
+    diff.has_changes = !diff.fields.is_empty();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_yaml;
+
+    const FLOW: &str = "\
+flow: {id: checkout, name: Checkout, type: traditional, domain: billing}
+trigger:
+  id: trigger
+  type: trigger
+  position: {x: 0, y: 0}
+  connections: [{targetNodeId: form}]
+  spec: {event: OrderPlaced}
+  label: Start
+nodes:
+  - id: form
+    type: input
+    position: {x: 0, y: 100}
+    connections: [{targetNodeId: check}]
+    spec:
+      fields:
+        - {name: email, type: string}
+        - {name: plan, type: string}
+    label: Order form
+  - id: check
+    type: decision
+    position: {x: 0, y: 200}
+    connections:
+      - {targetNodeId: charge, sourceHandle: 'true'}
+      - {targetNodeId: fail, sourceHandle: 'false'}
+    spec: {condition: in stock?}
+    label: In stock?
+  - id: charge
+    type: service_call
+    position: {x: 0, y: 300}
+    connections: [{targetNodeId: done}]
+    spec:
+      method: POST
+      url: https://pay.example.com
+      retry: {max_attempts: 3}
+    label: Charge
+  - id: done
+    type: terminal
+    position: {x: 0, y: 400}
+    label: Done
+  - id: fail
+    type: terminal
+    position: {x: 200, y: 300}
+    label: Out of stock
+";
+
+    fn flow(content: &str) -> FlowDocument {
+        from_yaml(content).unwrap()
+    }
+
+    fn compare(after: &str, ignore_positions: bool) -> FlowDiff {
+        diff_flows(Some(&flow(FLOW)), Some(&flow(after)), ignore_positions)
+    }
+
+    #[test]
+    fn identical_flows_have_no_changes() {
+        let diff = compare(FLOW, false);
+        assert!(!diff.has_changes);
+        assert!(diff.fields.is_empty() && diff.positions.is_empty());
+    }
+
+    #[test]
+    fn retyped_nodes_are_reported_with_both_types() {
+        let diff = compare(
+            &FLOW.replace(
+                "  - id: fail\n    type: terminal\n",
+                "  - id: fail\n    type: process\n",
+            ),
+            false,
+        );
+        let retyped: Vec<_> = diff
+            .nodes_retyped
+            .iter()
+            .map(|r| (r.id.as_str(), r.before.as_str(), r.after.as_str()))
+            .collect();
+        assert_eq!(retyped, vec![("fail", "terminal", "process")]);
+        assert!(diff.nodes_added.is_empty() && diff.nodes_removed.is_empty());
+        assert!(diff.has_changes);
+    }
+
+    #[test]
+    fn a_handle_pointing_elsewhere_is_one_rewiring() {
+        let after = FLOW.replace(
+            "{targetNodeId: fail, sourceHandle: 'false'}",
+            "{targetNodeId: done, sourceHandle: 'false'}",
+        );
+        let diff = compare(&after, false);
+        let changes: Vec<_> = diff
+            .connections
+            .iter()
+            .map(|c| {
+                (
+                    c.source.as_str(),
+                    c.source_handle.as_deref(),
+                    c.before.as_deref(),
+                    c.after.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            vec![("check", Some("false"), Some("fail"), Some("done"))]
+        );
+
+        // A second target on the same handle is an addition
+        let after = FLOW.replace(
+            "connections: [{targetNodeId: done}]",
+            "connections: [{targetNodeId: done}, {targetNodeId: fail}]",
+        );
+        let diff = compare(&after, false);
+        assert_eq!(diff.connections.len(), 1);
+        assert_eq!(diff.connections[0].before, None);
+        assert_eq!(diff.connections[0].after.as_deref(), Some("fail"));
+    }
+
+    #[test]
+    fn reordering_nodes_connections_and_keyed_lists_is_not_a_change() {
+        let after = FLOW
+            .replace(
+                "        - {name: email, type: string}\n        - {name: plan, type: string}\n",
+                "        - {name: plan, type: string}\n        - {name: email, type: string}\n",
+            )
+            .replace(
+                "      - {targetNodeId: charge, sourceHandle: 'true'}\n      - {targetNodeId: fail, sourceHandle: 'false'}\n",
+                "      - {targetNodeId: fail, sourceHandle: 'false'}\n      - {targetNodeId: charge, sourceHandle: 'true'}\n",
+            );
+        // Move `fail` before `done`
+        let (head, fail) = after.split_at(after.find("  - id: fail").unwrap());
+        let (head, done) = head.split_at(head.find("  - id: done").unwrap());
+        let reordered = format!("{}{}{}", head, fail, done);
+        let order: Vec<String> = flow(&reordered).nodes.into_iter().map(|n| n.id).collect();
+        assert_eq!(order, vec!["form", "check", "charge", "fail", "done"]);
+
+        let diff = compare(&reordered, false);
+        assert!(!diff.has_changes, "{:?}", diff);
+        assert!(diff.positions.is_empty());
+    }
+
+    #[test]
+    fn field_changes_are_located_by_node_and_path() {
+        let after = FLOW
+            .replace("max_attempts: 3", "max_attempts: 5")
+            .replace("{name: plan, type: string}", "{name: plan, type: number}");
+        let diff = compare(&after, false);
+        let fields: Vec<_> = diff
+            .fields
+            .iter()
+            .map(|f| (f.node.as_deref(), f.path.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (Some("form"), "nodes[id=form].spec.fields[name=plan].type"),
+                (Some("charge"), "nodes[id=charge].spec.retry.max_attempts"),
+            ]
+        );
+        assert_eq!(diff.fields[1].before, Some(Value::from(3)));
+        assert_eq!(diff.fields[1].after, Some(Value::from(5)));
+    }
+
+    #[test]
+    fn moved_nodes_only_show_up_as_positions() {
+        let after = FLOW.replace("position: {x: 200, y: 300}", "position: {x: 250, y: 320}");
+        let diff = compare(&after, false);
+        assert!(!diff.has_changes);
+        assert_eq!(diff.positions.len(), 1);
+        assert_eq!(diff.positions[0].id, "fail");
+        assert_eq!(diff.positions[0].after, Position { x: 250.0, y: 320.0 });
+
+        let diff = diff_flows(Some(&flow(FLOW)), Some(&flow(&after)), true);
+        assert!(!diff.has_changes);
+        assert!(diff.positions.is_empty());
+    }
+
+    #[test]
+    fn domain_wiring_matches_bare_and_spelled_out_events() {
+        let before: DomainConfig = from_yaml(
+            "\
+name: Billing
+publishes_events:
+  - PaymentFailed
+  - {event: InvoicePaid, from_flow: pay}
+layout:
+  flows:
+    pay: {x: 1, y: 2}
+",
+        )
+        .unwrap();
+        let after: DomainConfig = from_yaml(
+            "\
+name: Billing
+publishes_events:
+  - {event: InvoicePaid, from_flow: pay}
+  - {event: PaymentFailed}
+layout:
+  flows:
+    pay: {x: 5, y: 2}
+",
+        )
+        .unwrap();
+        let diff = diff_domains(Some(&before), Some(&after), false);
+        assert!(!diff.has_changes, "{:?}", diff.fields);
+        assert_eq!(diff.positions.len(), 1);
+        assert_eq!(diff.positions[0].id, "pay");
+        assert!(diff_domains(Some(&before), Some(&after), true)
+            .positions
+            .is_empty());
+
+        let diff = diff_domains(None, Some(&after), false);
+        assert!(diff.has_changes);
+        assert_eq!(diff.fields.len(), 1);
+        assert_eq!(diff.fields[0].path, "");
+        assert!(diff.fields[0].before.is_none());
+    }
+}
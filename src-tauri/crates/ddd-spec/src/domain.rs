@@ -0,0 +1,201 @@
+use super::{Extra, Position};
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `specs/domains/<domain>/domain.yaml`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct DomainConfig {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// `entity`, `process`, `interface` or `orchestration`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owns_schemas: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<FlowGroup>>,
+    #[serde(default)]
+    pub flows: Vec<DomainFlowEntry>,
+    #[serde(default)]
+    pub publishes_events: Vec<EventWiring>,
+    #[serde(default)]
+    pub consumes_events: Vec<EventWiring>,
+    #[serde(default)]
+    pub layout: DomainLayout,
+    /// Spec format version; absent in files older than version 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_version: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct DomainFlowEntry {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// `traditional` or `agent`.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub flow_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A published or consumed event. Older specs list events as bare names
+/// (`- UserRegistered`); those read with only `event` set and are written back as
+/// bare names as long as nothing else was added.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[serde(from = "EventWiringRepr", into = "EventWiringRepr")]
+pub struct EventWiring {
+    pub event: String,
+    pub schema: Option<String>,
+    pub from_flow: Option<String>,
+    pub handled_by_flow: Option<String>,
+    pub description: Option<String>,
+    pub payload: Option<Extra>,
+    pub extra: Extra,
+    /// Read from the bare-name form.
+    pub shorthand: bool,
+}
+
+impl EventWiring {
+    pub fn named(event: &str) -> Self {
+        EventWiring {
+            event: event.to_string(),
+            schema: None,
+            from_flow: None,
+            handled_by_flow: None,
+            description: None,
+            payload: None,
+            extra: Extra::new(),
+            shorthand: false,
+        }
+    }
+
+    fn is_bare(&self) -> bool {
+        self.schema.is_none()
+            && self.from_flow.is_none()
+            && self.handled_by_flow.is_none()
+            && self.description.is_none()
+            && self.payload.is_none()
+            && self.extra.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(untagged)]
+enum EventWiringRepr {
+    Name(String),
+    Full(Box<EventWiringFields>),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+struct EventWiringFields {
+    event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    from_flow: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    handled_by_flow: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    payload: Option<Extra>,
+    #[serde(flatten)]
+    extra: Extra,
+}
+
+impl From<EventWiringRepr> for EventWiring {
+    fn from(repr: EventWiringRepr) -> Self {
+        match repr {
+            EventWiringRepr::Name(event) => EventWiring {
+                shorthand: true,
+                ..EventWiring::named(&event)
+            },
+            EventWiringRepr::Full(f) => {
+                let f = *f;
+                EventWiring {
+                    event: f.event,
+                    schema: f.schema,
+                    from_flow: f.from_flow,
+                    handled_by_flow: f.handled_by_flow,
+                    description: f.description,
+                    payload: f.payload,
+                    extra: f.extra,
+                    shorthand: false,
+                }
+            }
+        }
+    }
+}
+
+impl From<EventWiring> for EventWiringRepr {
+    fn from(wiring: EventWiring) -> Self {
+        if wiring.shorthand && wiring.is_bare() {
+            return EventWiringRepr::Name(wiring.event);
+        }
+        EventWiringRepr::Full(Box::new(EventWiringFields {
+            event: wiring.event,
+            schema: wiring.schema,
+            from_flow: wiring.from_flow,
+            handled_by_flow: wiring.handled_by_flow,
+            description: wiring.description,
+            payload: wiring.payload,
+            extra: wiring.extra,
+        }))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct DomainLayout {
+    #[serde(default)]
+    pub flows: IndexMap<String, Position>,
+    #[serde(default)]
+    pub portals: IndexMap<String, Position>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct FlowGroup {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub flow_ids: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// `specs/system-layout.yaml`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct SystemLayout {
+    #[serde(default)]
+    pub domains: IndexMap<String, Position>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zones: Option<Vec<SystemZone>>,
+    /// Spec format version; absent in files older than version 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_version: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct SystemZone {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub domain_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
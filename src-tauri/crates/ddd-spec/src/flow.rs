@@ -0,0 +1,586 @@
+use super::nodes::*;
+use super::{number, Extra};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// `specs/domains/<domain>/flows/<flow>.yaml`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct FlowDocument {
+    pub flow: FlowHeader,
+    pub trigger: FlowNode,
+    #[serde(default)]
+    pub nodes: Vec<FlowNode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<FlowMetadata>,
+    /// Spec format version; absent in files older than version 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_version: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl FlowDocument {
+    /// The trigger followed by every other node.
+    pub fn all_nodes(&self) -> impl Iterator<Item = &FlowNode> {
+        std::iter::once(&self.trigger).chain(self.nodes.iter())
+    }
+
+    pub fn all_nodes_mut(&mut self) -> impl Iterator<Item = &mut FlowNode> {
+        std::iter::once(&mut self.trigger).chain(self.nodes.iter_mut())
+    }
+
+    pub fn node(&self, id: &str) -> Option<&FlowNode> {
+        self.all_nodes().find(|n| n.id == id)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct FlowHeader {
+    pub id: String,
+    pub name: String,
+    /// `traditional` or `agent`.
+    #[serde(rename = "type", default = "default_flow_type")]
+    pub flow_type: String,
+    #[serde(default)]
+    pub domain: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+fn default_flow_type() -> String {
+    "traditional".to_string()
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct FlowMetadata {
+    #[serde(default)]
+    pub created: String,
+    #[serde(default)]
+    pub modified: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct Position {
+    #[serde(serialize_with = "number")]
+    pub x: f64,
+    #[serde(serialize_with = "number")]
+    pub y: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Connection {
+    #[serde(rename = "targetNodeId")]
+    pub target_node_id: String,
+    #[serde(
+        rename = "sourceHandle",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub source_handle: Option<String>,
+    #[serde(
+        rename = "targetHandle",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub target_handle: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A persisted node (`DddFlowNode`). `spec` is typed according to `node_type`.
+#[derive(Clone, Debug)]
+pub struct FlowNode {
+    pub id: String,
+    pub node_type: NodeType,
+    pub position: Position,
+    pub connections: Vec<Connection>,
+    pub spec: NodeSpec,
+    pub label: String,
+    pub parent_id: Option<String>,
+    pub observability: Option<ObservabilityConfig>,
+    pub security: Option<SecurityConfig>,
+    pub extra: Extra,
+    /// Which of `position`, `connections`, `spec` and `label` the file left out.
+    pub omitted: OmittedKeys,
+}
+
+/// Keys of a node that were absent when it was read. They stay absent on output
+/// while they still hold their default, so a hand-written node isn't padded out.
+#[derive(Clone, Debug, Default)]
+pub struct OmittedKeys {
+    pub position: bool,
+    pub connections: bool,
+    pub spec: bool,
+    pub label: bool,
+}
+
+/// Wire shape of `FlowNode`; the spec can only be typed once `type` is known.
+#[derive(Deserialize, JsonSchema)]
+struct RawFlowNode {
+    id: String,
+    #[serde(rename = "type")]
+    node_type: NodeType,
+    #[serde(default)]
+    #[schemars(with = "Position")]
+    position: Option<Position>,
+    #[serde(default)]
+    #[schemars(with = "Vec<Connection>")]
+    connections: Option<Vec<Connection>>,
+    #[serde(default)]
+    spec: Value,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    label: Option<String>,
+    #[serde(rename = "parentId", default)]
+    parent_id: Option<String>,
+    #[serde(default)]
+    #[schemars(with = "Option<ObservabilityConfig>")]
+    observability: Value,
+    #[serde(default)]
+    #[schemars(with = "Option<SecurityConfig>")]
+    security: Value,
+    #[serde(flatten)]
+    extra: Extra,
+}
+
+/// What `FlowNode` serializes through, in the key order the frontend writes.
+#[derive(Serialize)]
+struct FlowNodeOut<'a> {
+    id: &'a str,
+    #[serde(rename = "type")]
+    node_type: &'a NodeType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<&'a Position>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connections: Option<&'a Vec<Connection>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spec: Option<&'a NodeSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<&'a str>,
+    #[serde(rename = "parentId", skip_serializing_if = "Option::is_none")]
+    parent_id: Option<&'a String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    observability: Option<&'a ObservabilityConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    security: Option<&'a SecurityConfig>,
+    #[serde(flatten)]
+    extra: &'a Extra,
+}
+
+impl Serialize for FlowNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let omitted = &self.omitted;
+        FlowNodeOut {
+            id: &self.id,
+            node_type: &self.node_type,
+            position: Some(&self.position)
+                .filter(|p| !omitted.position || **p != Position::default()),
+            connections: Some(&self.connections).filter(|c| !omitted.connections || !c.is_empty()),
+            spec: Some(&self.spec).filter(|s| {
+                !omitted.spec || s.to_value().as_object().is_none_or(|m| !m.is_empty())
+            }),
+            label: Some(self.label.as_str()).filter(|l| !omitted.label || !l.is_empty()),
+            parent_id: self.parent_id.as_ref(),
+            observability: self.observability.as_ref(),
+            security: self.security.as_ref(),
+            extra: &self.extra,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Type an optional block of a node. A block that doesn't match its type is kept
+/// verbatim in `extra` under its own key, so it is written back unchanged and
+/// validation can report it.
+fn typed_block<T: serde::de::DeserializeOwned>(
+    key: &str,
+    value: Value,
+    extra: &mut Extra,
+) -> Option<T> {
+    if value.is_null() {
+        return None;
+    }
+    match serde_json::from_value(value.clone()) {
+        Ok(typed) => Some(typed),
+        Err(_) => {
+            extra.insert(key.to_string(), value);
+            None
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FlowNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawFlowNode::deserialize(deserializer)?;
+        let spec_omitted = raw.spec.is_null();
+        // A missing or empty `spec:` reads as an empty spec.
+        let spec_value = match raw.spec {
+            Value::Null => Value::Object(Extra::new()),
+            other => other,
+        };
+        // A spec that doesn't match its node type is kept as is; validation reports it.
+        let spec = match NodeSpec::from_value(&raw.node_type, spec_value.clone()) {
+            Ok(spec) => spec,
+            Err(e) => match spec_value {
+                Value::Object(map) => NodeSpec::Other(map),
+                _ => {
+                    return Err(D::Error::custom(format!(
+                        "invalid spec for node {}: {}",
+                        raw.id, e
+                    )))
+                }
+            },
+        };
+        let mut extra = raw.extra;
+        let observability = typed_block("observability", raw.observability, &mut extra);
+        let security = typed_block("security", raw.security, &mut extra);
+        let omitted = OmittedKeys {
+            position: raw.position.is_none(),
+            connections: raw.connections.is_none(),
+            spec: spec_omitted,
+            label: raw.label.is_none(),
+        };
+        Ok(FlowNode {
+            id: raw.id,
+            node_type: raw.node_type,
+            position: raw.position.unwrap_or_default(),
+            connections: raw.connections.unwrap_or_default(),
+            spec,
+            label: raw.label.unwrap_or_default(),
+            parent_id: raw.parent_id,
+            observability,
+            security,
+            extra,
+            omitted,
+        })
+    }
+}
+
+macro_rules! node_types {
+    ($($variant:ident => $name:literal, $spec:ident;)*) => {
+        /// `DddNodeType`. Types this build doesn't know are kept as `Other`.
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        pub enum NodeType {
+            $($variant,)*
+            Other(String),
+        }
+
+        impl NodeType {
+            pub const ALL: &'static [NodeType] = &[$(NodeType::$variant,)*];
+
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(NodeType::$variant => $name,)*
+                    NodeType::Other(name) => name,
+                }
+            }
+
+            pub fn parse(name: &str) -> Self {
+                match name {
+                    $($name => NodeType::$variant,)*
+                    other => NodeType::Other(other.to_string()),
+                }
+            }
+        }
+
+        /// Spec of a node, one variant per node type. Serializes as the bare spec.
+        #[derive(Serialize, Clone, Debug)]
+        #[serde(untagged)]
+        pub enum NodeSpec {
+            $($variant($spec),)*
+            /// Spec of a node type this build doesn't know, kept verbatim.
+            Other(Extra),
+        }
+
+        impl NodeSpec {
+            pub fn from_value(node_type: &NodeType, value: Value) -> Result<Self, serde_json::Error> {
+                Ok(match node_type {
+                    $(NodeType::$variant => NodeSpec::$variant(serde_json::from_value(value)?),)*
+                    NodeType::Other(_) => NodeSpec::Other(serde_json::from_value(value)?),
+                })
+            }
+
+            /// The spec as a generic JSON value, e.g. for path-based lookups.
+            pub fn to_value(&self) -> Value {
+                serde_json::to_value(self).unwrap_or(Value::Null)
+            }
+
+            /// One `if type is X then spec is XSpec` clause per node type.
+            fn schemas_by_type(generator: &mut SchemaGenerator) -> Vec<Value> {
+                vec![$(json_schema!({
+                    "if": {
+                        "properties": { "type": { "const": $name } },
+                        "required": ["type"]
+                    },
+                    "then": {
+                        "properties": { "spec": generator.subschema_for::<Option<$spec>>() }
+                    }
+                })
+                .to_value(),)*]
+            }
+
+            /// Keys the spec struct doesn't model.
+            pub fn extra(&self) -> &Extra {
+                match self {
+                    $(NodeSpec::$variant(spec) => &spec.extra,)*
+                    NodeSpec::Other(extra) => extra,
+                }
+            }
+        }
+    };
+}
+
+node_types! {
+    Trigger => "trigger", TriggerSpec;
+    Input => "input", InputSpec;
+    Process => "process", ProcessSpec;
+    Decision => "decision", DecisionSpec;
+    Terminal => "terminal", TerminalSpec;
+    DataStore => "data_store", DataStoreSpec;
+    ServiceCall => "service_call", ServiceCallSpec;
+    Event => "event", EventNodeSpec;
+    Loop => "loop", LoopSpec;
+    Parallel => "parallel", ParallelSpec;
+    SubFlow => "sub_flow", SubFlowSpec;
+    LlmCall => "llm_call", LlmCallSpec;
+    Delay => "delay", DelaySpec;
+    Cache => "cache", CacheSpec;
+    Transform => "transform", TransformSpec;
+    Collection => "collection", CollectionSpec;
+    Parse => "parse", ParseSpec;
+    Crypto => "crypto", CryptoSpec;
+    Batch => "batch", BatchSpec;
+    Transaction => "transaction", TransactionSpec;
+    AgentLoop => "agent_loop", AgentLoopSpec;
+    Guardrail => "guardrail", GuardrailSpec;
+    HumanGate => "human_gate", HumanGateSpec;
+    Orchestrator => "orchestrator", OrchestratorSpec;
+    SmartRouter => "smart_router", SmartRouterSpec;
+    Handoff => "handoff", HandoffSpec;
+    AgentGroup => "agent_group", AgentGroupSpec;
+}
+
+impl std::fmt::Display for NodeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl JsonSchema for NodeType {
+    fn schema_name() -> Cow<'static, str> {
+        "NodeType".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let names: Vec<&str> = NodeType::ALL.iter().map(NodeType::as_str).collect();
+        json_schema!({ "type": "string", "enum": names })
+    }
+}
+
+impl JsonSchema for FlowNode {
+    fn schema_name() -> Cow<'static, str> {
+        "FlowNode".into()
+    }
+
+    /// The wire shape, with `spec` constrained by `type`.
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let mut schema = RawFlowNode::json_schema(generator);
+        schema.remove("description");
+        schema.insert(
+            "allOf".to_string(),
+            Value::Array(NodeSpec::schemas_by_type(generator)),
+        );
+        schema
+    }
+}
+
+impl Serialize for NodeType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(NodeType::parse(&name))
+    }
+}
+
+// --- Cross-cutting node config (src/types/crosscutting.ts) ---
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct ObservabilityConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logging: Option<LoggingConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<MetricsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracing: Option<TracingConfig>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct LoggingConfig {
+    /// `debug`, `info`, `warn` or `error`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_input: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_output: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct MetricsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_counters: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct TracingConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span_name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct SecurityConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authentication: Option<AuthenticationConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limiting: Option<RateLimitingConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit: Option<AuditConfig>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct AuthenticationConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub methods: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct RateLimitingConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "super::opt_number"
+    )]
+    pub requests_per_minute: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct EncryptionConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at_rest: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_transit: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pii_fields: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct AuditConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_yaml, to_yaml};
+
+    const MINIMAL: &str = "\
+flow:
+  id: ping
+  name: Ping
+trigger:
+  id: trigger-1
+  type: trigger
+nodes:
+- id: end
+  type: terminal
+";
+
+    #[test]
+    fn minimal_nodes_round_trip_without_added_keys() {
+        let flow: FlowDocument = from_yaml(MINIMAL).unwrap();
+        assert!(flow.trigger.connections.is_empty());
+        assert_eq!(flow.nodes[0].position, Position::default());
+
+        let written: Value = serde_yaml::from_str(&to_yaml(&flow).unwrap()).unwrap();
+        for node in [&written["trigger"], &written["nodes"][0]] {
+            let keys: Vec<&String> = node.as_object().unwrap().keys().collect();
+            assert_eq!(keys, vec!["id", "type"]);
+        }
+    }
+
+    #[test]
+    fn omitted_keys_are_written_once_they_are_set() {
+        let mut flow: FlowDocument = from_yaml(MINIMAL).unwrap();
+        flow.trigger.position = Position { x: 10.0, y: 20.0 };
+        flow.trigger.connections.push(Connection {
+            target_node_id: "end".to_string(),
+            source_handle: None,
+            target_handle: None,
+            extra: Extra::new(),
+        });
+        flow.nodes[0].label = "Done".to_string();
+
+        let written: Value = serde_yaml::from_str(&to_yaml(&flow).unwrap()).unwrap();
+        assert_eq!(written["trigger"]["position"]["x"], 10);
+        assert_eq!(written["trigger"]["connections"][0]["targetNodeId"], "end");
+        assert_eq!(written["nodes"][0]["label"], "Done");
+    }
+
+    #[test]
+    fn explicit_defaults_are_kept() {
+        let content = MINIMAL.replace(
+            "  type: terminal\n",
+            "  type: terminal\n  position:\n    x: 0\n    y: 0\n  connections: []\n  spec: {}\n  label: ''\n",
+        );
+        let flow: FlowDocument = from_yaml(&content).unwrap();
+        let written: Value = serde_yaml::from_str(&to_yaml(&flow).unwrap()).unwrap();
+        let keys: Vec<&String> = written["nodes"][0].as_object().unwrap().keys().collect();
+        assert_eq!(
+            keys,
+            vec!["id", "type", "position", "connections", "spec", "label"]
+        );
+    }
+}
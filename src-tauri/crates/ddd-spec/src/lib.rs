@@ -0,0 +1,146 @@
+//! Typed model of the YAML specs under `specs/`, mirroring `src/types/flow.ts` and
+//! `src/types/domain.ts`, plus `ddd-project.json`.
+//!
+//! Conventions shared by every type here:
+//! - Fields that are required in the TypeScript types are plain fields; optional ones
+//!   are `Option`s that are omitted again on output.
+//! - A node's `position`, `connections`, `spec` and `label` are plain fields, but
+//!   ones a file leaves out aren't written back while they still hold their default.
+//! - String unions (`'GET' | 'POST'`, `'block' | 'warn'`, …) stay `String`s so a bad
+//!   value is a validation issue rather than a parse failure.
+//! - A node spec, `observability` or `security` block with a mistyped value (a
+//!   `timeout: 30s` where a number belongs) is kept verbatim and reported by
+//!   validation instead of failing the whole file.
+//! - TypeScript `number`s are `f64` and whole numbers are written back without a
+//!   fraction, as the frontend's YAML writer does.
+//! - Every struct keeps keys it doesn't know in `extra`, so reading and writing a
+//!   document never drops data.
+
+pub mod diff;
+pub mod domain;
+pub mod flow;
+pub mod merge;
+pub mod migrate;
+pub mod nodes;
+pub mod patch;
+pub mod project;
+pub mod refactor;
+pub mod schema;
+
+pub use domain::{
+    DomainConfig, DomainFlowEntry, DomainLayout, EventWiring, FlowGroup, SystemLayout, SystemZone,
+};
+pub use flow::{
+    Connection, FlowDocument, FlowHeader, FlowMetadata, FlowNode, NodeSpec, NodeType,
+    ObservabilityConfig, Position, SecurityConfig,
+};
+pub use project::{ProjectConfig, ProjectDomain};
+
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+
+/// Keys not covered by a struct's fields, in document order.
+pub type Extra = serde_json::Map<String, serde_json::Value>;
+
+/// `Record<string, string>` in the TypeScript types.
+pub type StringMap = IndexMap<String, String>;
+
+/// Whole numbers below this magnitude are written as integers.
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+pub(crate) fn number<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    if value.fract() == 0.0 && value.abs() < MAX_EXACT_INTEGER {
+        serializer.serialize_i64(*value as i64)
+    } else {
+        serializer.serialize_f64(*value)
+    }
+}
+
+pub(crate) fn opt_number<S: Serializer>(
+    value: &Option<f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(v) => number(v, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Parse a spec document. Errors carry serde_yaml's line/column when available.
+pub fn from_yaml<T: DeserializeOwned>(content: &str) -> Result<T, serde_yaml::Error> {
+    serde_yaml::from_str(content)
+}
+
+pub fn to_yaml<T: Serialize>(value: &T) -> Result<String, serde_yaml::Error> {
+    serde_yaml::to_string(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// A file of `fixtures/sample-project`, what `src/utils/sample-project.ts` writes
+    /// as the `yaml` package formats it.
+    macro_rules! sample {
+        ($path:literal) => {
+            include_str!(concat!("../../../fixtures/sample-project/", $path))
+        };
+    }
+
+    const PROJECT_JSON: &str = sample!("ddd-project.json");
+    const BILLING_DOMAIN: &str = sample!("specs/domains/billing/domain.yaml");
+    const USER_REGISTER_FLOW: &str = sample!("specs/domains/users/flows/user-register.yaml");
+    const PAYMENT_FLOW: &str = sample!("specs/domains/billing/flows/payment-processing.yaml");
+    const SUPPORT_FLOW: &str = sample!("specs/domains/support/flows/support-ticket.yaml");
+
+    /// Parse `content` as `T`, write it back and check nothing was lost or changed.
+    fn assert_round_trip<T: DeserializeOwned + Serialize>(content: &str) -> T {
+        let original: Value = serde_yaml::from_str(content).unwrap();
+        let typed: T = from_yaml(content).unwrap();
+        assert_eq!(serde_json::to_value(&typed).unwrap(), original);
+        let written = to_yaml(&typed).unwrap();
+        let reread: Value = serde_yaml::from_str(&written).unwrap();
+        assert_eq!(reread, original);
+        typed
+    }
+
+    #[test]
+    fn sample_project_round_trips() {
+        let project: ProjectConfig = serde_json::from_str(PROJECT_JSON).unwrap();
+        let original: Value = serde_json::from_str(PROJECT_JSON).unwrap();
+        assert_eq!(serde_json::to_value(&project).unwrap(), original);
+        assert_eq!(project.domains[1].id(), "billing");
+
+        let domain: DomainConfig = assert_round_trip(BILLING_DOMAIN);
+        assert_eq!(domain.flows.len(), 2);
+
+        for content in [USER_REGISTER_FLOW, PAYMENT_FLOW, SUPPORT_FLOW] {
+            let flow: FlowDocument = assert_round_trip(content);
+            for node in flow.all_nodes() {
+                assert!(
+                    !matches!(node.spec, NodeSpec::Other(_)),
+                    "{} was not typed",
+                    node.id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mistyped_values_are_kept() {
+        let content = SUPPORT_FLOW
+            .replace("duration: 3600", "duration: 1h")
+            .replace(
+                "  - id: terminal-1\n",
+                "  - id: terminal-1\n    observability:\n      logging:\n        include_input: sometimes\n",
+            );
+        let flow: FlowDocument = assert_round_trip(&content);
+
+        let gate = flow.nodes.iter().find(|n| n.id == "human-gate-1").unwrap();
+        assert!(matches!(gate.spec, NodeSpec::Other(_)));
+        let terminal = flow.nodes.iter().find(|n| n.id == "terminal-1").unwrap();
+        assert!(terminal.observability.is_none());
+    }
+}
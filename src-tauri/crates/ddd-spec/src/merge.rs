@@ -0,0 +1,600 @@
+//! Three-way merge of flow and domain specs, for the merge command and the
+//! `ddd-merge-driver` git merge driver.
+//!
+//! The merge works on the documents' values rather than their lines. Sequence items
+//! are matched by `id` (or `event`, `name`), connections by target and handles, so
+//! both sides adding nodes or wiring doesn't conflict. Canvas positions and the
+//! `metadata.modified` stamp never conflict: positions keep ours, the stamp keeps
+//! the later one. What's left over are true conflicts, which keep ours and are
+//! reported. The merged document is written over ours with [`apply_patches`], so
+//! ours' formatting and comments survive.
+
+use super::diff::{push_key, ITEM_KEYS};
+use super::patch::{apply_patches, YamlPatch};
+use super::{DomainConfig, FlowDocument};
+use indexmap::{IndexMap, IndexSet};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeKind {
+    Flow,
+    Domain,
+}
+
+impl MergeKind {
+    /// From the file's path, which may be relative to a repository the project sits
+    /// inside: `…/domains/<domain>/domain.yaml` or `…/domains/<domain>/flows/<flow>.yaml`.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let parts: Vec<&str> = path
+            .components()
+            .map(|c| c.as_os_str().to_str().unwrap_or(""))
+            .collect();
+        match parts.as_slice() {
+            [.., "domains", _, "domain.yaml"] => Some(MergeKind::Domain),
+            [.., "domains", _, "flows", file]
+                if file.ends_with(".yaml") || file.ends_with(".yml") =>
+            {
+                Some(MergeKind::Flow)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    /// Ours was kept: a true conflict, or a position both sides moved.
+    Ours,
+    /// The later `metadata.modified` was kept.
+    Latest,
+    /// Dropped: a connection to a node the other side removed.
+    Dropped,
+    /// Put back: a connection to a node ours changed and theirs removed, so the kept
+    /// node isn't left unreachable.
+    Restored,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MergeConflict {
+    /// Patch-style path from the document root.
+    pub path: String,
+    pub message: String,
+    pub resolution: Resolution,
+    /// Absent where the value doesn't exist in that version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ours: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theirs: Option<Value>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MergeOutcome {
+    /// The merged document, based on ours' text.
+    pub content: String,
+    /// Changes both sides made differently. Each keeps ours in `content`.
+    pub conflicts: Vec<MergeConflict>,
+    /// Clashes settled without asking: positions, `metadata.modified`, connections
+    /// left pointing at a removed node and connections restored to a kept one.
+    pub auto_resolved: Vec<MergeConflict>,
+    /// No conflicts.
+    pub clean: bool,
+}
+
+struct Merger {
+    conflicts: Vec<MergeConflict>,
+    auto_resolved: Vec<MergeConflict>,
+    /// Ids of nodes kept because ours changed them while theirs removed them.
+    kept_nodes: Vec<String>,
+}
+
+/// Whether the value at `path` is a canvas position.
+fn is_position(path: &str) -> bool {
+    path.ends_with(".position")
+        || path == "position"
+        || path.starts_with("layout.flows.")
+        || path.starts_with("layout.portals.")
+}
+
+/// How the items of a sequence are matched across versions: by an identifying key
+/// of each item, or by position when they have none.
+fn item_ids(key: &str, items: &[Value]) -> Option<Vec<String>> {
+    let mut seen = IndexSet::new();
+    items
+        .iter()
+        .map(|item| {
+            let id = match item {
+                Value::Object(map) if map.contains_key("targetNodeId") => format!(
+                    "{}|{}|{}",
+                    map["targetNodeId"].as_str()?,
+                    map.get("sourceHandle")
+                        .and_then(Value::as_str)
+                        .unwrap_or(""),
+                    map.get("targetHandle")
+                        .and_then(Value::as_str)
+                        .unwrap_or("")
+                ),
+                Value::Object(map) => map.get(key)?.as_str()?.to_string(),
+                // Bare event names (`- UserRegistered`) and plain string lists.
+                Value::String(s) => s.clone(),
+                _ => return None,
+            };
+            seen.insert(id.clone()).then_some(id)
+        })
+        .collect()
+}
+
+/// Keyed items of every version present, if all of them can be keyed the same way.
+fn keyed(versions: [Option<&Value>; 3]) -> Option<[IndexMap<String, &Value>; 3]> {
+    let arrays: Vec<Option<&Vec<Value>>> = versions
+        .iter()
+        .map(|v| v.and_then(Value::as_array))
+        .collect();
+    ITEM_KEYS.into_iter().find_map(|key| {
+        let mut out: [IndexMap<String, &Value>; 3] = Default::default();
+        for (i, items) in arrays.iter().enumerate() {
+            let Some(items) = items else { continue };
+            let ids = item_ids(key, items)?;
+            out[i] = ids.into_iter().zip(items.iter()).collect();
+        }
+        Some(out)
+    })
+}
+
+impl Merger {
+    fn record(
+        &mut self,
+        path: &str,
+        message: String,
+        resolution: Resolution,
+        [base, ours, theirs]: [Option<&Value>; 3],
+    ) {
+        let conflict = MergeConflict {
+            path: path.to_string(),
+            message,
+            resolution,
+            base: base.cloned(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        };
+        match resolution {
+            Resolution::Ours if !is_position(path) => self.conflicts.push(conflict),
+            _ => self.auto_resolved.push(conflict),
+        }
+    }
+
+    /// The merged value at `path`, `None` when it ends up absent.
+    fn merge(
+        &mut self,
+        path: &str,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Option<Value> {
+        if ours == theirs || theirs == base {
+            return ours.cloned();
+        }
+        if ours == base {
+            return theirs.cloned();
+        }
+
+        if is_position(path) {
+            self.record(
+                path,
+                "Moved on both sides".to_string(),
+                Resolution::Ours,
+                [base, ours, theirs],
+            );
+            return ours.cloned();
+        }
+        if path == "metadata.modified" {
+            // ISO 8601 timestamps order as strings.
+            let latest = [ours, theirs]
+                .into_iter()
+                .flatten()
+                .max_by(|a, b| a.as_str().cmp(&b.as_str()));
+            self.record(
+                path,
+                "Modified on both sides".to_string(),
+                Resolution::Latest,
+                [base, ours, theirs],
+            );
+            return latest.cloned();
+        }
+
+        match (ours, theirs) {
+            (Some(Value::Object(o)), Some(Value::Object(t)))
+                if base.is_none_or(Value::is_object) =>
+            {
+                let empty = Map::new();
+                let b = base.and_then(Value::as_object).unwrap_or(&empty);
+                let keys: IndexSet<&String> = o.keys().chain(t.keys()).collect();
+                let mut merged = Map::new();
+                for key in keys {
+                    let child = push_key(path, key);
+                    if let Some(value) = self.merge(&child, b.get(key), o.get(key), t.get(key)) {
+                        merged.insert(key.clone(), value);
+                    }
+                }
+                return Some(Value::Object(merged));
+            }
+            (Some(Value::Array(_)), Some(Value::Array(_))) if base.is_none_or(Value::is_array) => {
+                if let Some([b, o, t]) = keyed([base, ours, theirs]) {
+                    return Some(self.merge_items(path, &b, &o, &t));
+                }
+            }
+            _ => {}
+        }
+
+        let message = match (ours, theirs) {
+            (None, _) => "Removed in ours, changed in theirs",
+            (_, None) => "Changed in ours, removed in theirs",
+            _ if base.is_none() => "Added differently on both sides",
+            _ => "Changed differently on both sides",
+        };
+        self.record(
+            path,
+            message.to_string(),
+            Resolution::Ours,
+            [base, ours, theirs],
+        );
+        ours.cloned()
+    }
+
+    /// Items in ours' order, followed by items only theirs added.
+    fn merge_items(
+        &mut self,
+        path: &str,
+        base: &IndexMap<String, &Value>,
+        ours: &IndexMap<String, &Value>,
+        theirs: &IndexMap<String, &Value>,
+    ) -> Value {
+        let ids: IndexSet<&String> = ours
+            .keys()
+            .chain(theirs.keys())
+            .chain(base.keys())
+            .collect();
+        let mut merged = Vec::new();
+        for id in ids {
+            let item = ours.get(id).or(theirs.get(id)).or(base.get(id)).copied();
+            let key = match item {
+                Some(Value::Object(map)) if map.contains_key("targetNodeId") => None,
+                Some(Value::Object(map)) => ITEM_KEYS
+                    .into_iter()
+                    .find(|k| map.get(*k).and_then(Value::as_str) == Some(id)),
+                _ => None,
+            };
+            let item_path = match key {
+                Some(key) => format!("{}[{}={}]", path, key, id),
+                None => format!("{}[{}]", path, id),
+            };
+            let (b, o, t) = (base.get(id), ours.get(id), theirs.get(id));
+            if path == "nodes" && b.is_some() && t.is_none() && o.is_some_and(|o| Some(o) != b) {
+                self.kept_nodes.push(id.clone());
+            }
+            if let Some(value) = self.merge(&item_path, b.copied(), o.copied(), t.copied()) {
+                merged.push(value);
+            }
+        }
+        Value::Array(merged)
+    }
+}
+
+/// The trigger or node with `id` in a flow document.
+fn node_mut<'a>(flow: &'a mut Value, id: &str) -> Option<&'a mut Value> {
+    if flow["trigger"]["id"].as_str() == Some(id) {
+        return flow.get_mut("trigger");
+    }
+    flow.get_mut("nodes")?
+        .as_array_mut()?
+        .iter_mut()
+        .find(|n| n["id"].as_str() == Some(id))
+}
+
+/// Put back ours' connections to nodes kept as "changed in ours, removed in theirs".
+/// Theirs removed the wiring along with the node, which would leave the kept node
+/// unreachable.
+fn restore_connections(merged: &mut Value, ours: &Value, merger: &mut Merger) {
+    let kept = std::mem::take(&mut merger.kept_nodes);
+    if kept.is_empty() {
+        return;
+    }
+    let sources =
+        std::iter::once(&ours["trigger"]).chain(ours["nodes"].as_array().into_iter().flatten());
+    for source in sources {
+        let Some(source_id) = source["id"].as_str() else {
+            continue;
+        };
+        let wanted: Vec<&Value> = source["connections"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|c| {
+                c["targetNodeId"]
+                    .as_str()
+                    .is_some_and(|t| kept.iter().any(|k| k == t))
+            })
+            .collect();
+        if wanted.is_empty() {
+            continue;
+        }
+        let Some(Value::Object(node)) = node_mut(merged, source_id) else {
+            continue;
+        };
+        let Value::Array(connections) = node
+            .entry("connections")
+            .or_insert_with(|| Value::Array(Vec::new()))
+        else {
+            continue;
+        };
+        for connection in wanted {
+            if connections.contains(connection) {
+                continue;
+            }
+            connections.push(connection.clone());
+            let path = if source_id == ours["trigger"]["id"].as_str().unwrap_or_default() {
+                "trigger.connections".to_string()
+            } else {
+                format!("nodes[id={}].connections", source_id)
+            };
+            merger.record(
+                &path,
+                format!(
+                    "Connection to {} kept with the node",
+                    connection["targetNodeId"].as_str().unwrap_or_default()
+                ),
+                Resolution::Restored,
+                [None, Some(connection), None],
+            );
+        }
+    }
+}
+
+/// Drop connections to nodes that no longer exist, which happens when one side
+/// removes a node the other side wired up.
+fn drop_dangling_connections(merged: &mut Value, merger: &mut Merger) {
+    let mut ids: IndexSet<String> = IndexSet::new();
+    let trigger_id = merged["trigger"]["id"].as_str().map(str::to_string);
+    ids.extend(trigger_id.clone());
+    if let Some(nodes) = merged["nodes"].as_array() {
+        ids.extend(
+            nodes
+                .iter()
+                .filter_map(|n| n["id"].as_str().map(str::to_string)),
+        );
+    }
+
+    let mut drop_from = |node: &mut Value, node_path: &str| {
+        let Some(connections) = node.get_mut("connections").and_then(Value::as_array_mut) else {
+            return;
+        };
+        connections.retain(|c| {
+            let Some(target) = c["targetNodeId"].as_str() else {
+                return true;
+            };
+            if ids.contains(target) {
+                return true;
+            }
+            merger.record(
+                &format!("{}.connections", node_path),
+                format!("Connection to removed node {}", target),
+                Resolution::Dropped,
+                [None, Some(c), None],
+            );
+            false
+        });
+    };
+    if let Some(trigger) = merged.get_mut("trigger") {
+        drop_from(trigger, "trigger");
+    }
+    if let Some(nodes) = merged.get_mut("nodes").and_then(Value::as_array_mut) {
+        for node in nodes {
+            let path = format!("nodes[id={}]", node["id"].as_str().unwrap_or_default());
+            drop_from(node, &path);
+        }
+    }
+}
+
+fn check<T: DeserializeOwned>(label: &str, content: &str) -> Result<Value, String> {
+    super::from_yaml::<T>(content).map_err(|e| format!("Failed to parse {}: {}", label, e))?;
+    super::from_yaml::<Value>(content).map_err(|e| format!("Failed to parse {}: {}", label, e))
+}
+
+fn parse(kind: MergeKind, label: &str, content: &str) -> Result<Value, String> {
+    match kind {
+        MergeKind::Flow => check::<FlowDocument>(label, content),
+        MergeKind::Domain => check::<DomainConfig>(label, content),
+    }
+}
+
+/// Merge `theirs` into `ours` given their common ancestor `base`. Fails when any
+/// version isn't a valid spec of `kind`.
+pub fn merge_specs(
+    kind: MergeKind,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+) -> Result<MergeOutcome, String> {
+    // A file both sides added has an empty base.
+    let base = match base.trim() {
+        "" => None,
+        _ => Some(parse(kind, "base", base)?),
+    };
+    let ours_value = parse(kind, "ours", ours)?;
+    let theirs_value = parse(kind, "theirs", theirs)?;
+
+    let mut merger = Merger {
+        conflicts: Vec::new(),
+        auto_resolved: Vec::new(),
+        kept_nodes: Vec::new(),
+    };
+    let mut merged = merger
+        .merge("", base.as_ref(), Some(&ours_value), Some(&theirs_value))
+        .unwrap_or(Value::Null);
+    if kind == MergeKind::Flow {
+        restore_connections(&mut merged, &ours_value, &mut merger);
+        drop_dangling_connections(&mut merged, &mut merger);
+    }
+
+    let content = apply_patches(
+        ours,
+        &[YamlPatch::Set {
+            path: String::new(),
+            value: merged,
+        }],
+    )?;
+    parse(kind, "merged result", &content)?;
+    Ok(MergeOutcome {
+        clean: merger.conflicts.is_empty(),
+        content,
+        conflicts: merger.conflicts,
+        auto_resolved: merger.auto_resolved,
+    })
+}
+
+/// A YAML comment listing `conflicts`, for the top of a merged file so whoever
+/// resolves the merge sees what was decided.
+pub fn conflict_header(conflicts: &[MergeConflict]) -> String {
+    let mut header = String::from(
+        "# Merge conflicts — ours was kept for each; review and remove this comment:\n",
+    );
+    for conflict in conflicts {
+        let show = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "(absent)".to_string(),
+        };
+        header.push_str(&format!(
+            "#   {}: {} (ours {}, theirs {})\n",
+            conflict.path,
+            conflict.message,
+            show(&conflict.ours),
+            show(&conflict.theirs)
+        ));
+    }
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "\
+flow:
+  id: checkout
+  name: Checkout
+  type: traditional
+  domain: orders
+trigger:
+  id: trigger-1
+  type: trigger
+  position:
+    x: 0
+    y: 0
+  connections:
+    - targetNodeId: process-1
+  spec:
+    event: POST /checkout
+nodes:
+  - id: process-1
+    type: process
+    position:
+      x: 0
+      y: 100
+    connections:
+      - targetNodeId: terminal-1
+    spec:
+      action: charge card
+  - id: terminal-1
+    type: terminal
+    position:
+      x: 0
+      y: 200
+    connections: []
+    spec:
+      outcome: done
+";
+
+    fn merge_flow(ours: &str, theirs: &str) -> (Value, MergeOutcome) {
+        let outcome = merge_specs(MergeKind::Flow, BASE, ours, theirs).unwrap();
+        let merged = serde_yaml::from_str(&outcome.content).unwrap();
+        (merged, outcome)
+    }
+
+    #[test]
+    fn separate_edits_merge_cleanly() {
+        let ours = BASE.replace("action: charge card", "action: charge card twice");
+        let theirs = BASE.replace("outcome: done", "outcome: paid");
+        let (merged, outcome) = merge_flow(&ours, &theirs);
+
+        assert!(outcome.clean);
+        assert!(outcome.auto_resolved.is_empty());
+        assert_eq!(merged["nodes"][0]["spec"]["action"], "charge card twice");
+        assert_eq!(merged["nodes"][1]["spec"]["outcome"], "paid");
+    }
+
+    #[test]
+    fn connections_to_a_node_theirs_removed_are_dropped() {
+        let ours = BASE.replace(
+            "  connections:\n    - targetNodeId: process-1\n",
+            "  connections:\n    - targetNodeId: process-1\n    - targetNodeId: terminal-1\n",
+        );
+        let theirs = BASE.replace(
+            "    connections:\n      - targetNodeId: terminal-1\n",
+            "    connections: []\n",
+        );
+        let theirs = theirs[..theirs.find("  - id: terminal-1").unwrap()].to_string();
+        let (merged, outcome) = merge_flow(&ours, &theirs);
+
+        assert!(outcome.clean);
+        assert_eq!(merged["nodes"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            merged["trigger"]["connections"],
+            serde_json::json!([{ "targetNodeId": "process-1" }])
+        );
+        assert!(outcome
+            .auto_resolved
+            .iter()
+            .any(|c| c.resolution == Resolution::Dropped));
+    }
+
+    #[test]
+    fn node_changed_in_ours_and_removed_in_theirs_stays_connected() {
+        let ours = BASE.replace("action: charge card", "action: charge card twice");
+        // Theirs removes process-1 and wires the trigger straight to the terminal.
+        let start = BASE.find("  - id: process-1").unwrap();
+        let end = BASE.find("  - id: terminal-1").unwrap();
+        let theirs = format!("{}{}", &BASE[..start], &BASE[end..]).replace(
+            "    - targetNodeId: process-1\n",
+            "    - targetNodeId: terminal-1\n",
+        );
+        let (merged, outcome) = merge_flow(&ours, &theirs);
+
+        assert!(!outcome.clean);
+        assert!(outcome
+            .conflicts
+            .iter()
+            .any(|c| c.message == "Changed in ours, removed in theirs"));
+        assert_eq!(merged["nodes"][0]["id"], "process-1");
+        assert_eq!(merged["nodes"][0]["spec"]["action"], "charge card twice");
+        assert_eq!(
+            merged["trigger"]["connections"],
+            serde_json::json!([
+                { "targetNodeId": "terminal-1" },
+                { "targetNodeId": "process-1" }
+            ])
+        );
+        let restored: Vec<&MergeConflict> = outcome
+            .auto_resolved
+            .iter()
+            .filter(|c| c.resolution == Resolution::Restored)
+            .collect();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].path, "trigger.connections");
+    }
+}
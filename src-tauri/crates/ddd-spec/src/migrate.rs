@@ -0,0 +1,401 @@
+//! `spec_version` and the steps that upgrade older documents to it.
+//!
+//! A document without `spec_version` is version 1. Each step rewrites the parsed
+//! document to the next version and describes what it changed; YAML files are then
+//! updated through [`apply_patches`], so comments and formatting of untouched parts
+//! survive. Documents from a newer version are refused rather than guessed at.
+
+use super::patch::{apply_patches, YamlPatch};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The version this build reads and writes.
+pub const SPEC_VERSION: u64 = 2;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentKind {
+    /// `ddd-project.json`
+    Project,
+    /// `specs/system-layout.yaml`
+    SystemLayout,
+    /// `specs/domains/<domain>/domain.yaml`
+    Domain,
+    /// `specs/domains/<domain>/flows/<flow>.yaml`
+    Flow,
+}
+
+struct Migration {
+    /// The version this step produces from the one before it.
+    to: u64,
+    /// Upgrade the document in place, noting each change.
+    apply: fn(DocumentKind, &mut Value, &mut Vec<String>),
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    apply: to_v2,
+}];
+
+/// The result of upgrading one document.
+#[derive(Serialize, Clone, Debug)]
+pub struct Migrated {
+    pub from: u64,
+    pub to: u64,
+    /// What each step changed besides the version stamp.
+    pub changes: Vec<String>,
+    /// The upgraded file contents.
+    pub content: String,
+}
+
+/// `spec_version` of a parsed document; 1 when absent.
+pub fn document_version(doc: &Value) -> Result<u64, String> {
+    match doc.get("spec_version") {
+        None | Some(Value::Null) => Ok(1),
+        Some(v) => v
+            .as_u64()
+            .filter(|&v| v >= 1)
+            .ok_or_else(|| format!("spec_version must be a positive integer, found {}", v)),
+    }
+}
+
+/// Upgrade a document to [`SPEC_VERSION`]. `None` when it is already current.
+pub fn migrate(kind: DocumentKind, content: &str) -> Result<Option<Migrated>, String> {
+    let mut doc: Value = match kind {
+        DocumentKind::Project => serde_json::from_str(content).map_err(|e| e.to_string())?,
+        _ => serde_yaml::from_str(content).map_err(|e| e.to_string())?,
+    };
+    let from = document_version(&doc)?;
+    if from > SPEC_VERSION {
+        return Err(format!(
+            "spec_version {} is newer than this version of DDD Tool supports ({})",
+            from, SPEC_VERSION
+        ));
+    }
+    if from == SPEC_VERSION {
+        return Ok(None);
+    }
+    if !doc.is_object() {
+        return Err("Document is not a mapping".to_string());
+    }
+    let mut changes = Vec::new();
+    for step in MIGRATIONS.iter().filter(|m| m.to > from) {
+        (step.apply)(kind, &mut doc, &mut changes);
+        if let Value::Object(map) = &mut doc {
+            map.insert("spec_version".to_string(), step.to.into());
+        }
+    }
+
+    let content = match kind {
+        DocumentKind::Project => {
+            let mut json = serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())?;
+            if content.ends_with('\n') {
+                json.push('\n');
+            }
+            json
+        }
+        _ => apply_patches(
+            content,
+            &[YamlPatch::Set {
+                path: String::new(),
+                value: doc,
+            }],
+        )?,
+    };
+    Ok(Some(Migrated {
+        from,
+        to: SPEC_VERSION,
+        changes,
+        content,
+    }))
+}
+
+// --- 1 → 2 ---
+
+/// Request tuning keys that sat directly in a service_call spec before they were
+/// grouped under `request_config`.
+const REQUEST_CONFIG_KEYS: &[&str] = &[
+    "user_agent",
+    "delay",
+    "cookie_jar",
+    "proxy",
+    "tls_fingerprint",
+    "fallback",
+];
+
+/// Before version 2, agent tools, orchestrator agents and agent group members could
+/// be bare names, service_call request tuning was flat, and agent flows didn't
+/// always declare `flow.type`.
+fn to_v2(kind: DocumentKind, doc: &mut Value, changes: &mut Vec<String>) {
+    if kind != DocumentKind::Flow {
+        return;
+    }
+    let mut has_agent_loop = false;
+    if let Some(trigger) = doc.get_mut("trigger") {
+        has_agent_loop |= node_to_v2(trigger, changes);
+    }
+    if let Some(nodes) = doc.get_mut("nodes").and_then(Value::as_array_mut) {
+        for node in nodes {
+            has_agent_loop |= node_to_v2(node, changes);
+        }
+    }
+
+    if has_agent_loop {
+        if let Some(header) = doc.get_mut("flow").and_then(Value::as_object_mut) {
+            if !header.contains_key("type") {
+                header.insert("type".to_string(), "agent".into());
+                changes.push("flow.type set to agent".to_string());
+            }
+        }
+    }
+}
+
+/// Upgrade one node's spec. Returns whether it is an agent loop.
+fn node_to_v2(node: &mut Value, changes: &mut Vec<String>) -> bool {
+    let id = node
+        .get("id")
+        .and_then(Value::as_str)
+        .unwrap_or("?")
+        .to_string();
+    let node_type = node
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_string();
+    let Some(spec) = node.get_mut("spec").and_then(Value::as_object_mut) else {
+        return node_type == "agent_loop";
+    };
+    let expansion = match node_type.as_str() {
+        "agent_loop" => Some((
+            "tools",
+            &["id", "name"][..],
+            "tool names to tool definitions",
+        )),
+        "orchestrator" => Some((
+            "agents",
+            &["id", "flow"][..],
+            "agent names to agent entries",
+        )),
+        "agent_group" => Some(("members", &["flow"][..], "member names to member entries")),
+        _ => None,
+    };
+    if let Some((key, fields, what)) = expansion {
+        if expand_names(spec, key, fields) {
+            changes.push(format!("node {}: expanded {}", id, what));
+        }
+    }
+    if node_type == "service_call" {
+        let moved = nest_request_config(spec);
+        if !moved.is_empty() {
+            changes.push(format!(
+                "node {}: moved {} into request_config",
+                id,
+                moved.join(", ")
+            ));
+        }
+    }
+    node_type == "agent_loop"
+}
+
+/// Move flat request tuning keys under `request_config`, keeping any value already
+/// there. Returns the keys moved.
+fn nest_request_config(spec: &mut Map<String, Value>) -> Vec<String> {
+    let moved: Vec<String> = REQUEST_CONFIG_KEYS
+        .iter()
+        .filter(|k| spec.contains_key(**k))
+        .map(|k| k.to_string())
+        .collect();
+    if moved.is_empty() {
+        return moved;
+    }
+    let mut config = match spec.remove("request_config") {
+        Some(Value::Object(config)) => config,
+        _ => Map::new(),
+    };
+    for key in &moved {
+        if let Some(value) = spec.remove(key) {
+            config.entry(key.clone()).or_insert(value);
+        }
+    }
+    spec.insert("request_config".to_string(), Value::Object(config));
+    moved
+}
+
+/// Replace bare strings in the sequence `spec[key]` with mappings that set each of
+/// `fields` to the string. Returns whether anything changed.
+fn expand_names(spec: &mut Map<String, Value>, key: &str, fields: &[&str]) -> bool {
+    let Some(items) = spec.get_mut(key).and_then(Value::as_array_mut) else {
+        return false;
+    };
+    let mut changed = false;
+    for item in items {
+        if let Value::String(name) = item {
+            let entry: Map<String, Value> = fields
+                .iter()
+                .map(|f| (f.to_string(), Value::String(name.clone())))
+                .collect();
+            *item = Value::Object(entry);
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_yaml, FlowDocument, NodeSpec};
+
+    /// A flow as version 1 wrote it: no `spec_version` or `flow.type`, bare tool,
+    /// agent and member names and flat request tuning keys.
+    const V1_FLOW: &str = "\
+# Support triage, hand-written
+flow:
+  id: triage
+  name: Triage
+  domain: support
+trigger:
+  id: trigger-1
+  type: trigger
+  position: { x: 0, y: 0 }
+  connections:
+    - targetNodeId: agent-1
+  spec:
+    event: TicketOpened
+nodes:
+  - id: agent-1
+    type: agent_loop # the main loop
+    position: { x: 0, y: 100 }
+    connections:
+      - targetNodeId: call-1
+    spec:
+      model: claude-sonnet
+      tools:
+        - lookup_order
+        - id: refund
+          name: issue_refund
+  - id: call-1
+    type: service_call
+    position: { x: 0, y: 200 }
+    connections:
+      - targetNodeId: orchestrator-1
+    spec:
+      method: GET
+      url: https://example.com/orders
+      user_agent: triage-bot # keep the bot identifiable
+      delay:
+        min_ms: 100
+        max_ms: 500
+      request_config:
+        proxy: http://proxy:8080
+  - id: orchestrator-1
+    type: orchestrator
+    position: { x: 0, y: 300 }
+    connections:
+      - targetNodeId: group-1
+    spec:
+      agents:
+        - billing-agent
+  - id: group-1
+    type: agent_group
+    position: { x: 0, y: 400 }
+    connections: []
+    spec:
+      members:
+        - shipping-agent
+";
+
+    fn migrate_flow(content: &str) -> Migrated {
+        migrate(DocumentKind::Flow, content).unwrap().unwrap()
+    }
+
+    #[test]
+    fn v1_flow_is_upgraded_and_loads_typed() {
+        let migrated = migrate_flow(V1_FLOW);
+        assert_eq!((migrated.from, migrated.to), (1, SPEC_VERSION));
+        assert_eq!(
+            migrated.changes,
+            [
+                "node agent-1: expanded tool names to tool definitions",
+                "node call-1: moved user_agent, delay into request_config",
+                "node orchestrator-1: expanded agent names to agent entries",
+                "node group-1: expanded member names to member entries",
+                "flow.type set to agent",
+            ]
+        );
+
+        let flow: FlowDocument = from_yaml(&migrated.content).unwrap();
+        assert_eq!(flow.spec_version, Some(SPEC_VERSION));
+        assert_eq!(flow.flow.flow_type, "agent");
+        for node in flow.all_nodes() {
+            assert!(
+                !matches!(node.spec, NodeSpec::Other(_)),
+                "{} was not typed",
+                node.id
+            );
+        }
+
+        let doc: Value = serde_yaml::from_str(&migrated.content).unwrap();
+        let tools = &doc["nodes"][0]["spec"]["tools"];
+        assert_eq!(
+            tools[0],
+            serde_json::json!({ "id": "lookup_order", "name": "lookup_order" })
+        );
+        assert_eq!(tools[1]["name"], "issue_refund");
+        let call = &doc["nodes"][1]["spec"];
+        assert_eq!(call.get("user_agent"), None);
+        assert_eq!(
+            call["request_config"],
+            serde_json::json!({
+                "proxy": "http://proxy:8080",
+                "user_agent": "triage-bot",
+                "delay": { "min_ms": 100, "max_ms": 500 },
+            })
+        );
+        assert_eq!(
+            doc["nodes"][2]["spec"]["agents"],
+            serde_json::json!([{ "id": "billing-agent", "flow": "billing-agent" }])
+        );
+        assert_eq!(
+            doc["nodes"][3]["spec"]["members"],
+            serde_json::json!([{ "flow": "shipping-agent" }])
+        );
+    }
+
+    #[test]
+    fn comments_survive_the_migration() {
+        let content = migrate_flow(V1_FLOW).content;
+        assert!(content.starts_with("# Support triage, hand-written\n"));
+        assert!(content.contains("type: agent_loop # the main loop"));
+    }
+
+    #[test]
+    fn current_documents_are_left_alone() {
+        let current = format!("{}spec_version: {}\n", V1_FLOW, SPEC_VERSION);
+        assert!(migrate(DocumentKind::Flow, &current).unwrap().is_none());
+        let project = format!("{{\"name\": \"shop\", \"spec_version\": {}}}", SPEC_VERSION);
+        assert!(migrate(DocumentKind::Project, &project).unwrap().is_none());
+    }
+
+    #[test]
+    fn newer_documents_are_refused() {
+        let newer = format!("{}spec_version: {}\n", V1_FLOW, SPEC_VERSION + 1);
+        let err = migrate(DocumentKind::Flow, &newer).unwrap_err();
+        assert!(err.contains("is newer than this version of DDD Tool supports"));
+
+        let err = migrate(DocumentKind::Domain, "name: Billing\nspec_version: 0\n").unwrap_err();
+        assert!(err.contains("spec_version must be a positive integer"));
+    }
+
+    #[test]
+    fn project_json_keeps_its_trailing_newline() {
+        let migrated = migrate(DocumentKind::Project, "{\"name\": \"shop\"}\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            migrated.content,
+            "{\n  \"name\": \"shop\",\n  \"spec_version\": 2\n}\n"
+        );
+        assert!(migrated.changes.is_empty());
+    }
+}
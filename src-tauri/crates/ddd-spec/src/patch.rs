@@ -0,0 +1,1416 @@
+//! Targeted edits to YAML spec files that leave the rest of the text alone.
+//!
+//! The document is parsed into a tree of source spans; each edit is a splice of the
+//! original text, so comments, key order, anchors, quoting and line wrapping outside
+//! the edited values survive. New keys are appended after a mapping's last entry and
+//! use the document's own indentation.
+
+use saphyr_parser::{Event, Parser, ScalarStyle};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// One edit. Paths are dotted keys with `[n]` for a sequence index and
+/// `[field=value]` for the sequence item whose `field` equals `value`, e.g.
+/// `nodes[id=process-1].spec.url`. Keys containing `.` or `[` can be written as
+/// `["a.b"]`. The empty path is the document root.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum YamlPatch {
+    /// Set the value at `path`, creating missing mappings along the way. Mappings and
+    /// sequences are reconciled with the existing value entry by entry, so unchanged
+    /// parts keep their formatting.
+    Set { path: String, value: Value },
+    /// Remove a key or sequence item. Missing paths are a no-op.
+    Remove { path: String },
+    /// Append to the sequence at `path`, creating it if missing.
+    Append { path: String, value: Value },
+    /// Rename the mapping key at `path` to `to`, keeping its value and position.
+    RenameKey { path: String, to: String },
+}
+
+/// Apply `patches` in order and return the new document text.
+pub fn apply_patches(content: &str, patches: &[YamlPatch]) -> Result<String, String> {
+    let mut text = content.to_string();
+    for (i, patch) in patches.iter().enumerate() {
+        text = apply_patch(&text, patch).map_err(|e| format!("Patch {} failed: {}", i, e))?;
+    }
+    // Every splice is local, but a bad one must never reach disk.
+    serde_yaml::from_str::<serde_yaml::Value>(&text)
+        .map_err(|e| format!("Patched document is not valid YAML: {}", e))?;
+    Ok(text)
+}
+
+/// 1-based line and column of the value at a JSON pointer (`/nodes/2/spec`), or of
+/// the deepest part of it that exists. A value under a mapping key is located at its
+/// key. `None` if the document doesn't parse or is empty.
+pub fn locate(content: &str, pointer: &str) -> Option<(usize, usize)> {
+    let doc = Document::parse(content).ok()?;
+    let mut node = doc.root.as_ref()?;
+    let mut pos = node.start;
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        let next = match &node.kind {
+            Kind::Mapping { entries, .. } => entries
+                .iter()
+                .find(|(k, _)| k.key() == Some(token.as_str()))
+                .map(|(k, v)| (k.start, v)),
+            Kind::Sequence { items, .. } => token
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| items.get(i))
+                .map(|v| (v.start, v)),
+            _ => None,
+        };
+        let Some((at, child)) = next else {
+            break;
+        };
+        pos = at;
+        node = child;
+    }
+    let line = content[..pos].matches('\n').count() + 1;
+    Some((line, column(content, pos) + 1))
+}
+
+fn apply_patch(text: &str, patch: &YamlPatch) -> Result<String, String> {
+    match patch {
+        YamlPatch::Set { path, value } => {
+            let path = parse_path(path)?;
+            let mut text = text.to_string();
+            for edit in plan_set(&text, &path, value)? {
+                text = apply_edit(&text, &edit)?;
+            }
+            Ok(text)
+        }
+        YamlPatch::Remove { path } => {
+            let path = parse_path(path)?;
+            if path.is_empty() {
+                return Err("Cannot remove the document root".to_string());
+            }
+            let doc = Document::parse(text)?;
+            let (concrete, found) = concrete_path(&doc, &path)?;
+            if found < path.len() {
+                return Ok(text.to_string());
+            }
+            apply_edit(text, &Edit::Remove(concrete))
+        }
+        YamlPatch::Append { path, value } => {
+            let path = parse_path(path)?;
+            let doc = Document::parse(text)?;
+            let (concrete, found) = concrete_path(&doc, &path)?;
+            if found < path.len() {
+                let mut text = text.to_string();
+                for edit in plan_set(&text, &path, &Value::Array(vec![value.clone()]))? {
+                    text = apply_edit(&text, &edit)?;
+                }
+                return Ok(text);
+            }
+            let node = doc.get(&concrete)?;
+            match &node.kind {
+                Kind::Sequence { .. } => apply_edit(text, &Edit::Append(concrete, value.clone())),
+                Kind::Scalar { .. } if node.is_empty_scalar() => apply_edit(
+                    text,
+                    &Edit::Replace(concrete, Value::Array(vec![value.clone()])),
+                ),
+                _ => Err(format!("{} is not a sequence", display_path(&path))),
+            }
+        }
+        YamlPatch::RenameKey { path, to } => {
+            let path = parse_path(path)?;
+            let doc = Document::parse(text)?;
+            let (concrete, found) = concrete_path(&doc, &path)?;
+            if found < path.len() {
+                return Err(format!("{} not found", display_path(&path)));
+            }
+            let Some((Segment::Key(from), parent_path)) = concrete.split_last() else {
+                return Err(format!("{} is not a mapping key", display_path(&path)));
+            };
+            let parent = doc.get(parent_path)?;
+            let Kind::Mapping { entries, .. } = &parent.kind else {
+                return Err(format!("{} is not a mapping", display_path(parent_path)));
+            };
+            if from != to && entries.iter().any(|(k, _)| k.key() == Some(to.as_str())) {
+                return Err(format!(
+                    "{} already has a key {}",
+                    display_path(parent_path),
+                    to
+                ));
+            }
+            let key = entries
+                .iter()
+                .map(|(k, _)| k)
+                .find(|k| k.key() == Some(from.as_str()))
+                .ok_or_else(|| format!("{} not found", display_path(&path)))?;
+            // Keep the key's quoting: `'old': 1` becomes `'new': 1`.
+            let rendered = match key.kind {
+                Kind::Scalar {
+                    style: style @ (ScalarStyle::SingleQuoted | ScalarStyle::DoubleQuoted),
+                    ..
+                } => doc.render().scalar(&Value::String(to.clone()), Some(style)),
+                _ if parent.is_flow() => flow_string(to),
+                _ => plain_string(to),
+            };
+            Ok(splice(text, key.start, key.end, &rendered))
+        }
+    }
+}
+
+// ---- Paths -------------------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Match { key: String, value: String },
+}
+
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    if s.len() >= 2
+        && ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')))
+    {
+        &s[1..s.len() - 1]
+    } else {
+        s
+    }
+}
+
+fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut rest = path.trim();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let close = after
+                .find(']')
+                .ok_or_else(|| format!("Unclosed '[' in path {}", path))?;
+            let inner = after[..close].trim();
+            let quoted = inner.starts_with('"') || inner.starts_with('\'');
+            segments.push(if quoted {
+                Segment::Key(unquote(inner).to_string())
+            } else if let Some((key, value)) = inner.split_once('=') {
+                Segment::Match {
+                    key: key.trim().to_string(),
+                    value: unquote(value).to_string(),
+                }
+            } else {
+                Segment::Index(
+                    inner
+                        .parse()
+                        .map_err(|_| format!("Invalid index [{}] in path {}", inner, path))?,
+                )
+            });
+            rest = &after[close + 1..];
+        } else {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("Empty key in path {}", path));
+            }
+            segments.push(Segment::Key(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        if let Some(after) = rest.strip_prefix('.') {
+            rest = after;
+            if rest.is_empty() || rest.starts_with('.') {
+                return Err(format!("Empty key in path {}", path));
+            }
+        }
+    }
+    Ok(segments)
+}
+
+fn display_path(path: &[Segment]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                out.push_str(key);
+            }
+            Segment::Index(i) => out.push_str(&format!("[{}]", i)),
+            Segment::Match { key, value } => out.push_str(&format!("[{}={}]", key, value)),
+        }
+    }
+    if out.is_empty() {
+        "(root)".to_string()
+    } else {
+        out
+    }
+}
+
+// ---- Source tree -------------------------------------------------------------
+
+/// A node with its byte span in the source. `head` is where the value's slot begins:
+/// just after the `:` of its key or the `-` of its sequence item. `props` holds an
+/// anchor or tag written before the value, kept when the value is replaced.
+#[derive(Debug)]
+struct Node {
+    start: usize,
+    end: usize,
+    head: usize,
+    props: String,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Scalar {
+        value: String,
+        style: ScalarStyle,
+        tagged: bool,
+    },
+    Alias,
+    Mapping {
+        flow: bool,
+        entries: Vec<(Node, Node)>,
+    },
+    Sequence {
+        flow: bool,
+        items: Vec<Node>,
+        /// Position of each item's `-` in a block sequence.
+        dashes: Vec<usize>,
+    },
+}
+
+impl Node {
+    fn is_flow(&self) -> bool {
+        matches!(
+            self.kind,
+            Kind::Mapping { flow: true, .. } | Kind::Sequence { flow: true, .. }
+        )
+    }
+
+    fn is_empty_scalar(&self) -> bool {
+        matches!(self.kind, Kind::Scalar { .. }) && self.start == self.end
+    }
+
+    fn key(&self) -> Option<&str> {
+        match &self.kind {
+            Kind::Scalar { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    fn child(&self, segment: &Segment) -> Option<&Node> {
+        match (&self.kind, segment) {
+            (Kind::Mapping { entries, .. }, Segment::Key(key)) => entries
+                .iter()
+                .find(|(k, _)| k.key() == Some(key))
+                .map(|(_, v)| v),
+            (Kind::Sequence { items, .. }, Segment::Index(i)) => items.get(*i),
+            (Kind::Sequence { items, .. }, Segment::Match { key, value }) => {
+                items.iter().find(|item| {
+                    item.child(&Segment::Key(key.clone())).and_then(|v| v.key())
+                        == Some(value.as_str())
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The scalar's value as JSON, resolved like the YAML core schema.
+    fn scalar_value(&self) -> Option<Value> {
+        let Kind::Scalar {
+            value,
+            style,
+            tagged,
+        } = &self.kind
+        else {
+            return None;
+        };
+        if *style != ScalarStyle::Plain || *tagged {
+            return Some(Value::String(value.clone()));
+        }
+        if self.start == self.end {
+            return Some(Value::Null);
+        }
+        Some(serde_yaml::from_str(value).unwrap_or_else(|_| Value::String(value.clone())))
+    }
+}
+
+struct Document<'a> {
+    text: &'a str,
+    root: Option<Node>,
+    /// Spaces per nesting level of block mappings.
+    indent: usize,
+    /// Column of a block sequence's `-` relative to its parent key.
+    seq_indent: usize,
+}
+
+fn skip_blank_and_comments(text: &str, mut pos: usize) -> usize {
+    let bytes = text.as_bytes();
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b' ' | b'\t' | b'\r' | b'\n' => pos += 1,
+            b'#' => {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            }
+            _ => break,
+        }
+    }
+    pos
+}
+
+/// The `-` introducing the block sequence item that starts at `pos`, stepping back
+/// over blanks and any anchor or tag.
+fn dash_before(text: &str, pos: usize) -> usize {
+    let bytes = text.as_bytes();
+    let mut pos = pos;
+    loop {
+        while pos > 0 && bytes[pos - 1].is_ascii_whitespace() {
+            pos -= 1;
+        }
+        if pos == 0 {
+            return 0;
+        }
+        let token_end = pos;
+        while pos > 0 && !bytes[pos - 1].is_ascii_whitespace() {
+            pos -= 1;
+        }
+        if &text[pos..token_end] == "-" {
+            return pos;
+        }
+    }
+}
+
+/// Anchors and tags between `head` and the value: `&anchor`, `!tag`.
+fn read_props(text: &str, head: usize) -> String {
+    let bytes = text.as_bytes();
+    let mut pos = head;
+    let mut props = Vec::new();
+    loop {
+        while pos < bytes.len() && (bytes[pos] == b' ' || bytes[pos] == b'\t') {
+            pos += 1;
+        }
+        if pos >= bytes.len() || (bytes[pos] != b'&' && bytes[pos] != b'!') {
+            break;
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        props.push(&text[start..pos]);
+    }
+    props.join(" ")
+}
+
+fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0)
+}
+
+/// End of the line containing `pos`, before its `\n` or `\r\n`.
+fn line_end(text: &str, pos: usize) -> usize {
+    match text[pos..].find('\n') {
+        Some(i) if text[..pos + i].ends_with('\r') => pos + i - 1,
+        Some(i) => pos + i,
+        None => text.len(),
+    }
+}
+
+/// The document's line break, taken from its first line.
+fn line_break(text: &str) -> &'static str {
+    match text.find('\n') {
+        Some(i) if text[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
+    }
+}
+
+fn column(text: &str, pos: usize) -> usize {
+    text[line_start(text, pos)..pos].chars().count()
+}
+
+struct TreeBuilder<'a> {
+    text: &'a str,
+    events: Vec<(Event<'a>, usize, usize)>,
+    next: usize,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn event(&mut self) -> Result<&(Event<'a>, usize, usize), String> {
+        let event = self
+            .events
+            .get(self.next)
+            .ok_or_else(|| "Unexpected end of document".to_string())?;
+        self.next += 1;
+        Ok(event)
+    }
+
+    fn peek_end(&self, end: fn(&Event) -> bool) -> bool {
+        self.events.get(self.next).is_some_and(|(e, _, _)| end(e))
+    }
+
+    fn node(&mut self) -> Result<Node, String> {
+        let text = self.text;
+        let (event, start, end) = self.event()?;
+        let (start, end) = (*start, *end);
+        let node = |kind, start, end| Node {
+            start,
+            end,
+            head: start,
+            props: String::new(),
+            kind,
+        };
+        match event {
+            Event::Scalar(value, style, _, tag) => {
+                let end = match style {
+                    // Block scalars end where the next token starts; drop the trailing
+                    // blank lines and indentation.
+                    ScalarStyle::Literal | ScalarStyle::Folded => {
+                        start + text[start..end].trim_end().len()
+                    }
+                    _ => end,
+                };
+                Ok(node(
+                    Kind::Scalar {
+                        value: value.to_string(),
+                        style: *style,
+                        tagged: tag.is_some(),
+                    },
+                    start,
+                    end,
+                ))
+            }
+            Event::Alias(_) => Ok(node(Kind::Alias, start, end)),
+            Event::SequenceStart(..) => {
+                let flow = text[start..].starts_with('[');
+                let mut items = Vec::new();
+                let mut dashes = Vec::new();
+                while !self.peek_end(|e| matches!(e, Event::SequenceEnd)) {
+                    let next_dash = items
+                        .last()
+                        .map(|prev| skip_blank_and_comments(text, prev_end(prev)));
+                    let mut item = self.node()?;
+                    let dash = next_dash.unwrap_or_else(|| dash_before(text, item.start));
+                    if !flow {
+                        item.head = dash + 1;
+                        item.props = read_props(text, dash + 1);
+                        if item.is_empty_scalar() {
+                            item.start = dash + 1;
+                            item.end = dash + 1;
+                        }
+                        dashes.push(dash);
+                    }
+                    items.push(item);
+                }
+                let (_, _, close_end) = self.event()?;
+                let end = if flow {
+                    *close_end
+                } else {
+                    items.last().map(prev_end).unwrap_or(start)
+                };
+                Ok(node(
+                    Kind::Sequence {
+                        flow,
+                        items,
+                        dashes,
+                    },
+                    start,
+                    end,
+                ))
+            }
+            Event::MappingStart(..) => {
+                let flow = text[start..].starts_with('{');
+                let mut entries: Vec<(Node, Node)> = Vec::new();
+                while !self.peek_end(|e| matches!(e, Event::MappingEnd)) {
+                    let key = self.node()?;
+                    let mut value = self.node()?;
+                    let after_key = skip_blank_and_comments(text, key.end);
+                    let head = if text[after_key..].starts_with(':') {
+                        after_key + 1
+                    } else {
+                        key.end
+                    };
+                    value.head = head;
+                    value.props = read_props(text, head);
+                    if value.is_empty_scalar() {
+                        value.start = head;
+                        value.end = head;
+                    }
+                    entries.push((key, value));
+                }
+                let (_, _, close_end) = self.event()?;
+                let start = entries
+                    .first()
+                    .filter(|_| !flow)
+                    .map(|(k, _)| k.start)
+                    .unwrap_or(start);
+                let end = if flow {
+                    *close_end
+                } else {
+                    entries.last().map(|(_, v)| prev_end(v)).unwrap_or(start)
+                };
+                Ok(node(Kind::Mapping { flow, entries }, start, end))
+            }
+            other => Err(format!("Unexpected YAML event {:?}", other)),
+        }
+    }
+}
+
+/// End of a node's text including a trailing empty value's slot.
+fn prev_end(node: &Node) -> usize {
+    node.end.max(node.head)
+}
+
+impl<'a> Document<'a> {
+    fn parse(text: &'a str) -> Result<Self, String> {
+        // Marker indices count chars; splices need byte offsets.
+        let mut offsets: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+        offsets.push(text.len());
+        let byte = |index: usize| offsets.get(index).copied().unwrap_or(text.len());
+
+        let mut events = Vec::new();
+        for result in Parser::new_from_str(text) {
+            let (event, span) = result.map_err(|e| {
+                format!(
+                    "{} at line {} column {}",
+                    e.info(),
+                    e.marker().line(),
+                    e.marker().col() + 1
+                )
+            })?;
+            match event {
+                Event::StreamStart | Event::DocumentStart(_) | Event::Nothing => {}
+                Event::DocumentEnd | Event::StreamEnd => break,
+                other => events.push((other, byte(span.start.index()), byte(span.end.index()))),
+            }
+        }
+        let root = if events.is_empty() {
+            None
+        } else {
+            let mut builder = TreeBuilder {
+                text,
+                events,
+                next: 0,
+            };
+            Some(builder.node()?)
+        };
+        let mut doc = Document {
+            text,
+            root,
+            indent: 2,
+            seq_indent: 2,
+        };
+        if let Some(root) = &doc.root {
+            let (indent, seq_indent) = detect_indent(text, root);
+            doc.indent = indent.unwrap_or(2);
+            doc.seq_indent = seq_indent.unwrap_or(doc.indent);
+        }
+        Ok(doc)
+    }
+
+    fn render(&self) -> Render {
+        Render {
+            indent: self.indent,
+            seq_indent: self.seq_indent,
+        }
+    }
+
+    fn root(&self) -> Result<&Node, String> {
+        self.root
+            .as_ref()
+            .ok_or_else(|| "Document is empty".to_string())
+    }
+
+    fn find(&self, path: &[Segment]) -> Result<Option<&Node>, String> {
+        let mut node = self.root()?;
+        for (i, segment) in path.iter().enumerate() {
+            if matches!(node.kind, Kind::Alias) {
+                return Err(format!(
+                    "{} is an alias; edit the anchored value instead",
+                    display_path(&path[..i])
+                ));
+            }
+            match node.child(segment) {
+                Some(child) => node = child,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(node))
+    }
+
+    fn get(&self, path: &[Segment]) -> Result<&Node, String> {
+        self.find(path)?
+            .ok_or_else(|| format!("{} not found", display_path(path)))
+    }
+}
+
+/// Indentation used by the document: (mapping step, sequence dash offset).
+fn detect_indent(text: &str, node: &Node) -> (Option<usize>, Option<usize>) {
+    let (mut indent, mut seq_indent) = (None, None);
+    if let Kind::Mapping {
+        flow: false,
+        entries,
+    } = &node.kind
+    {
+        for (key, value) in entries {
+            let key_col = column(text, key.start);
+            match &value.kind {
+                Kind::Mapping { flow: false, .. } if indent.is_none() => {
+                    indent = column(text, value.start).checked_sub(key_col);
+                }
+                Kind::Sequence {
+                    flow: false,
+                    dashes,
+                    ..
+                } if seq_indent.is_none() => {
+                    seq_indent = dashes
+                        .first()
+                        .and_then(|d| column(text, *d).checked_sub(key_col));
+                }
+                _ => {}
+            }
+        }
+    }
+    let children: Vec<&Node> = match &node.kind {
+        Kind::Mapping { entries, .. } => entries.iter().map(|(_, v)| v).collect(),
+        Kind::Sequence { items, .. } => items.iter().collect(),
+        _ => Vec::new(),
+    };
+    for child in children {
+        if indent.is_some() && seq_indent.is_some() {
+            break;
+        }
+        let (i, s) = detect_indent(text, child);
+        indent = indent.or(i.filter(|&i| i > 0));
+        seq_indent = seq_indent.or(s);
+    }
+    (indent.filter(|&i| i > 0), seq_indent)
+}
+
+// ---- Planning ----------------------------------------------------------------
+
+/// A primitive splice. Paths only use keys and indexes once planned.
+#[derive(Debug)]
+enum Edit {
+    Replace(Vec<Segment>, Value),
+    InsertKey(Vec<Segment>, String, Value),
+    Remove(Vec<Segment>),
+    Append(Vec<Segment>, Value),
+    /// Write the first value of a document that has none.
+    Fill(Value),
+}
+
+/// Resolve `[field=value]` selectors to indexes so later edits address the same item.
+/// An empty document has no part of any path.
+fn concrete_path(doc: &Document, path: &[Segment]) -> Result<(Vec<Segment>, usize), String> {
+    let Some(mut node) = doc.root.as_ref() else {
+        return Ok((Vec::new(), 0));
+    };
+    let mut out = Vec::new();
+    for segment in path {
+        if matches!(node.kind, Kind::Alias) {
+            return Err(format!(
+                "{} is an alias; edit the anchored value instead",
+                display_path(&out)
+            ));
+        }
+        let concrete = match (segment, &node.kind) {
+            (Segment::Match { .. }, Kind::Sequence { items, .. }) => {
+                let found = node.child(segment);
+                match items
+                    .iter()
+                    .position(|item| found.is_some_and(|f| std::ptr::eq(f, item)))
+                {
+                    Some(i) => Segment::Index(i),
+                    None => break,
+                }
+            }
+            _ => segment.clone(),
+        };
+        match node.child(segment) {
+            Some(child) => {
+                out.push(concrete);
+                node = child;
+            }
+            None => break,
+        }
+    }
+    let found = out.len();
+    Ok((out, found))
+}
+
+fn plan_set(text: &str, path: &[Segment], value: &Value) -> Result<Vec<Edit>, String> {
+    let doc = Document::parse(text)?;
+    if doc.root.is_none() {
+        return Ok(vec![Edit::Fill(nest(path, value)?)]);
+    }
+    let (mut concrete, found) = concrete_path(&doc, path)?;
+    let mut edits = Vec::new();
+    if found == path.len() {
+        reconcile(doc.get(&concrete)?, &mut concrete, value, &mut edits);
+        return Ok(edits);
+    }
+
+    // Build the missing tail as nested mappings and insert it at the deepest
+    // existing node.
+    let missing = &path[found..];
+    let Segment::Key(first) = &missing[0] else {
+        return Err(format!("{} not found", display_path(&path[..found + 1])));
+    };
+    let nested = nest(&missing[1..], value)?;
+    let parent = doc.get(&concrete)?;
+    match &parent.kind {
+        Kind::Mapping { .. } => edits.push(Edit::InsertKey(concrete, first.clone(), nested)),
+        _ if parent.is_empty_scalar() => {
+            let mut map = Map::new();
+            map.insert(first.clone(), nested);
+            edits.push(Edit::Replace(concrete, Value::Object(map)));
+        }
+        _ => return Err(format!("{} is not a mapping", display_path(&concrete))),
+    }
+    Ok(edits)
+}
+
+/// `value` wrapped in one mapping per key of `path`.
+fn nest(path: &[Segment], value: &Value) -> Result<Value, String> {
+    let mut nested = value.clone();
+    for segment in path.iter().rev() {
+        let Segment::Key(key) = segment else {
+            return Err(format!("{} not found", display_path(path)));
+        };
+        let mut map = Map::new();
+        map.insert(key.clone(), nested);
+        nested = Value::Object(map);
+    }
+    Ok(nested)
+}
+
+/// Edits that turn `node` into `value` while touching as little text as possible.
+fn reconcile(node: &Node, path: &mut Vec<Segment>, value: &Value, edits: &mut Vec<Edit>) {
+    match (&node.kind, value) {
+        (Kind::Mapping { entries, .. }, Value::Object(map)) if !map.is_empty() => {
+            for name in entries.iter().filter_map(|(k, _)| k.key()) {
+                if !map.contains_key(name) {
+                    path.push(Segment::Key(name.to_string()));
+                    edits.push(Edit::Remove(path.clone()));
+                    path.pop();
+                }
+            }
+            for (name, new) in map {
+                let existing = entries
+                    .iter()
+                    .find(|(k, _)| k.key() == Some(name))
+                    .map(|(_, v)| v);
+                match existing {
+                    Some(child) => {
+                        path.push(Segment::Key(name.clone()));
+                        reconcile(child, path, new, edits);
+                        path.pop();
+                    }
+                    None => edits.push(Edit::InsertKey(path.clone(), name.clone(), new.clone())),
+                }
+            }
+        }
+        (Kind::Sequence { items, .. }, Value::Array(values)) if !values.is_empty() => {
+            for (i, (item, new)) in items.iter().zip(values).enumerate() {
+                path.push(Segment::Index(i));
+                reconcile(item, path, new, edits);
+                path.pop();
+            }
+            // Remove from the end so earlier indexes stay valid.
+            for i in (values.len()..items.len()).rev() {
+                path.push(Segment::Index(i));
+                edits.push(Edit::Remove(path.clone()));
+                path.pop();
+            }
+            for new in values.iter().skip(items.len()) {
+                edits.push(Edit::Append(path.clone(), new.clone()));
+            }
+        }
+        (Kind::Scalar { .. }, new) if node.scalar_value().as_ref() == Some(new) => {}
+        (Kind::Mapping { entries, .. }, Value::Object(map))
+            if entries.is_empty() && map.is_empty() => {}
+        (Kind::Sequence { items, .. }, Value::Array(values))
+            if items.is_empty() && values.is_empty() => {}
+        _ => edits.push(Edit::Replace(path.clone(), value.clone())),
+    }
+}
+
+// ---- Splicing ----------------------------------------------------------------
+
+/// Replace `start..end` with `with`, whose line breaks are `\n` and are written in the
+/// document's own style.
+fn splice(text: &str, start: usize, end: usize, with: &str) -> String {
+    let with = match line_break(text) {
+        "\n" => with.to_string(),
+        newline => with.replace('\n', newline),
+    };
+    let mut out = String::with_capacity(text.len() + with.len());
+    out.push_str(&text[..start]);
+    out.push_str(&with);
+    out.push_str(&text[end..]);
+    out
+}
+
+/// How a value is laid out: the slot after a key (`key_col` known), a block sequence
+/// item, the document root, or inside a flow collection.
+#[derive(Clone, Copy)]
+enum Slot {
+    MapValue { key_col: usize },
+    SeqItem { dash_col: usize },
+    Root,
+    Flow,
+}
+
+fn slot_of(doc: &Document, path: &[Segment]) -> Result<Slot, String> {
+    if path.is_empty() {
+        return Ok(Slot::Root);
+    }
+    let parent = doc.get(&path[..path.len() - 1])?;
+    if parent.is_flow() {
+        return Ok(Slot::Flow);
+    }
+    Ok(match (&parent.kind, path.last()) {
+        (Kind::Mapping { entries, .. }, Some(Segment::Key(key))) => {
+            let key_node = entries
+                .iter()
+                .find(|(k, _)| k.key() == Some(key))
+                .map(|(k, _)| k)
+                .ok_or_else(|| format!("{} not found", display_path(path)))?;
+            Slot::MapValue {
+                key_col: column(doc.text, key_node.start),
+            }
+        }
+        (Kind::Sequence { dashes, .. }, Some(Segment::Index(i))) => Slot::SeqItem {
+            dash_col: column(doc.text, dashes[*i]),
+        },
+        _ => return Err(format!("{} not found", display_path(path))),
+    })
+}
+
+fn apply_edit(text: &str, edit: &Edit) -> Result<String, String> {
+    let doc = Document::parse(text)?;
+    let r = doc.render();
+    match edit {
+        Edit::Fill(value) => {
+            // After any comments or `---` already in the document
+            let rendered = if is_inline(value) {
+                r.scalar(value, None)
+            } else {
+                r.block(value, 0)
+            };
+            let end = text.trim_end().len();
+            let separator = if end == 0 { "" } else { "\n" };
+            Ok(splice(
+                text,
+                end,
+                text.len(),
+                &format!("{}{}\n", separator, rendered),
+            ))
+        }
+        Edit::Replace(path, value) => {
+            let node = doc.get(path)?;
+            let slot = slot_of(&doc, path)?;
+            let inline_old = matches!(node.kind, Kind::Scalar { .. } | Kind::Alias)
+                && !node.is_empty_scalar()
+                && !matches!(
+                    node.kind,
+                    Kind::Scalar {
+                        style: ScalarStyle::Literal | ScalarStyle::Folded,
+                        ..
+                    }
+                );
+            // Stay in flow style inside flow collections and for non-empty flow values;
+            // an empty `[]`/`{}` that gains entries is laid out in block style.
+            let flow_old = match &node.kind {
+                Kind::Mapping { flow, entries } => *flow && !entries.is_empty(),
+                Kind::Sequence { flow, items, .. } => *flow && !items.is_empty(),
+                _ => false,
+            };
+            if matches!(slot, Slot::Flow) || flow_old || (node.is_flow() && is_inline(value)) {
+                return Ok(splice(text, node.start, node.end, &r.flow(value)));
+            }
+            if inline_old && is_inline(value) && !is_multiline(value) {
+                let style = match node.kind {
+                    Kind::Scalar { style, .. } => Some(style),
+                    _ => None,
+                };
+                return Ok(splice(text, node.start, node.end, &r.scalar(value, style)));
+            }
+            if let Slot::Root = slot {
+                let rendered = r.block(value, 0);
+                return Ok(splice(text, node.start, node.end, &rendered));
+            }
+            let mut rendered = String::new();
+            if !node.props.is_empty() {
+                rendered.push(' ');
+                rendered.push_str(&node.props);
+            }
+            rendered.push_str(&r.after_slot(value, slot));
+            Ok(splice(text, node.head, prev_end(node), &rendered))
+        }
+        Edit::InsertKey(path, key, value) => {
+            let node = doc.get(path)?;
+            let Kind::Mapping { flow, entries } = &node.kind else {
+                return Err(format!("{} is not a mapping", display_path(path)));
+            };
+            if *flow {
+                let entry = format!("{}: {}", r.flow_key(key), r.flow(value));
+                return Ok(match entries.last() {
+                    Some((_, last)) => splice(text, last.end, last.end, &format!(", {}", entry)),
+                    None => {
+                        let mut map = Map::new();
+                        map.insert(key.clone(), value.clone());
+                        return apply_edit(text, &Edit::Replace(path.clone(), Value::Object(map)));
+                    }
+                });
+            }
+            let (first_key, _) = &entries[0];
+            let key_col = column(text, first_key.start);
+            let (_, last) = entries.last().expect("block mapping has entries");
+            let at = line_end(text, prev_end(last));
+            let entry = format!(
+                "\n{}{}:{}",
+                " ".repeat(key_col),
+                r.key(key),
+                r.after_slot(value, Slot::MapValue { key_col })
+            );
+            Ok(splice(text, at, at, &entry))
+        }
+        Edit::Append(path, value) => {
+            let node = doc.get(path)?;
+            let Kind::Sequence {
+                flow,
+                items,
+                dashes,
+            } = &node.kind
+            else {
+                return Err(format!("{} is not a sequence", display_path(path)));
+            };
+            if items.is_empty() {
+                // `[]` written inline; lay the sequence out in the slot's own style.
+                return apply_edit(
+                    text,
+                    &Edit::Replace(path.clone(), Value::Array(vec![value.clone()])),
+                );
+            }
+            if *flow {
+                let last = items.last().expect("non-empty");
+                return Ok(splice(
+                    text,
+                    last.end,
+                    last.end,
+                    &format!(", {}", r.flow(value)),
+                ));
+            }
+            let dash_col = column(text, dashes[0]);
+            let at = line_end(text, prev_end(items.last().expect("non-empty")));
+            let item = format!(
+                "\n{}-{}",
+                " ".repeat(dash_col),
+                r.after_slot(value, Slot::SeqItem { dash_col })
+            );
+            Ok(splice(text, at, at, &item))
+        }
+        Edit::Remove(path) => {
+            let (parent_path, last) = path.split_at(path.len() - 1);
+            let parent = doc.get(parent_path)?;
+            match (&parent.kind, &last[0]) {
+                (Kind::Mapping { flow, entries }, Segment::Key(key)) => {
+                    let i = entries
+                        .iter()
+                        .position(|(k, _)| k.key() == Some(key))
+                        .ok_or_else(|| format!("{} not found", display_path(path)))?;
+                    if entries.len() == 1 {
+                        return apply_edit(
+                            text,
+                            &Edit::Replace(parent_path.to_vec(), Value::Object(Map::new())),
+                        );
+                    }
+                    let (key_node, value_node) = &entries[i];
+                    let (start, end) = match entries.get(i + 1) {
+                        Some((next, _)) => (key_node.start, next.start),
+                        None if *flow => (prev_end(&entries[i - 1].1), prev_end(value_node)),
+                        None => (
+                            line_end(text, prev_end(&entries[i - 1].1)),
+                            line_end(text, prev_end(value_node)),
+                        ),
+                    };
+                    Ok(splice(text, start, end, ""))
+                }
+                (
+                    Kind::Sequence {
+                        flow,
+                        items,
+                        dashes,
+                    },
+                    Segment::Index(i),
+                ) => {
+                    let i = *i;
+                    if i >= items.len() {
+                        return Err(format!("{} not found", display_path(path)));
+                    }
+                    if items.len() == 1 {
+                        return apply_edit(
+                            text,
+                            &Edit::Replace(parent_path.to_vec(), Value::Array(Vec::new())),
+                        );
+                    }
+                    let (start, end) = match (flow, items.get(i + 1)) {
+                        (true, Some(next)) => (items[i].start, next.start),
+                        (true, None) => (prev_end(&items[i - 1]), prev_end(&items[i])),
+                        (false, Some(_)) => (dashes[i], dashes[i + 1]),
+                        (false, None) => (
+                            line_end(text, prev_end(&items[i - 1])),
+                            line_end(text, prev_end(&items[i])),
+                        ),
+                    };
+                    Ok(splice(text, start, end, ""))
+                }
+                _ => Err(format!("{} not found", display_path(path))),
+            }
+        }
+    }
+}
+
+// ---- Rendering ---------------------------------------------------------------
+
+fn is_inline(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => true,
+    }
+}
+
+fn is_multiline(value: &Value) -> bool {
+    matches!(value, Value::String(s) if s.contains('\n'))
+}
+
+struct Render {
+    indent: usize,
+    seq_indent: usize,
+}
+
+impl Render {
+    /// Text placed after `key:` or `-` for `value`, including the leading space or
+    /// newline.
+    fn after_slot(&self, value: &Value, slot: Slot) -> String {
+        let (nested_col, seq_col, inline_first) = match slot {
+            Slot::MapValue { key_col } => (key_col + self.indent, key_col + self.seq_indent, false),
+            Slot::SeqItem { dash_col } => (dash_col + 2, dash_col + 2, true),
+            Slot::Root => (0, 0, false),
+            Slot::Flow => return format!(" {}", self.flow(value)),
+        };
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                let block = self.block(value, nested_col);
+                if inline_first {
+                    format!(" {}", block.trim_start())
+                } else {
+                    format!("\n{}", block)
+                }
+            }
+            Value::Array(items) if !items.is_empty() => {
+                let col = if inline_first { nested_col } else { seq_col };
+                let block = self.block(value, col);
+                if inline_first {
+                    format!(" {}", block.trim_start())
+                } else {
+                    format!("\n{}", block)
+                }
+            }
+            Value::String(s) if s.contains('\n') => format!(" {}", self.literal(s, nested_col)),
+            _ => format!(" {}", self.scalar(value, None)),
+        }
+    }
+
+    /// A non-empty mapping or sequence in block style with its first line indented
+    /// to `col`; other values as a single scalar.
+    fn block(&self, value: &Value, col: usize) -> String {
+        let pad = " ".repeat(col);
+        match value {
+            Value::Object(map) if !map.is_empty() => map
+                .iter()
+                .map(|(k, v)| {
+                    format!(
+                        "{}{}:{}",
+                        pad,
+                        self.key(k),
+                        self.after_slot(v, Slot::MapValue { key_col: col })
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Value::Array(items) if !items.is_empty() => items
+                .iter()
+                .map(|v| {
+                    format!(
+                        "{}-{}",
+                        pad,
+                        self.after_slot(v, Slot::SeqItem { dash_col: col })
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => format!("{}{}", pad, self.scalar(value, None)),
+        }
+    }
+
+    /// `|` block for multi-line strings, or a double-quoted string when the text
+    /// can't be represented literally.
+    fn literal(&self, s: &str, col: usize) -> String {
+        let body = s.strip_suffix('\n').unwrap_or(s);
+        let representable = !s.starts_with([' ', '\t', '\n'])
+            && !body.ends_with('\n')
+            && !s.contains('\r')
+            && !body.lines().any(|l| l.ends_with([' ', '\t']));
+        if !representable {
+            return serde_json::to_string(s).unwrap_or_default();
+        }
+        let pad = " ".repeat(col);
+        let indicator = if s.ends_with('\n') { "|" } else { "|-" };
+        let lines: Vec<String> = body
+            .split('\n')
+            .map(|l| {
+                if l.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}{}", pad, l)
+                }
+            })
+            .collect();
+        format!("{}\n{}", indicator, lines.join("\n"))
+    }
+
+    /// A scalar on one line, in `style` when the value allows it.
+    fn scalar(&self, value: &Value, style: Option<ScalarStyle>) -> String {
+        match value {
+            Value::Null => "null".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => number(n),
+            Value::String(s) => match style {
+                Some(ScalarStyle::SingleQuoted) if !s.contains('\n') => {
+                    format!("'{}'", s.replace('\'', "''"))
+                }
+                Some(ScalarStyle::DoubleQuoted) => serde_json::to_string(s).unwrap_or_default(),
+                _ if s.contains('\n') => serde_json::to_string(s).unwrap_or_default(),
+                _ => plain_string(s),
+            },
+            Value::Object(_) | Value::Array(_) => self.flow(value),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        if key.contains('\n') {
+            serde_json::to_string(key).unwrap_or_default()
+        } else {
+            plain_string(key)
+        }
+    }
+
+    fn flow_key(&self, key: &str) -> String {
+        flow_string(key)
+    }
+
+    /// Flow style (`{a: 1, b: [x, y]}`), used inside existing flow collections.
+    fn flow(&self, value: &Value) -> String {
+        match value {
+            Value::Object(map) => {
+                let entries: Vec<String> = map
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", flow_string(k), self.flow(v)))
+                    .collect();
+                if entries.is_empty() {
+                    "{}".to_string()
+                } else {
+                    format!("{{{}}}", entries.join(", "))
+                }
+            }
+            Value::Array(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(|v| self.flow(v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::String(s) => flow_string(s),
+            other => self.scalar(other, None),
+        }
+    }
+}
+
+/// Whole numbers are written without a fraction, as `spec::number` does.
+fn number(n: &serde_json::Number) -> String {
+    match n.as_f64() {
+        Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < 9_007_199_254_740_992.0 => {
+            format!("{}", f as i64)
+        }
+        _ => n.to_string(),
+    }
+}
+
+/// A single-line string, plain when YAML reads it back as the same string and quoted
+/// otherwise.
+fn plain_string(s: &str) -> String {
+    serde_yaml::to_string(s)
+        .map(|out| out.trim_end_matches('\n').to_string())
+        .ok()
+        .filter(|out| !out.contains('\n'))
+        .unwrap_or_else(|| serde_json::to_string(s).unwrap_or_default())
+}
+
+fn flow_string(s: &str) -> String {
+    let plain = plain_string(s);
+    if plain.contains([',', '[', ']', '{', '}']) && !plain.starts_with(['\'', '"']) {
+        serde_json::to_string(s).unwrap_or_default()
+    } else {
+        plain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn set(path: &str, value: Value) -> YamlPatch {
+        YamlPatch::Set {
+            path: path.to_string(),
+            value,
+        }
+    }
+
+    fn patch(content: &str, patches: &[YamlPatch]) -> String {
+        apply_patches(content, patches).unwrap()
+    }
+
+    #[test]
+    fn comments_and_anchors_survive_edits() {
+        let content = "\
+# Flow header
+flow:
+  id: checkout # stable id
+  name: Checkout
+defaults: &defaults
+  timeout_ms: 100
+nodes:
+  - id: a
+    spec: *defaults
+";
+        let out = patch(
+            content,
+            &[
+                set("flow.name", json!("Checkout v2")),
+                set("defaults.timeout_ms", json!(250)),
+            ],
+        );
+        assert_eq!(
+            out,
+            "\
+# Flow header
+flow:
+  id: checkout # stable id
+  name: Checkout v2
+defaults: &defaults
+  timeout_ms: 250
+nodes:
+  - id: a
+    spec: *defaults
+"
+        );
+    }
+
+    #[test]
+    fn replaced_scalars_keep_their_quoting() {
+        let content = "a: 'one'\nb: \"two\"\nc: three\n";
+        let out = patch(
+            content,
+            &[
+                set("a", json!("uno")),
+                set("b", json!("dos")),
+                set("c", json!("tres")),
+            ],
+        );
+        assert_eq!(out, "a: 'uno'\nb: \"dos\"\nc: tres\n");
+    }
+
+    #[test]
+    fn new_keys_use_the_document_indentation() {
+        let content = "flow:\n    id: f\nnodes:\n    -   id: a\n";
+        let out = patch(
+            content,
+            &[
+                set("flow.tags", json!({ "team": "billing" })),
+                YamlPatch::Append {
+                    path: "nodes".to_string(),
+                    value: json!({ "id": "b" }),
+                },
+            ],
+        );
+        assert_eq!(
+            out,
+            "flow:\n    id: f\n    tags:\n        team: billing\nnodes:\n    -   id: a\n    - id: b\n"
+        );
+    }
+
+    #[test]
+    fn crlf_documents_stay_crlf() {
+        let content = "flow:\r\n  id: f\r\n  name: F\r\nnodes: []\r\n";
+        let out = patch(
+            content,
+            &[
+                set("flow.name", json!("G")),
+                set("flow.meta", json!({ "owner": "me" })),
+                YamlPatch::Append {
+                    path: "nodes".to_string(),
+                    value: json!({ "id": "a" }),
+                },
+                YamlPatch::Remove {
+                    path: "flow.id".to_string(),
+                },
+            ],
+        );
+        assert_eq!(
+            out,
+            "flow:\r\n  name: G\r\n  meta:\r\n    owner: me\r\nnodes:\r\n  - id: a\r\n"
+        );
+        assert!(!out.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn set_on_an_empty_document_creates_the_mappings() {
+        let out = patch("", &[set("flow.id", json!("f"))]);
+        assert_eq!(out, "flow:\n  id: f\n");
+
+        let out = patch("# new flow\n", &[set("flow", json!({ "id": "f" }))]);
+        assert_eq!(out, "# new flow\nflow:\n  id: f\n");
+
+        let out = patch(
+            "",
+            &[YamlPatch::Append {
+                path: "nodes".to_string(),
+                value: json!("a"),
+            }],
+        );
+        assert_eq!(out, "nodes:\n  - a\n");
+    }
+
+    #[test]
+    fn rename_key_keeps_the_key_quoting() {
+        let content = "'old': 1\n\"other\": 2\nplain: 3\n";
+        let rename = |path: &str, to: &str| YamlPatch::RenameKey {
+            path: path.to_string(),
+            to: to.to_string(),
+        };
+        let out = patch(
+            content,
+            &[
+                rename("old", "new"),
+                rename("other", "second"),
+                rename("plain", "third"),
+            ],
+        );
+        assert_eq!(out, "'new': 1\n\"second\": 2\nthird: 3\n");
+    }
+
+    #[test]
+    fn locate_points_at_keys() {
+        let content = "flow:\n  id: f\nnodes:\n  - id: a\n    spec:\n      url: x\n";
+        assert_eq!(locate(content, "/nodes/0/spec/url"), Some((6, 7)));
+        assert_eq!(locate(content, "/nodes/0/missing"), Some((4, 5)));
+    }
+}
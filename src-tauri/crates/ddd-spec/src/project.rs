@@ -0,0 +1,55 @@
+use super::Extra;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `ddd-project.json`. Written by the new-project wizard with camelCase keys
+/// (`techStack`, `createdAt`), which stay in `extra`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default)]
+pub struct ProjectConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub domains: Vec<ProjectDomain>,
+    /// Spec format version; absent in files older than version 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_version: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct ProjectDomain {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl ProjectDomain {
+    /// Directory name under `specs/domains/`, derived from the name as the frontend does.
+    pub fn id(&self) -> String {
+        domain_id(&self.name)
+    }
+}
+
+/// The directory name for a domain called `name`: lowercased, with each run of
+/// whitespace replaced by `-`.
+pub fn domain_id(name: &str) -> String {
+    let mut id = String::with_capacity(name.len());
+    let mut in_space = false;
+    for c in name.to_lowercase().chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                id.push('-');
+            }
+            in_space = true;
+        } else {
+            id.push(c);
+            in_space = false;
+        }
+    }
+    id
+}
@@ -0,0 +1,777 @@
+//! Renaming a domain, flow, event or node id in every spec document that refers to it.
+//!
+//! Each `rename_in_*` function updates one typed document in place and returns how
+//! many references it changed. [`edit_yaml`] and [`edit_json`] run such a function
+//! against a file's contents and carry only the values that changed back into the
+//! source text, so comments and formatting elsewhere survive.
+
+use super::nodes::TriggerEvent;
+use super::patch::{apply_patches, YamlPatch};
+use super::project::domain_id;
+use super::{DomainConfig, FlowDocument, NodeSpec, ProjectConfig, SystemLayout};
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Rename {
+    /// `from` is the domain's directory name and `to` its new name; the directory is
+    /// renamed to the id derived from `to`.
+    Domain {
+        from: String,
+        to: String,
+    },
+    Flow {
+        domain: String,
+        from: String,
+        to: String,
+    },
+    Event {
+        from: String,
+        to: String,
+    },
+    Node {
+        domain: String,
+        flow: String,
+        from: String,
+        to: String,
+    },
+}
+
+fn swap(value: &mut String, from: &str, to: &str) -> usize {
+    if value == from {
+        *value = to.to_string();
+        1
+    } else {
+        0
+    }
+}
+
+fn swap_opt(value: &mut Option<String>, from: &str, to: &str) -> usize {
+    value.as_mut().map_or(0, |v| swap(v, from, to))
+}
+
+fn swap_all(values: &mut [String], from: &str, to: &str) -> usize {
+    values.iter_mut().map(|v| swap(v, from, to)).sum()
+}
+
+/// Rename a map key in place, keeping its position. Nothing happens if `to` is taken.
+fn rename_key<V>(map: &mut IndexMap<String, V>, from: &str, to: &str) -> usize {
+    if !map.contains_key(from) || map.contains_key(to) {
+        return 0;
+    }
+    *map = std::mem::take(map)
+        .into_iter()
+        .map(|(k, v)| {
+            if k == from {
+                (to.to_string(), v)
+            } else {
+                (k, v)
+            }
+        })
+        .collect();
+    1
+}
+
+/// A flow reference written either as `domain/flow` or as a bare flow id, which is
+/// relative to `current` (the domain of the document it appears in).
+fn rename_flow_ref(rename: &Rename, current: &str, reference: &mut String) -> usize {
+    let renamed = match (rename, reference.split_once('/')) {
+        (Rename::Domain { from, to }, Some((domain, flow))) if domain == from => {
+            format!("{}/{}", domain_id(to), flow)
+        }
+        (Rename::Flow { domain, from, to }, Some((d, flow))) if d == domain && flow == from => {
+            format!("{}/{}", domain, to)
+        }
+        (Rename::Flow { domain, from, to }, None) if current == domain && reference == from => {
+            to.clone()
+        }
+        _ => return 0,
+    };
+    *reference = renamed;
+    1
+}
+
+/// A flow named by id plus an optional domain that defaults to `current`.
+fn rename_flow_target(
+    rename: &Rename,
+    current: &str,
+    flow: &mut String,
+    domain: &mut Option<String>,
+) -> usize {
+    let Some(target_domain) = domain.as_mut() else {
+        return rename_flow_ref(rename, current, flow);
+    };
+    match rename {
+        Rename::Domain { from, to } => swap(target_domain, from, &domain_id(to)),
+        Rename::Flow {
+            domain: d,
+            from,
+            to,
+        } if target_domain == d => swap(flow, from, to),
+        _ => 0,
+    }
+}
+
+fn rename_in_spec(rename: &Rename, current: &str, spec: &mut NodeSpec) -> usize {
+    match (rename, spec) {
+        (Rename::Event { from, to }, NodeSpec::Trigger(spec)) => match &mut spec.event {
+            Some(TriggerEvent::One(event)) => swap(event, from, to),
+            Some(TriggerEvent::Many(events)) => swap_all(events, from, to),
+            None => 0,
+        },
+        (Rename::Event { from, to }, NodeSpec::Event(spec)) => {
+            swap_opt(&mut spec.event_name, from, to)
+        }
+        (_, NodeSpec::SubFlow(spec)) => spec
+            .flow_ref
+            .as_mut()
+            .map_or(0, |r| rename_flow_ref(rename, current, r)),
+        (_, NodeSpec::Orchestrator(spec)) => spec
+            .agents
+            .iter_mut()
+            .flatten()
+            .map(|agent| rename_flow_ref(rename, current, &mut agent.flow))
+            .sum(),
+        (_, NodeSpec::AgentGroup(spec)) => spec
+            .members
+            .iter_mut()
+            .flatten()
+            .map(|m| rename_flow_target(rename, current, &mut m.flow, &mut m.domain))
+            .sum(),
+        (_, NodeSpec::Handoff(spec)) => {
+            let mut count = 0;
+            if let Some(target) = spec.target.as_mut() {
+                if let Some(flow) = target.flow.as_mut() {
+                    count += rename_flow_target(rename, current, flow, &mut target.domain);
+                }
+            }
+            if let Some(return_to) = spec.on_complete.as_mut().and_then(|c| c.return_to.as_mut()) {
+                count += rename_flow_ref(rename, current, return_to);
+            }
+            count
+        }
+        _ => 0,
+    }
+}
+
+/// A flow document stored under `specs/domains/<domain>/`.
+pub fn rename_in_flow(rename: &Rename, domain: &str, doc: &mut FlowDocument) -> usize {
+    let mut count = 0;
+    match rename {
+        Rename::Domain { from, to } if domain == from => {
+            count += swap(&mut doc.flow.domain, from, &domain_id(to));
+        }
+        Rename::Flow {
+            domain: d,
+            from,
+            to,
+        } if domain == d => {
+            count += swap(&mut doc.flow.id, from, to);
+        }
+        Rename::Node {
+            domain: d,
+            flow,
+            from,
+            to,
+        } if domain == d && doc.flow.id == *flow => {
+            for node in doc.all_nodes_mut() {
+                count += swap(&mut node.id, from, to);
+                count += swap_opt(&mut node.parent_id, from, to);
+                for connection in &mut node.connections {
+                    count += swap(&mut connection.target_node_id, from, to);
+                }
+            }
+        }
+        _ => {}
+    }
+    for node in doc.all_nodes_mut() {
+        count += rename_in_spec(rename, domain, &mut node.spec);
+    }
+    count
+}
+
+/// `specs/domains/<domain>/domain.yaml`.
+pub fn rename_in_domain(rename: &Rename, domain: &str, config: &mut DomainConfig) -> usize {
+    let mut count = 0;
+    match rename {
+        Rename::Domain { from, to } => {
+            if domain == from && config.name != *to {
+                config.name = to.clone();
+                count += 1;
+            }
+            count += rename_key(&mut config.layout.portals, from, &domain_id(to));
+        }
+        Rename::Flow {
+            domain: d,
+            from,
+            to,
+        } if domain == d => {
+            for entry in &mut config.flows {
+                count += swap(&mut entry.id, from, to);
+            }
+            count += rename_key(&mut config.layout.flows, from, to);
+            for group in config.groups.iter_mut().flatten() {
+                count += swap_all(&mut group.flow_ids, from, to);
+            }
+            for wiring in &mut config.publishes_events {
+                count += swap_opt(&mut wiring.from_flow, from, to);
+            }
+            for wiring in &mut config.consumes_events {
+                count += swap_opt(&mut wiring.handled_by_flow, from, to);
+            }
+        }
+        Rename::Event { from, to } => {
+            for wiring in config
+                .publishes_events
+                .iter_mut()
+                .chain(config.consumes_events.iter_mut())
+            {
+                count += swap(&mut wiring.event, from, to);
+            }
+        }
+        _ => {}
+    }
+    count
+}
+
+/// `specs/system-layout.yaml`.
+pub fn rename_in_layout(rename: &Rename, layout: &mut SystemLayout) -> usize {
+    let Rename::Domain { from, to } = rename else {
+        return 0;
+    };
+    let id = domain_id(to);
+    let mut count = rename_key(&mut layout.domains, from, &id);
+    for zone in layout.zones.iter_mut().flatten() {
+        count += swap_all(&mut zone.domain_ids, from, &id);
+    }
+    count
+}
+
+/// `ddd-project.json`.
+pub fn rename_in_project(rename: &Rename, project: &mut ProjectConfig) -> usize {
+    let Rename::Domain { from, to } = rename else {
+        return 0;
+    };
+    let mut count = 0;
+    for domain in &mut project.domains {
+        if domain.id() == *from && domain.name != *to {
+            domain.name = to.clone();
+            count += 1;
+        }
+    }
+    count
+}
+
+/// `.ddd/mapping.yaml`, whose `flows` are keyed by `domain/flow` and record the spec
+/// path of each implemented flow.
+pub fn rename_in_mapping(rename: &Rename, mapping: &mut Value) -> usize {
+    let Some(Value::Object(flows)) = mapping.get_mut("flows") else {
+        return 0;
+    };
+    let rekey = |key: &str| -> Option<String> {
+        let (domain, flow) = key.split_once('/')?;
+        match rename {
+            Rename::Domain { from, to } if domain == from => {
+                Some(format!("{}/{}", domain_id(to), flow))
+            }
+            Rename::Flow {
+                domain: d,
+                from,
+                to,
+            } if domain == d && flow == from => Some(format!("{}/{}", domain, to)),
+            _ => None,
+        }
+    };
+    let mut count = 0;
+    let mut renamed = serde_json::Map::new();
+    for (key, mut entry) in std::mem::take(flows) {
+        let Some(new_key) = rekey(&key) else {
+            renamed.insert(key, entry);
+            continue;
+        };
+        count += 1;
+        let (domain, flow) = new_key.split_once('/').unwrap_or_default();
+        if let Some(Value::String(spec)) = entry.get_mut("spec") {
+            *spec = format!("specs/domains/{}/flows/{}.yaml", domain, flow);
+        }
+        renamed.insert(new_key, entry);
+    }
+    *flows = renamed;
+    count
+}
+
+// --- Carrying typed edits back to the source ---
+
+#[derive(Clone)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+enum Change {
+    Set(Vec<Step>, Value),
+    /// The mapping key at the path was renamed, keeping its position.
+    RenameKey(Vec<Step>, String),
+}
+
+/// Where `after` differs from `before`. A mapping whose keys were renamed in place
+/// gets a key rename per changed key; other mappings whose keys changed, and
+/// sequences whose length changed, are replaced as a whole.
+fn differences(before: &Value, after: &Value, path: &mut Vec<Step>, out: &mut Vec<Change>) {
+    match (before, after) {
+        (Value::Object(b), Value::Object(a))
+            if b.len() == a.len()
+                && b.keys()
+                    .zip(a.keys())
+                    .all(|(old, new)| old == new || !b.contains_key(new)) =>
+        {
+            for ((old_key, b), (new_key, a)) in b.iter().zip(a) {
+                path.push(Step::Key(old_key.clone()));
+                if old_key != new_key {
+                    out.push(Change::RenameKey(path.clone(), new_key.clone()));
+                    path.pop();
+                    path.push(Step::Key(new_key.clone()));
+                }
+                differences(b, a, path, out);
+                path.pop();
+            }
+        }
+        (Value::Array(b), Value::Array(a)) if b.len() == a.len() => {
+            for (i, (b, a)) in b.iter().zip(a).enumerate() {
+                path.push(Step::Index(i));
+                differences(b, a, path, out);
+                path.pop();
+            }
+        }
+        _ if before != after => out.push(Change::Set(path.clone(), after.clone())),
+        _ => {}
+    }
+}
+
+/// A path in the syntax of [`YamlPatch`]; keys that would be misread are quoted.
+fn patch_path(path: &[Step]) -> String {
+    let mut out = String::new();
+    for step in path {
+        match step {
+            Step::Key(key) if !key.is_empty() && !key.contains(['.', '[', ']', '=']) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                out.push_str(key);
+            }
+            Step::Key(key) => out.push_str(&format!("[\"{}\"]", key)),
+            Step::Index(i) => out.push_str(&format!("[{}]", i)),
+        }
+    }
+    out
+}
+
+fn value_at<'a>(doc: &'a mut Value, path: &[Step]) -> Option<&'a mut Value> {
+    path.iter().try_fold(doc, |node, step| match step {
+        Step::Key(key) => node.get_mut(key.as_str()),
+        Step::Index(i) => node.get_mut(*i),
+    })
+}
+
+fn typed_edit<T: Serialize + DeserializeOwned>(
+    doc: Value,
+    edit: impl FnOnce(&mut T) -> usize,
+) -> Result<Option<(Vec<Change>, usize)>, String> {
+    let mut typed: T = serde_json::from_value(doc).map_err(|e| e.to_string())?;
+    let before = serde_json::to_value(&typed).map_err(|e| e.to_string())?;
+    let count = edit(&mut typed);
+    if count == 0 {
+        return Ok(None);
+    }
+    let after = serde_json::to_value(&typed).map_err(|e| e.to_string())?;
+    let mut changes = Vec::new();
+    differences(&before, &after, &mut Vec::new(), &mut changes);
+    Ok(Some((changes, count)))
+}
+
+/// Apply `edit` to the typed form of a YAML document and patch the changed values into
+/// `content`. Returns the new text and the number of references changed, or `None`
+/// when nothing in the document referred to the renamed item.
+pub fn edit_yaml<T: Serialize + DeserializeOwned>(
+    content: &str,
+    edit: impl FnOnce(&mut T) -> usize,
+) -> Result<Option<(String, usize)>, String> {
+    let doc: Value = serde_yaml::from_str(content).map_err(|e| e.to_string())?;
+    let Some((changes, count)) = typed_edit(doc, edit)? else {
+        return Ok(None);
+    };
+    let patches: Vec<YamlPatch> = changes
+        .into_iter()
+        .map(|change| match change {
+            Change::Set(path, value) => YamlPatch::Set {
+                path: patch_path(&path),
+                value,
+            },
+            Change::RenameKey(path, to) => YamlPatch::RenameKey {
+                path: patch_path(&path),
+                to,
+            },
+        })
+        .collect();
+    Ok(Some((apply_patches(content, &patches)?, count)))
+}
+
+/// [`edit_yaml`] for a JSON document, which is written back pretty-printed.
+pub fn edit_json<T: Serialize + DeserializeOwned>(
+    content: &str,
+    edit: impl FnOnce(&mut T) -> usize,
+) -> Result<Option<(String, usize)>, String> {
+    let mut doc: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let Some((changes, count)) = typed_edit(doc.clone(), edit)? else {
+        return Ok(None);
+    };
+    for change in changes {
+        match change {
+            Change::Set(path, value) => {
+                if let Some(target) = value_at(&mut doc, &path) {
+                    *target = value;
+                }
+            }
+            Change::RenameKey(mut path, to) => {
+                let Some(Step::Key(from)) = path.pop() else {
+                    continue;
+                };
+                if let Some(Value::Object(map)) = value_at(&mut doc, &path) {
+                    *map = std::mem::take(map)
+                        .into_iter()
+                        .map(|(k, v)| if k == from { (to.clone(), v) } else { (k, v) })
+                        .collect();
+                }
+            }
+        }
+    }
+    let mut json = serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())?;
+    if content.ends_with('\n') {
+        json.push('\n');
+    }
+    Ok(Some((json, count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW: &str = "\
+# Checkout, started from the storefront
+flow:
+  id: checkout
+  name: Checkout
+  type: agent
+  domain: billing
+trigger:
+  id: trigger-1
+  type: trigger
+  connections:
+    - targetNodeId: step-1
+  spec:
+    event:
+      - OrderPlaced # from the storefront
+      - CartAbandoned
+  label: Start
+nodes:
+  - id: step-1
+    type: sub_flow
+    connections:
+      - targetNodeId: step-2
+    spec:
+      flow_ref: payment
+    label: Pay
+  - id: step-2
+    type: sub_flow
+    parentId: step-1
+    spec:
+      flow_ref: billing/payment # qualified
+    label: Pay again
+  - id: handoff-1
+    type: handoff
+    spec:
+      target:
+        flow: payment
+      on_complete:
+        return_to: billing/payment
+    label: Hand off
+  - id: handoff-2
+    type: handoff
+    spec:
+      target:
+        flow: payment
+        domain: billing
+    label: Hand off elsewhere
+  - id: orchestrator-1
+    type: orchestrator
+    spec:
+      agents:
+        - id: payer
+          flow: payment
+    label: Orchestrate
+  - id: event-1
+    type: event
+    spec:
+      event_name: OrderPlaced
+    label: Announce
+";
+
+    const DOMAIN: &str = "\
+name: Billing
+flows:
+  - id: payment # the main one
+    name: Payment
+layout:
+  flows:
+    payment:
+      x: 10
+      y: 20
+    v1.2:
+      x: 30
+      y: 40
+  portals:
+    users:
+      x: 1
+      y: 2
+groups:
+  - id: core
+    name: Core
+    flow_ids: [payment, refund]
+publishes_events:
+  - event: PaymentFailed
+    from_flow: payment
+  - OrderPlaced
+consumes_events:
+  - event: OrderPlaced
+    handled_by_flow: payment
+";
+
+    fn flow(rename: &Rename, domain: &str) -> (String, usize) {
+        edit_yaml(FLOW, |d: &mut FlowDocument| {
+            rename_in_flow(rename, domain, d)
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    fn domain(rename: &Rename, domain: &str) -> (String, usize) {
+        edit_yaml(DOMAIN, |c: &mut DomainConfig| {
+            rename_in_domain(rename, domain, c)
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    fn flow_rename(domain: &str, from: &str, to: &str) -> Rename {
+        Rename::Flow {
+            domain: domain.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn flow_renames_reach_every_flow_target() {
+        let (text, count) = flow(&flow_rename("billing", "payment", "payments"), "billing");
+        // step-1, step-2, both handoff targets, return_to and the orchestrator agent
+        assert_eq!(count, 6);
+        assert!(!text.contains("payment\n") && !text.contains("payment #"));
+        assert!(text.contains("flow_ref: payments\n"));
+        assert!(text.contains("flow_ref: billing/payments # qualified\n"));
+        assert!(text.contains("return_to: billing/payments\n"));
+        assert!(text.starts_with("# Checkout, started from the storefront\n"));
+        assert!(text.contains("- OrderPlaced # from the storefront\n"));
+
+        // Bare ids are relative to the document's own domain
+        let (text, count) = flow(&flow_rename("billing", "payment", "payments"), "users");
+        assert_eq!(count, 3);
+        assert!(text.contains("flow_ref: payment\n"));
+        assert!(text.contains("flow_ref: billing/payments # qualified\n"));
+        assert!(text.contains("        flow: payments\n        domain: billing\n"));
+    }
+
+    #[test]
+    fn domain_renames_use_the_derived_id() {
+        let rename = Rename::Domain {
+            from: "billing".to_string(),
+            to: "Billing Ops".to_string(),
+        };
+        let (text, count) = flow(&rename, "billing");
+        // flow.domain, step-2, return_to and handoff-2's domain
+        assert_eq!(count, 4);
+        assert!(text.contains("  domain: billing-ops\n"));
+        assert!(text.contains("flow_ref: billing-ops/payment # qualified\n"));
+        assert!(text.contains("return_to: billing-ops/payment\n"));
+        assert!(text.contains("        domain: billing-ops\n"));
+
+        let (text, count) = domain(&rename, "billing");
+        assert_eq!(count, 1);
+        assert!(text.starts_with("name: Billing Ops\n"));
+        let (text, count) = domain(
+            &Rename::Domain {
+                from: "users".to_string(),
+                to: "Members".to_string(),
+            },
+            "billing",
+        );
+        assert_eq!(count, 1);
+        assert!(text.contains("  portals:\n    members:\n      x: 1\n"));
+    }
+
+    #[test]
+    fn event_renames_reach_triggers_event_nodes_and_wiring() {
+        let rename = Rename::Event {
+            from: "OrderPlaced".to_string(),
+            to: "OrderSubmitted".to_string(),
+        };
+        let (text, count) = flow(&rename, "billing");
+        assert_eq!(count, 2);
+        assert!(text.contains("- OrderSubmitted # from the storefront\n"));
+        assert!(text.contains("event_name: OrderSubmitted\n"));
+
+        let (text, count) = domain(&rename, "billing");
+        assert_eq!(count, 2);
+        assert!(text.contains("  - OrderSubmitted\nconsumes_events:\n  - event: OrderSubmitted\n"));
+        assert!(text.contains("  - event: PaymentFailed\n"));
+    }
+
+    #[test]
+    fn node_renames_update_connections_and_parents() {
+        let rename = Rename::Node {
+            domain: "billing".to_string(),
+            flow: "checkout".to_string(),
+            from: "step-1".to_string(),
+            to: "pay".to_string(),
+        };
+        let (text, count) = flow(&rename, "billing");
+        assert_eq!(count, 3);
+        assert!(text.contains("    - targetNodeId: pay\n"));
+        assert!(text.contains("  - id: pay\n"));
+        assert!(text.contains("    parentId: pay\n"));
+
+        // Only in the named flow
+        let other = Rename::Node {
+            domain: "billing".to_string(),
+            flow: "refund".to_string(),
+            from: "step-1".to_string(),
+            to: "pay".to_string(),
+        };
+        let result = edit_yaml(FLOW, |d: &mut FlowDocument| {
+            rename_in_flow(&other, "billing", d)
+        });
+        assert!(result.unwrap().is_none());
+    }
+
+    #[test]
+    fn flow_renames_in_domain_files_keep_layout_positions() {
+        let (text, count) = domain(&flow_rename("billing", "payment", "payments"), "billing");
+        // flow entry, layout key, group, from_flow and handled_by_flow
+        assert_eq!(count, 5);
+        assert!(text.contains("  - id: payments # the main one\n"));
+        assert!(text.contains("  flows:\n    payments:\n      x: 10\n      y: 20\n    v1.2:\n"));
+        assert!(text.contains("flow_ids: [payments, refund]"));
+        assert!(text.contains("    from_flow: payments\n"));
+        assert!(text.contains("    handled_by_flow: payments\n"));
+    }
+
+    #[test]
+    fn keys_with_path_syntax_are_quoted() {
+        let (text, count) = domain(&flow_rename("billing", "v1.2", "v2.0"), "billing");
+        assert_eq!(count, 1);
+        assert!(text.contains("      y: 20\n    v2.0:\n      x: 30\n      y: 40\n"));
+
+        let path = [
+            Step::Key("layout".to_string()),
+            Step::Key("a.b".to_string()),
+            Step::Index(0),
+            Step::Key(String::new()),
+            Step::Key("x".to_string()),
+        ];
+        assert_eq!(patch_path(&path), "layout[\"a.b\"][0][\"\"].x");
+    }
+
+    #[test]
+    fn differences_rename_keys_in_place_and_replace_resized_lists() {
+        let before = serde_json::json!({"a": {"x": 1}, "b": [1, 2], "c": 3});
+        let after = serde_json::json!({"z": {"x": 1}, "b": [1, 2, 3], "c": 4});
+        let mut changes = Vec::new();
+        differences(&before, &after, &mut Vec::new(), &mut changes);
+        let described: Vec<String> = changes
+            .iter()
+            .map(|change| match change {
+                Change::Set(path, value) => format!("set {} = {}", patch_path(path), value),
+                Change::RenameKey(path, to) => format!("rename {} -> {}", patch_path(path), to),
+            })
+            .collect();
+        assert_eq!(
+            described,
+            vec!["rename a -> z", "set b = [1,2,3]", "set c = 4"]
+        );
+
+        // A new key that was already there can't be a rename
+        let before = serde_json::json!({"a": 1, "b": 2});
+        let after = serde_json::json!({"b": 1, "a": 2});
+        let mut changes = Vec::new();
+        differences(&before, &after, &mut Vec::new(), &mut changes);
+        assert!(matches!(&changes[..], [Change::Set(path, _)] if path.is_empty()));
+    }
+
+    #[test]
+    fn mapping_keys_and_spec_paths_follow_renames() {
+        let mapping = "\
+flows:
+  billing/payment:
+    spec: specs/domains/billing/flows/payment.yaml # generated
+    files: [src/payment.ts]
+  users/signup:
+    spec: specs/domains/users/flows/signup.yaml
+";
+        let rename = flow_rename("billing", "payment", "payments");
+        let (text, count) = edit_yaml::<Value>(mapping, |m| rename_in_mapping(&rename, m))
+            .unwrap()
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            text,
+            "\
+flows:
+  billing/payments:
+    spec: specs/domains/billing/flows/payments.yaml # generated
+    files: [src/payment.ts]
+  users/signup:
+    spec: specs/domains/users/flows/signup.yaml
+"
+        );
+
+        let rename = Rename::Domain {
+            from: "users".to_string(),
+            to: "Members".to_string(),
+        };
+        let (text, _) = edit_yaml::<Value>(mapping, |m| rename_in_mapping(&rename, m))
+            .unwrap()
+            .unwrap();
+        assert!(
+            text.contains("  members/signup:\n    spec: specs/domains/members/flows/signup.yaml\n")
+        );
+    }
+
+    #[test]
+    fn project_json_names_follow_domain_renames() {
+        let project = "{\n  \"name\": \"Shop\",\n  \"domains\": [\n    {\n      \"name\": \"Billing\"\n    }\n  ]\n}\n";
+        let rename = Rename::Domain {
+            from: "billing".to_string(),
+            to: "Billing Ops".to_string(),
+        };
+        let (text, count) = edit_json(project, |p: &mut ProjectConfig| {
+            rename_in_project(&rename, p)
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(count, 1);
+        assert!(text.contains("\"name\": \"Billing Ops\""));
+        assert!(text.ends_with("}\n"));
+    }
+}
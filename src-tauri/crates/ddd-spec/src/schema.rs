@@ -0,0 +1,73 @@
+//! JSON Schema (draft 2020-12) for the spec files, generated from the typed model so
+//! it can't drift from what the tool reads. Written to `.ddd/schema/` for editors;
+//! a spec file opts in with a modeline such as
+//! `# yaml-language-server: $schema=../../../../.ddd/schema/flow.schema.json`.
+
+use super::{DomainConfig, FlowDocument, SystemLayout};
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// Where the schemas are written, relative to the project root.
+pub const SCHEMA_DIR: &str = ".ddd/schema";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaKind {
+    Flow,
+    Domain,
+    SystemLayout,
+}
+
+impl SchemaKind {
+    pub const ALL: [SchemaKind; 3] = [
+        SchemaKind::Flow,
+        SchemaKind::Domain,
+        SchemaKind::SystemLayout,
+    ];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            SchemaKind::Flow => "flow.schema.json",
+            SchemaKind::Domain => "domain.schema.json",
+            SchemaKind::SystemLayout => "system-layout.schema.json",
+        }
+    }
+
+    /// The schema a spec file is checked against, from its path relative to the
+    /// project root. `None` for files that aren't specs.
+    pub fn for_path(relative: &Path) -> Option<Self> {
+        let parts: Vec<&str> = relative
+            .components()
+            .map(|c| c.as_os_str().to_str().unwrap_or(""))
+            .collect();
+        match parts.as_slice() {
+            ["specs", "system-layout.yaml"] => Some(SchemaKind::SystemLayout),
+            ["specs", "domains", _, "domain.yaml"] => Some(SchemaKind::Domain),
+            ["specs", "domains", _, "flows", file]
+                if file.ends_with(".yaml") || file.ends_with(".yml") =>
+            {
+                Some(SchemaKind::Flow)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn schema(self) -> Value {
+        match self {
+            SchemaKind::Flow => root_schema::<FlowDocument>("DDD flow"),
+            SchemaKind::Domain => root_schema::<DomainConfig>("DDD domain"),
+            SchemaKind::SystemLayout => root_schema::<SystemLayout>("DDD system layout"),
+        }
+    }
+}
+
+fn root_schema<T: JsonSchema>(title: &str) -> Value {
+    let mut schema = SchemaSettings::draft2020_12()
+        .into_generator()
+        .into_root_schema_for::<T>();
+    schema.insert("title".to_string(), Value::String(title.to_string()));
+    schema.to_value()
+}
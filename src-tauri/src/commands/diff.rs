@@ -0,0 +1,198 @@
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use crate::spec::diff::{diff_domains, diff_flows, SpecDiff};
+use crate::spec::schema::SchemaKind;
+use crate::spec::{self, DomainConfig, FlowDocument};
+use git2::{ErrorCode, Repository};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+fn project_root(sandbox: &ProjectSandbox) -> Result<PathBuf, String> {
+    sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: "specs".to_string(),
+        }
+        .to_string()
+    })
+}
+
+/// Whether `path` is a flow or a domain spec, from where it sits in the project.
+fn spec_kind(root: &Path, path: &Path) -> Result<SchemaKind, String> {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    match SchemaKind::for_path(relative) {
+        Some(kind @ (SchemaKind::Flow | SchemaKind::Domain)) => Ok(kind),
+        _ => Err(format!(
+            "{} is not a flow or domain spec",
+            relative.to_string_lossy()
+        )),
+    }
+}
+
+fn parse<T: DeserializeOwned>(label: &str, content: Option<&str>) -> Result<Option<T>, String> {
+    content
+        .map(|c| spec::from_yaml(c).map_err(|e| format!("Failed to parse {}: {}", label, e)))
+        .transpose()
+}
+
+/// Diff two versions of a spec. `None` content means the file doesn't exist on that
+/// side.
+fn diff_contents(
+    kind: SchemaKind,
+    before: (&str, Option<&str>),
+    after: (&str, Option<&str>),
+    ignore_positions: bool,
+) -> Result<SpecDiff, String> {
+    Ok(match kind {
+        SchemaKind::Flow => {
+            let b: Option<FlowDocument> = parse(before.0, before.1)?;
+            let a: Option<FlowDocument> = parse(after.0, after.1)?;
+            SpecDiff::Flow(diff_flows(b.as_ref(), a.as_ref(), ignore_positions))
+        }
+        _ => {
+            let b: Option<DomainConfig> = parse(before.0, before.1)?;
+            let a: Option<DomainConfig> = parse(after.0, after.1)?;
+            SpecDiff::Domain(diff_domains(b.as_ref(), a.as_ref(), ignore_positions))
+        }
+    })
+}
+
+/// The file's contents, or `None` if it doesn't exist.
+fn read_optional(path: &Path) -> Result<Option<String>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Semantic diff of two flow or two domain spec files in the project: nodes added,
+/// removed or retyped, connections rewired and fields changed, with position-only
+/// changes listed separately (or dropped with `ignore_positions`). A missing file
+/// counts as empty, so everything in the other one shows as added or removed.
+#[tauri::command]
+pub async fn diff_spec_files(
+    sandbox: State<'_, ProjectSandbox>,
+    before: String,
+    after: String,
+    ignore_positions: Option<bool>,
+) -> Result<SpecDiff, String> {
+    let root = project_root(&sandbox)?;
+    let before_path = sandbox.resolve(&before).map_err(|e| e.to_string())?;
+    let after_path = sandbox.resolve(&after).map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let kind = spec_kind(&root, &after_path)?;
+        if spec_kind(&root, &before_path)? != kind {
+            return Err("Cannot diff a flow against a domain spec".to_string());
+        }
+        let before_content = read_optional(&before_path)?;
+        let after_content = read_optional(&after_path)?;
+        diff_contents(
+            kind,
+            (&before, before_content.as_deref()),
+            (&after, after_content.as_deref()),
+            ignore_positions.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Spec diff failed: {}", e))?
+}
+
+/// The file's contents at `rev` as text, or `None` if it doesn't exist there. Invalid
+/// UTF-8 is replaced; use `read_bytes_at_revision` for anything that is written back.
+pub(super) fn read_at_revision(
+    repo: &Repository,
+    rev: &str,
+    relative: &Path,
+) -> Result<Option<String>, String> {
+    Ok(read_bytes_at_revision(repo, rev, relative)?
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// The file's exact contents at `rev`, or `None` if it doesn't exist there.
+pub(super) fn read_bytes_at_revision(
+    repo: &Repository,
+    rev: &str,
+    relative: &Path,
+) -> Result<Option<Vec<u8>>, String> {
+    let tree = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_tree())
+        .map_err(|e| format!("Failed to resolve revision {}: {}", rev, e))?;
+    let entry = match tree.get_path(relative) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(format!(
+                "Failed to read {} at {}: {}",
+                relative.display(),
+                rev,
+                e
+            ))
+        }
+    };
+    let blob = entry
+        .to_object(repo)
+        .and_then(|object| object.peel_to_blob())
+        .map_err(|e| format!("Failed to read {} at {}: {}", relative.display(), rev, e))?;
+    Ok(Some(blob.content().to_vec()))
+}
+
+fn diff_revisions_at(
+    root: &Path,
+    path: &Path,
+    from: &str,
+    to: Option<&str>,
+    ignore_positions: bool,
+) -> Result<SpecDiff, String> {
+    let kind = spec_kind(root, path)?;
+    let repo = Repository::discover(root)
+        .map_err(|e| format!("Failed to open repo at {}: {}", root.display(), e))?;
+    let workdir = repo
+        .workdir()
+        .and_then(|dir| dir.canonicalize().ok())
+        .ok_or_else(|| "Repository has no working directory".to_string())?;
+    let relative = path
+        .strip_prefix(&workdir)
+        .map_err(|_| format!("{} is outside the repository", path.display()))?;
+
+    let before = read_at_revision(&repo, from, relative)?;
+    let after = match to {
+        Some(rev) => read_at_revision(&repo, rev, relative)?,
+        None => read_optional(path)?,
+    };
+    let label = |rev: &str| format!("{} at {}", relative.display(), rev);
+    diff_contents(
+        kind,
+        (&label(from), before.as_deref()),
+        (&label(to.unwrap_or("working tree")), after.as_deref()),
+        ignore_positions,
+    )
+}
+
+/// Semantic diff of a flow or domain spec between two git revisions. `from` defaults
+/// to `HEAD`; without `to` the working tree copy is compared, so the defaults give
+/// working tree vs HEAD.
+#[tauri::command]
+pub async fn diff_spec_revisions(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    from: Option<String>,
+    to: Option<String>,
+    ignore_positions: Option<bool>,
+) -> Result<SpecDiff, String> {
+    let root = project_root(&sandbox)?;
+    let resolved = sandbox.resolve(&path).map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        diff_revisions_at(
+            &root,
+            &resolved,
+            from.as_deref().unwrap_or("HEAD"),
+            to.as_deref(),
+            ignore_positions.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Spec diff failed: {}", e))?
+}
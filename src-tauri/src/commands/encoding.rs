@@ -0,0 +1,164 @@
+use serde::Serialize;
+
+/// How much of a file is inspected for binary content and line endings.
+pub const SAMPLE_BYTES: usize = 1024 * 1024;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Not valid UTF-8 and no BOM, e.g. Latin-1 or a binary file.
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+    Mixed,
+    /// No line breaks at all.
+    None,
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct FileFormat {
+    pub encoding: TextEncoding,
+    pub bom: bool,
+    pub line_ending: LineEnding,
+    pub is_binary: bool,
+    pub final_newline: bool,
+}
+
+/// Decode UTF-16 code units, replacing unpaired surrogates.
+fn decode_utf16(bytes: &[u8], little_endian: bool) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| {
+        if little_endian {
+            u16::from_le_bytes([pair[0], pair[1]])
+        } else {
+            u16::from_be_bytes([pair[0], pair[1]])
+        }
+    });
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn detect_line_ending(text: &str) -> LineEnding {
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                crlf += 1;
+            }
+            '\r' => cr += 1,
+            '\n' => lf += 1,
+            _ => {}
+        }
+    }
+    match (lf > 0, crlf > 0, cr > 0) {
+        (false, false, false) => LineEnding::None,
+        (true, false, false) => LineEnding::Lf,
+        (false, true, false) => LineEnding::Crlf,
+        (false, false, true) => LineEnding::Cr,
+        _ => LineEnding::Mixed,
+    }
+}
+
+/// Detect BOM, encoding and line-ending style from the start of a file. `bytes` may be
+/// a prefix; only the first megabyte is examined.
+pub fn detect_format(bytes: &[u8]) -> FileFormat {
+    let (encoding, bom, body) = if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        (TextEncoding::Utf8, true, rest)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16LE_BOM) {
+        (TextEncoding::Utf16Le, true, rest)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16BE_BOM) {
+        (TextEncoding::Utf16Be, true, rest)
+    } else {
+        (TextEncoding::Utf8, false, bytes)
+    };
+    let sample = &body[..body.len().min(SAMPLE_BYTES)];
+
+    let text = match encoding {
+        TextEncoding::Utf16Le => Some(decode_utf16(sample, true)),
+        TextEncoding::Utf16Be => Some(decode_utf16(sample, false)),
+        _ => match std::str::from_utf8(sample) {
+            Ok(s) => Some(s.to_string()),
+            // A sample cut mid-character is still UTF-8.
+            Err(e) if e.error_len().is_none() => {
+                Some(String::from_utf8_lossy(&sample[..e.valid_up_to()]).to_string())
+            }
+            Err(_) => None,
+        },
+    };
+
+    let Some(text) = text else {
+        return FileFormat {
+            encoding: TextEncoding::Unknown,
+            bom,
+            line_ending: LineEnding::None,
+            is_binary: sample.contains(&0),
+            final_newline: false,
+        };
+    };
+    FileFormat {
+        encoding,
+        bom,
+        line_ending: detect_line_ending(&text),
+        is_binary: text.contains('\0'),
+        final_newline: text.ends_with('\n') || text.ends_with('\r'),
+    }
+}
+
+/// Encode editor text (any line endings, optional leading U+FEFF) so that it keeps the
+/// line-ending style, BOM and encoding described by `format`. Mixed or absent line
+/// endings are left as written.
+pub fn encode_text(text: &str, format: &FileFormat) -> Vec<u8> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let normalized = || text.replace("\r\n", "\n").replace('\r', "\n");
+    let text = match format.line_ending {
+        LineEnding::Lf => normalized(),
+        LineEnding::Crlf => normalized().replace('\n', "\r\n"),
+        LineEnding::Cr => normalized().replace('\n', "\r"),
+        LineEnding::Mixed | LineEnding::None => text.to_string(),
+    };
+
+    match format.encoding {
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+            let little_endian = format.encoding == TextEncoding::Utf16Le;
+            let mut out = Vec::with_capacity(text.len() * 2 + 2);
+            if format.bom {
+                out.extend_from_slice(if little_endian {
+                    UTF16LE_BOM
+                } else {
+                    UTF16BE_BOM
+                });
+            }
+            for unit in text.encode_utf16() {
+                out.extend_from_slice(&if little_endian {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                });
+            }
+            out
+        }
+        TextEncoding::Utf8 | TextEncoding::Unknown => {
+            let mut out = Vec::with_capacity(text.len() + 3);
+            if format.bom {
+                out.extend_from_slice(UTF8_BOM);
+            }
+            out.extend_from_slice(text.as_bytes());
+            out
+        }
+    }
+}
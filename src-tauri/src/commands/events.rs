@@ -0,0 +1,80 @@
+use super::file::write_atomic;
+use super::project::{load_project_graph, SpecParseError};
+use crate::events::{self, EventCatalog, CATALOG_PATH};
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProjectEventCatalog {
+    #[serde(flatten)]
+    pub catalog: EventCatalog,
+    /// Spec files that could not be read or parsed; events they mention are missing.
+    pub parse_errors: Vec<SpecParseError>,
+}
+
+fn project_root(sandbox: &ProjectSandbox) -> Result<PathBuf, String> {
+    sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: "specs".to_string(),
+        }
+        .to_string()
+    })
+}
+
+fn catalog_at(root: &Path) -> ProjectEventCatalog {
+    let graph = load_project_graph(root);
+    let catalog = events::build_catalog(
+        graph
+            .domains
+            .iter()
+            .map(|(id, d)| (id.as_str(), d.config.as_ref(), &d.flows)),
+    );
+    ProjectEventCatalog {
+        catalog,
+        parse_errors: graph.errors,
+    }
+}
+
+/// Every event of the open project with its publishers, consumers and the flow nodes
+/// that emit or consume it, plus dangling consumers, unconsumed events, payload
+/// mismatches and cycles between domains.
+#[tauri::command]
+pub async fn event_catalog(
+    sandbox: State<'_, ProjectSandbox>,
+) -> Result<ProjectEventCatalog, String> {
+    let root = project_root(&sandbox)?;
+    tokio::task::spawn_blocking(move || catalog_at(&root))
+        .await
+        .map_err(|e| format!("Event catalog failed: {}", e))
+}
+
+fn export_at(root: &Path) -> Result<ProjectEventCatalog, String> {
+    let report = catalog_at(root);
+    let content = events::to_yaml(&report.catalog)
+        .map_err(|e| format!("Failed to serialize event catalog: {}", e))?;
+    let path = root.join(CATALOG_PATH);
+    if fs::read_to_string(&path).ok().as_deref() != Some(content.as_str()) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
+        write_atomic(&path, content.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", CATALOG_PATH, e))?;
+    }
+    Ok(report)
+}
+
+/// Build the catalog and write it to `.ddd/events.yaml`, leaving the file alone when
+/// nothing changed. Returns the catalog written.
+#[tauri::command]
+pub async fn export_event_catalog(
+    sandbox: State<'_, ProjectSandbox>,
+) -> Result<ProjectEventCatalog, String> {
+    let root = project_root(&sandbox)?;
+    tokio::task::spawn_blocking(move || export_at(&root))
+        .await
+        .map_err(|e| format!("Event catalog export failed: {}", e))?
+}
@@ -1,56 +1,541 @@
+use crate::commands::encoding::{
+    detect_format, encode_text, FileFormat, TextEncoding, SAMPLE_BYTES,
+};
+use crate::commands::implementation::hash_bytes;
+use crate::commands::logs::{append_rotating, LogRotationOptions, RotationPolicy};
+use crate::commands::trash;
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use crate::spec::patch::{apply_patches, YamlPatch};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
 use std::fs;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::path::Path;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::ipc::{InvokeBody, Request, Response};
+use tauri::State;
 
+/// Error returned by the file commands. Sandbox violations stay typed so callers in
+/// the backend can match on them; I/O failures carry the formatted message.
+#[derive(Debug)]
+pub enum FileError {
+    Sandbox(SandboxError),
+    /// The file on disk no longer matches the hash the caller last read.
+    Conflict {
+        path: String,
+        expected: String,
+        actual: Option<String>,
+    },
+    /// `patch_yaml_file` can't edit the file in place because it is missing or not
+    /// valid YAML; the caller may write the whole document instead.
+    Unpatchable {
+        path: String,
+        reason: String,
+    },
+    Io(String),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::Sandbox(e) => write!(f, "{}", e),
+            FileError::Conflict {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "[conflict] {} changed on disk (expected sha256 {}, found {})",
+                path,
+                expected,
+                actual.as_deref().unwrap_or("no file")
+            ),
+            FileError::Unpatchable { path, reason } => {
+                write!(
+                    f,
+                    "[unpatchable] {} can't be patched in place: {}",
+                    path, reason
+                )
+            }
+            FileError::Io(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+impl Serialize for FileError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl From<SandboxError> for FileError {
+    fn from(e: SandboxError) -> Self {
+        FileError::Sandbox(e)
+    }
+}
+
+impl From<String> for FileError {
+    fn from(msg: String) -> Self {
+        FileError::Io(msg)
+    }
+}
+
+#[tauri::command]
+pub fn read_file(sandbox: State<'_, ProjectSandbox>, path: String) -> Result<String, FileError> {
+    let resolved = sandbox.resolve(&path)?;
+    fs::read_to_string(&resolved)
+        .map_err(|e| FileError::Io(format!("Failed to read file {}: {}", path, e)))
+}
+
+/// Header carrying the target path of `write_file_raw`, URI-encoded by the caller.
+pub const RAW_PATH_HEADER: &str = "x-ddd-path";
+/// Optional header carrying the `expected_hash` precondition of `write_file_raw`.
+pub const RAW_EXPECTED_HASH_HEADER: &str = "x-ddd-expected-hash";
+
+/// A slice of a file as returned by `read_file_bytes`.
+#[derive(Serialize)]
+pub struct FileChunk {
+    /// Base64 (standard alphabet, padded) of the bytes read.
+    pub data: String,
+    pub offset: u64,
+    pub length: u64,
+    pub total_size: u64,
+    pub eof: bool,
+}
+
+/// Read up to `length` bytes starting at `offset` (the rest of the file when `length` is
+/// absent). Also returns the file size. Offsets past the end yield no bytes.
+fn read_range(path: &Path, offset: u64, length: Option<u64>) -> io::Result<(Vec<u8>, u64)> {
+    let mut file = File::open(path)?;
+    let total_size = file.metadata()?.len();
+    let start = offset.min(total_size);
+    let available = total_size - start;
+    let wanted = length.map_or(available, |l| l.min(available));
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::with_capacity(wanted as usize);
+    file.take(wanted).read_to_end(&mut bytes)?;
+    Ok((bytes, total_size))
+}
+
+/// Read raw bytes of any file, whole or a range, base64-encoded for JSON IPC.
 #[tauri::command]
-pub fn read_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path).map_err(|e| format!("Failed to read file {}: {}", path, e))
+pub fn read_file_bytes(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<FileChunk, FileError> {
+    let resolved = sandbox.resolve(&path)?;
+    let offset = offset.unwrap_or(0);
+    let (bytes, total_size) = read_range(&resolved, offset, length)
+        .map_err(|e| FileError::Io(format!("Failed to read file {}: {}", path, e)))?;
+    let offset = offset.min(total_size);
+    Ok(FileChunk {
+        data: BASE64.encode(&bytes),
+        offset,
+        length: bytes.len() as u64,
+        total_size,
+        eof: offset + bytes.len() as u64 >= total_size,
+    })
 }
 
+/// Same as `read_file_bytes` but returns the bytes as a raw IPC response (an
+/// `ArrayBuffer` on the JS side), avoiding the base64 overhead for large files.
 #[tauri::command]
-pub fn write_file(path: String, contents: String) -> Result<(), String> {
-    if let Some(parent) = Path::new(&path).parent() {
+pub fn read_file_raw(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<Response, FileError> {
+    let resolved = sandbox.resolve(&path)?;
+    let (bytes, _) = read_range(&resolved, offset.unwrap_or(0), length)
+        .map_err(|e| FileError::Io(format!("Failed to read file {}: {}", path, e)))?;
+    Ok(Response::new(bytes))
+}
+
+fn write_bytes(
+    sandbox: &ProjectSandbox,
+    path: &str,
+    bytes: &[u8],
+    expected_hash: Option<&str>,
+) -> Result<String, FileError> {
+    let resolved = sandbox.resolve(path)?;
+    if let Some(parent) = resolved.parent() {
         fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create directories for {}: {}", path, e))?;
     }
-    fs::write(&path, contents).map_err(|e| format!("Failed to write file {}: {}", path, e))
+    if let Some(expected) = expected_hash {
+        check_expected_hash(&resolved, path, expected)?;
+    }
+    write_atomic(&resolved, bytes)
+        .map_err(|e| FileError::Io(format!("Failed to write file {}: {}", path, e)))?;
+    Ok(hash_bytes(bytes))
 }
 
+/// Write base64-encoded bytes atomically, with the same `expected_hash` precondition as
+/// `write_file`. Returns the new hash.
 #[tauri::command]
-pub fn path_exists(path: String) -> bool {
-    Path::new(&path).exists()
+pub fn write_file_bytes(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    data: String,
+    expected_hash: Option<String>,
+) -> Result<String, FileError> {
+    let bytes = BASE64
+        .decode(data.as_bytes())
+        .map_err(|e| FileError::Io(format!("Invalid base64 data for {}: {}", path, e)))?;
+    write_bytes(&sandbox, &path, &bytes, expected_hash.as_deref())
+}
+
+/// Decode `%XX` escapes as produced by `encodeURIComponent`.
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
 }
 
+/// Write a raw IPC body (`invoke('write_file_raw', bytes, { headers })`) atomically.
+/// The path comes from the `x-ddd-path` header, the optional precondition from
+/// `x-ddd-expected-hash`. Returns the new hash.
 #[tauri::command]
-pub fn create_directory(path: String) -> Result<(), String> {
-    fs::create_dir_all(&path).map_err(|e| format!("Failed to create directory {}: {}", path, e))
+pub fn write_file_raw(
+    sandbox: State<'_, ProjectSandbox>,
+    request: Request<'_>,
+) -> Result<String, FileError> {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let path = header(RAW_PATH_HEADER)
+        .and_then(|p| percent_decode(&p))
+        .ok_or_else(|| FileError::Io(format!("Missing or invalid {} header", RAW_PATH_HEADER)))?;
+    let InvokeBody::Raw(bytes) = request.body() else {
+        return Err(FileError::Io(format!(
+            "write_file_raw expects a raw byte body for {}",
+            path
+        )));
+    };
+    write_bytes(
+        &sandbox,
+        &path,
+        bytes,
+        header(RAW_EXPECTED_HASH_HEADER).as_deref(),
+    )
 }
 
+/// Report BOM, encoding, line-ending style and whether the file looks binary, from its
+/// first megabyte.
 #[tauri::command]
-pub fn delete_file(path: String) -> Result<(), String> {
-    let p = Path::new(&path);
-    if p.exists() {
-        fs::remove_file(p).map_err(|e| format!("Failed to delete file {}: {}", path, e))
-    } else {
-        Ok(())
+pub fn detect_file_format(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+) -> Result<FileFormat, FileError> {
+    let resolved = sandbox.resolve(&path)?;
+    let (bytes, _) = read_range(&resolved, 0, Some(SAMPLE_BYTES as u64))
+        .map_err(|e| FileError::Io(format!("Failed to read file {}: {}", path, e)))?;
+    Ok(detect_format(&bytes))
+}
+
+#[derive(Serialize, Debug)]
+pub struct PatchedFile {
+    /// The patched text, with `\n` line endings as the editor sees it.
+    pub contents: String,
+    pub hash: String,
+}
+
+/// Apply targeted edits to a YAML file without rewriting the untouched parts, so
+/// comments, key order, anchors and quoting survive. The file's BOM and line endings
+/// are kept. Without `expected_hash` the write still fails if the file changes between
+/// the read and the write.
+#[tauri::command]
+pub fn patch_yaml_file(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    patches: Vec<YamlPatch>,
+    expected_hash: Option<String>,
+) -> Result<PatchedFile, FileError> {
+    let resolved = sandbox.resolve(&path)?;
+    let bytes = fs::read(&resolved).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => FileError::Unpatchable {
+            path: path.clone(),
+            reason: "the file does not exist".to_string(),
+        },
+        _ => FileError::Io(format!("Failed to read file {}: {}", path, e)),
+    })?;
+    let read_hash = hash_bytes(&bytes);
+    if let Some(expected) = expected_hash.as_deref() {
+        if !read_hash.eq_ignore_ascii_case(expected) {
+            return Err(FileError::Conflict {
+                path,
+                expected: expected.to_string(),
+                actual: Some(read_hash),
+            });
+        }
+    }
+
+    let format = detect_format(&bytes);
+    let text = match format.encoding {
+        TextEncoding::Utf8 if !format.is_binary => std::str::from_utf8(&bytes)
+            .map_err(|_| FileError::Io(format!("{} is not valid UTF-8", path)))?,
+        _ => return Err(FileError::Io(format!("{} is not a UTF-8 text file", path))),
+    };
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    if let Err(e) = serde_yaml::from_str::<serde_yaml::Value>(&text) {
+        return Err(FileError::Unpatchable {
+            path,
+            reason: format!("it is not valid YAML: {}", e),
+        });
+    }
+    let contents = apply_patches(&text, &patches)
+        .map_err(|e| FileError::Io(format!("Failed to patch {}: {}", path, e)))?;
+    let hash = write_bytes(
+        &sandbox,
+        &path,
+        &encode_text(&contents, &format),
+        Some(&read_hash),
+    )?;
+    Ok(PatchedFile { contents, hash })
+}
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write `contents` so readers see either the old or the new file, never a truncated one:
+/// write to a temp file in the same directory, fsync it, rename it over `path`, then fsync
+/// the directory so the rename itself survives a crash.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = path.parent().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path has no parent directory")
+    })?;
+    let tmp_path = temp_sibling(path, "tmp");
+
+    let result = (|| {
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(contents)?;
+        // Keep the mode of the file being replaced (e.g. executable scripts).
+        if let Ok(meta) = fs::metadata(path) {
+            tmp.set_permissions(meta.permissions())?;
+        }
+        tmp.sync_all()?;
+        drop(tmp);
+        fs::rename(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+        return result;
     }
+    sync_dir(parent)
 }
 
+/// Unique hidden sibling of `path`, e.g. `.flow.yaml.tmp-<pid>-<nanos>-<seq>`. Keeping it in
+/// the same directory guarantees the later rename stays on one filesystem.
+fn temp_sibling(path: &Path, tag: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let seq = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(
+        ".{}.{}-{}-{}-{}",
+        name,
+        tag,
+        std::process::id(),
+        nanos,
+        seq
+    ))
+}
+
+/// Whether a file name was produced by `temp_sibling` (atomic-write temp files and batch
+/// backups), so watchers and scanners can skip them.
+pub fn is_temp_sibling(file_name: &str) -> bool {
+    file_name.starts_with('.') && (file_name.contains(".tmp-") || file_name.contains(".bak-"))
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    // Directories can't be opened for syncing on Windows; the rename is already durable
+    // once MoveFileEx returns.
+    Ok(())
+}
+
+/// Fail with `FileError::Conflict` unless the file at `path` hashes to `expected`.
+pub(crate) fn check_expected_hash(
+    path: &Path,
+    display: &str,
+    expected: &str,
+) -> Result<(), FileError> {
+    let actual = match fs::read(path) {
+        Ok(bytes) => Some(hash_bytes(&bytes)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(FileError::Io(format!(
+                "Failed to read file {}: {}",
+                display, e
+            )))
+        }
+    };
+    if actual.as_deref().map(|a| a.eq_ignore_ascii_case(expected)) == Some(true) {
+        return Ok(());
+    }
+    Err(FileError::Conflict {
+        path: display.to_string(),
+        expected: expected.to_string(),
+        actual,
+    })
+}
+
+/// Write a file atomically. When `expected_hash` is given, the write only happens if the
+/// current contents still hash to it (SHA-256 hex, as returned by `compute_file_hash`),
+/// so concurrent editors can't silently overwrite each other. With `preserve_format`, the
+/// existing file's BOM, encoding and line endings are kept. Returns the new hash.
 #[tauri::command]
-pub fn delete_directory(path: String) -> Result<(), String> {
-    let p = Path::new(&path);
-    if p.exists() {
-        fs::remove_dir_all(p).map_err(|e| format!("Failed to delete directory {}: {}", path, e))
-    } else {
+pub fn write_file(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    contents: String,
+    expected_hash: Option<String>,
+    preserve_format: Option<bool>,
+) -> Result<String, FileError> {
+    if !preserve_format.unwrap_or(false) {
+        return write_bytes(&sandbox, &path, contents.as_bytes(), expected_hash.as_deref());
+    }
+    // Keep the BOM, encoding and line endings of the file being replaced.
+    let resolved = sandbox.resolve(&path)?;
+    let bytes = match read_range(&resolved, 0, Some(SAMPLE_BYTES as u64)) {
+        Ok((existing, _)) => {
+            let format = detect_format(&existing);
+            if format.is_binary || format.encoding == TextEncoding::Unknown {
+                contents.into_bytes()
+            } else {
+                encode_text(&contents, &format)
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => contents.into_bytes(),
+        Err(e) => return Err(FileError::Io(format!("Failed to read file {}: {}", path, e))),
+    };
+    write_bytes(&sandbox, &path, &bytes, expected_hash.as_deref())
+}
+
+#[tauri::command]
+pub fn path_exists(sandbox: State<'_, ProjectSandbox>, path: String) -> Result<bool, FileError> {
+    Ok(sandbox.resolve(&path)?.exists())
+}
+
+#[tauri::command]
+pub fn create_directory(sandbox: State<'_, ProjectSandbox>, path: String) -> Result<(), FileError> {
+    let resolved = sandbox.resolve(&path)?;
+    fs::create_dir_all(&resolved)
+        .map_err(|e| FileError::Io(format!("Failed to create directory {}: {}", path, e)))
+}
+
+/// Whether deleting `path` has somewhere safe to go: the project trash, or nowhere
+/// because it is scratch state. Everything else (the trash itself, `.ddd/`, allowlisted
+/// app files) is refused rather than removed for good.
+fn check_deletable(
+    sandbox: &ProjectSandbox,
+    path: &Path,
+    display: &str,
+) -> Result<(), SandboxError> {
+    let deletable = sandbox
+        .root()
+        .is_some_and(|root| trash::should_trash(&root, path) || trash::is_scratch(&root, path));
+    if deletable {
         Ok(())
+    } else {
+        Err(SandboxError::ProtectedPath {
+            path: display.to_string(),
+        })
     }
 }
 
-/// Append a line to a log file with rotation (max_bytes per file, keep max_files).
+/// Move `path` into the project trash, or remove it when it is scratch state.
+/// Callers check `check_deletable` first.
+fn trash_or_remove(sandbox: &ProjectSandbox, path: &Path) -> io::Result<()> {
+    let root = sandbox
+        .root()
+        .ok_or_else(|| io::Error::other("no project is open"))?;
+    if trash::should_trash(&root, path) {
+        return trash::move_to_trash(&root, path, path).map(|_| ());
+    }
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[tauri::command]
+pub fn delete_file(sandbox: State<'_, ProjectSandbox>, path: String) -> Result<(), FileError> {
+    let p = sandbox.resolve_removable(&path)?;
+    let Ok(meta) = fs::symlink_metadata(&p) else {
+        return Ok(());
+    };
+    if meta.is_dir() {
+        return Err(FileError::Io(format!(
+            "Cannot delete {} as a file: it is a directory",
+            path
+        )));
+    }
+    check_deletable(&sandbox, &p, &path)?;
+    trash_or_remove(&sandbox, &p)
+        .map_err(|e| FileError::Io(format!("Failed to delete file {}: {}", path, e)))
+}
+
+#[tauri::command]
+pub fn delete_directory(sandbox: State<'_, ProjectSandbox>, path: String) -> Result<(), FileError> {
+    let p = sandbox.resolve_removable(&path)?;
+    if fs::symlink_metadata(&p).is_err() {
+        return Ok(());
+    }
+    check_deletable(&sandbox, &p, &path)?;
+    trash_or_remove(&sandbox, &p)
+        .map_err(|e| FileError::Io(format!("Failed to delete directory {}: {}", path, e)))
+}
+
+/// Append a line to a log file with rotation (max_bytes per file, keep max_files),
+/// optionally daily, gzip-compressed and capped to a total disk budget.
 #[tauri::command]
-pub fn append_log(path: String, line: String, max_bytes: u64, max_files: u32) -> Result<(), String> {
-    let log_path = Path::new(&path);
+pub fn append_log(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    line: String,
+    max_bytes: u64,
+    max_files: u32,
+    rotation: Option<LogRotationOptions>,
+) -> Result<(), FileError> {
+    let resolved = sandbox.resolve(&path)?;
+    let log_path = resolved.as_path();
 
     // Ensure parent directory exists
     if let Some(parent) = log_path.parent() {
@@ -58,48 +543,600 @@ pub fn append_log(path: String, line: String, max_bytes: u64, max_files: u32) ->
             .map_err(|e| format!("Failed to create log directory: {}", e))?;
     }
 
-    // Check if rotation is needed
-    if log_path.exists() {
-        if let Ok(meta) = fs::metadata(log_path) {
-            if meta.len() >= max_bytes {
-                // Rotate: .4 -> delete, .3 -> .4, .2 -> .3, .1 -> .2, current -> .1
-                for i in (1..max_files).rev() {
-                    let from = if i == 1 {
-                        path.clone()
-                    } else {
-                        format!("{}.{}", path, i - 1)
-                    };
-                    let to = format!("{}.{}", path, i);
-                    if Path::new(&from).exists() {
-                        let _ = fs::rename(&from, &to);
-                    }
+    let rotation = rotation.unwrap_or_default();
+    let policy = RotationPolicy {
+        max_bytes,
+        max_files,
+        compress: rotation.compress,
+        daily: rotation.daily,
+        max_total_bytes: rotation.max_total_bytes,
+    };
+    append_rotating(log_path, &line, &policy)
+        .map_err(|e| FileError::Io(format!("Failed to write to log file {}: {}", path, e)))
+}
+
+#[tauri::command]
+pub fn list_directory(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+) -> Result<Vec<String>, FileError> {
+    let p = sandbox.resolve(&path)?;
+    if !p.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(&p)
+        .map_err(|e| FileError::Io(format!("Failed to list directory {}: {}", path, e)))?;
+    Ok(entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .collect())
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum FileOperation {
+    Write {
+        path: String,
+        contents: String,
+        expected_hash: Option<String>,
+    },
+    /// Removes a file or a whole directory. Missing paths are a no-op.
+    Delete {
+        path: String,
+    },
+    Rename {
+        from: String,
+        to: String,
+        #[serde(default)]
+        overwrite: bool,
+    },
+    Mkdir {
+        path: String,
+    },
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Applied,
+    RolledBack,
+    Failed,
+    Skipped,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OperationReport {
+    pub index: usize,
+    pub op: FileOperation,
+    pub status: OperationStatus,
+    pub error: Option<String>,
+    /// SHA-256 of the written contents, for `write` operations that were applied.
+    pub hash: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BatchReport {
+    /// True when every operation was applied and kept.
+    pub committed: bool,
+    pub operations: Vec<OperationReport>,
+    /// Failures while undoing; non-empty means the tree may need manual repair.
+    pub rollback_errors: Vec<String>,
+    /// Failures tidying up after a committed batch (moving deleted files to the trash,
+    /// removing backups). The operations themselves were all applied.
+    pub cleanup_errors: Vec<String>,
+}
+
+/// How to reverse one applied step.
+enum Undo {
+    /// Remove a file this batch created.
+    RemoveFile(PathBuf),
+    /// Remove a directory this batch created (only if still empty).
+    RemoveDir(PathBuf),
+    /// Move a backup or moved path back to where it was.
+    MoveBack { from: PathBuf, to: PathBuf },
+}
+
+impl Undo {
+    fn run(&self) -> io::Result<()> {
+        match self {
+            Undo::RemoveFile(path) => fs::remove_file(path),
+            Undo::RemoveDir(path) => fs::remove_dir(path),
+            Undo::MoveBack { from, to } => fs::rename(from, to),
+        }
+    }
+}
+
+/// Bookkeeping for an in-flight batch: undo steps plus backups to drop on commit.
+#[derive(Default)]
+struct BatchJournal {
+    undo: Vec<Undo>,
+    backups: Vec<PathBuf>,
+    /// Deleted paths as (backup, original); moved to the project trash on commit.
+    deleted: Vec<(PathBuf, PathBuf)>,
+}
+
+impl BatchJournal {
+    fn create_dir_all(&mut self, dir: &Path) -> io::Result<()> {
+        let mut missing = Vec::new();
+        let mut current = Some(dir);
+        while let Some(d) = current {
+            if d.exists() {
+                break;
+            }
+            missing.push(d.to_path_buf());
+            current = d.parent();
+        }
+        for d in missing.into_iter().rev() {
+            fs::create_dir(&d)?;
+            self.undo.push(Undo::RemoveDir(d));
+        }
+        Ok(())
+    }
+
+    /// Move `path` aside to a hidden sibling so it can be restored on rollback.
+    fn move_aside(&mut self, path: &Path) -> io::Result<PathBuf> {
+        let backup = temp_sibling(path, "bak");
+        fs::rename(path, &backup)?;
+        self.undo.push(Undo::MoveBack {
+            from: backup.clone(),
+            to: path.to_path_buf(),
+        });
+        self.backups.push(backup.clone());
+        Ok(backup)
+    }
+
+    /// Copy `path` to a hidden sibling, leaving the original readable until it's replaced.
+    fn copy_aside(&mut self, path: &Path) -> io::Result<()> {
+        let backup = temp_sibling(path, "bak");
+        fs::copy(path, &backup)?;
+        self.undo.push(Undo::MoveBack {
+            from: backup.clone(),
+            to: path.to_path_buf(),
+        });
+        self.backups.push(backup);
+        Ok(())
+    }
+
+    fn rollback(self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for step in self.undo.iter().rev() {
+            if let Err(e) = step.run() {
+                let target = match step {
+                    Undo::RemoveFile(p) | Undo::RemoveDir(p) => p,
+                    Undo::MoveBack { to, .. } => to,
+                };
+                errors.push(format!("Failed to restore {}: {}", target.display(), e));
+            }
+        }
+        errors
+    }
+
+    /// Keep the applied steps. Returns what couldn't be tidied up.
+    fn commit(self, project_root: Option<&Path>) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (backup, original) in &self.deleted {
+            let Some(root) = project_root.filter(|r| trash::should_trash(r, original)) else {
+                continue;
+            };
+            if let Err(e) = trash::move_to_trash(root, backup, original) {
+                errors.push(format!(
+                    "Failed to move {} to trash: {}",
+                    original.display(),
+                    e
+                ));
+            }
+        }
+        for backup in self.backups {
+            let removed = if backup.is_dir() {
+                fs::remove_dir_all(&backup)
+            } else {
+                fs::remove_file(&backup)
+            };
+            // A backup inside a directory that was itself deleted later in the batch is
+            // already gone with that directory's backup.
+            match removed {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => errors.push(format!(
+                    "Failed to remove batch backup {}: {}",
+                    backup.display(),
+                    e
+                )),
+                _ => {}
+            }
+        }
+        errors
+    }
+}
+
+fn apply_operation(
+    journal: &mut BatchJournal,
+    op: &FileOperation,
+    paths: &[PathBuf],
+) -> Result<Option<String>, FileError> {
+    match op {
+        FileOperation::Write {
+            path,
+            contents,
+            expected_hash,
+        } => {
+            let target = &paths[0];
+            if let Some(expected) = expected_hash.as_deref() {
+                check_expected_hash(target, path, expected)?;
+            }
+            if let Some(parent) = target.parent() {
+                journal
+                    .create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create directories for {}: {}", path, e))?;
+            }
+            let existed = target.exists();
+            if existed {
+                journal
+                    .copy_aside(target)
+                    .map_err(|e| format!("Failed to back up {}: {}", path, e))?;
+            }
+            write_atomic(target, contents.as_bytes())
+                .map_err(|e| format!("Failed to write file {}: {}", path, e))?;
+            if !existed {
+                journal.undo.push(Undo::RemoveFile(target.clone()));
+            }
+            Ok(Some(hash_bytes(contents.as_bytes())))
+        }
+        FileOperation::Delete { path } => {
+            let target = &paths[0];
+            if fs::symlink_metadata(target).is_ok() {
+                let backup = journal
+                    .move_aside(target)
+                    .map_err(|e| format!("Failed to delete {}: {}", path, e))?;
+                journal.deleted.push((backup, target.clone()));
+            }
+            Ok(None)
+        }
+        FileOperation::Rename {
+            from,
+            to,
+            overwrite,
+        } => {
+            let (source, dest) = (&paths[0], &paths[1]);
+            if !source.exists() {
+                return Err(FileError::Io(format!("Cannot rename {}: not found", from)));
+            }
+            if dest.exists() {
+                if !overwrite {
+                    return Err(FileError::Io(format!(
+                        "Cannot rename {} to {}: destination exists",
+                        from, to
+                    )));
                 }
+                let backup = journal
+                    .move_aside(dest)
+                    .map_err(|e| format!("Failed to replace {}: {}", to, e))?;
+                journal.deleted.push((backup, dest.clone()));
+            }
+            if let Some(parent) = dest.parent() {
+                journal
+                    .create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create directories for {}: {}", to, e))?;
             }
+            fs::rename(source, dest)
+                .map_err(|e| format!("Failed to rename {} to {}: {}", from, to, e))?;
+            journal.undo.push(Undo::MoveBack {
+                from: dest.clone(),
+                to: source.clone(),
+            });
+            Ok(None)
+        }
+        FileOperation::Mkdir { path } => {
+            journal
+                .create_dir_all(&paths[0])
+                .map_err(|e| format!("Failed to create directory {}: {}", path, e))?;
+            Ok(None)
         }
     }
+}
 
-    // Append line
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_path)
-        .map_err(|e| format!("Failed to open log file: {}", e))?;
-    writeln!(file, "{}", line)
-        .map_err(|e| format!("Failed to write to log file: {}", e))?;
-    Ok(())
+fn resolve_operation(
+    sandbox: &ProjectSandbox,
+    op: &FileOperation,
+) -> Result<Vec<PathBuf>, SandboxError> {
+    match op {
+        FileOperation::Write { path, .. } | FileOperation::Mkdir { path } => {
+            Ok(vec![sandbox.resolve(path)?])
+        }
+        FileOperation::Delete { path } => {
+            let target = sandbox.resolve_removable(path)?;
+            check_deletable(sandbox, &target, path)?;
+            Ok(vec![target])
+        }
+        FileOperation::Rename { from, to, .. } => Ok(vec![
+            sandbox.resolve_removable(from)?,
+            sandbox.resolve_removable(to)?,
+        ]),
+    }
 }
 
+/// Apply `operations` in order, all-or-nothing. Every path is checked against the
+/// sandbox before anything is touched; if any step fails, the applied steps are undone
+/// in reverse from their backups. Deleted project files end up in the project trash.
+pub fn apply_batch(sandbox: &ProjectSandbox, operations: Vec<FileOperation>) -> BatchReport {
+    let mut reports: Vec<OperationReport> = operations
+        .into_iter()
+        .enumerate()
+        .map(|(index, op)| OperationReport {
+            index,
+            op,
+            status: OperationStatus::Skipped,
+            error: None,
+            hash: None,
+        })
+        .collect();
+
+    let mut resolved = Vec::with_capacity(reports.len());
+    for report in reports.iter_mut() {
+        match resolve_operation(sandbox, &report.op) {
+            Ok(paths) => resolved.push(paths),
+            Err(e) => {
+                report.status = OperationStatus::Failed;
+                report.error = Some(e.to_string());
+                return BatchReport {
+                    committed: false,
+                    operations: reports,
+                    rollback_errors: Vec::new(),
+                    cleanup_errors: Vec::new(),
+                };
+            }
+        }
+    }
+
+    let mut journal = BatchJournal::default();
+    let mut failed_at = None;
+    for (report, paths) in reports.iter_mut().zip(resolved.iter()) {
+        match apply_operation(&mut journal, &report.op, paths) {
+            Ok(hash) => {
+                report.status = OperationStatus::Applied;
+                report.hash = hash;
+            }
+            Err(e) => {
+                report.status = OperationStatus::Failed;
+                report.error = Some(e.to_string());
+                failed_at = Some(report.index);
+                break;
+            }
+        }
+    }
+
+    match failed_at {
+        None => {
+            let cleanup_errors = journal.commit(sandbox.root().as_deref());
+            BatchReport {
+                committed: true,
+                operations: reports,
+                rollback_errors: Vec::new(),
+                cleanup_errors,
+            }
+        }
+        Some(failed) => {
+            let rollback_errors = journal.rollback();
+            for report in reports.iter_mut().take(failed) {
+                report.status = OperationStatus::RolledBack;
+                report.hash = None;
+            }
+            BatchReport {
+                committed: false,
+                operations: reports,
+                rollback_errors,
+                cleanup_errors: Vec::new(),
+            }
+        }
+    }
+}
+
+/// Apply a list of write/delete/rename/mkdir operations as one transaction, e.g. when
+/// renaming a domain touches domain.yaml, ddd-project.json and system-layout.yaml.
 #[tauri::command]
-pub fn list_directory(path: String) -> Vec<String> {
-    let p = Path::new(&path);
-    if !p.exists() {
-        return Vec::new();
-    }
-    match fs::read_dir(p) {
-        Ok(entries) => entries
-            .filter_map(|e| e.ok())
-            .filter_map(|e| e.file_name().into_string().ok())
-            .collect(),
-        Err(_) => Vec::new(),
+pub fn apply_file_batch(
+    sandbox: State<'_, ProjectSandbox>,
+    operations: Vec<FileOperation>,
+) -> BatchReport {
+    apply_batch(&sandbox, operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    /// Every file (with its bytes) and directory (`None`) under `root`.
+    fn snapshot(root: &Path) -> BTreeMap<PathBuf, Option<Vec<u8>>> {
+        let mut entries = BTreeMap::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                let relative = path.strip_prefix(root).unwrap().to_path_buf();
+                if path.is_dir() {
+                    entries.insert(relative, None);
+                    pending.push(path);
+                } else {
+                    entries.insert(relative, Some(fs::read(&path).unwrap()));
+                }
+            }
+        }
+        entries
+    }
+
+    /// A project with a few specs, open in a sandbox.
+    fn project() -> (tempfile::TempDir, PathBuf, ProjectSandbox) {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = ProjectSandbox::new(Vec::new());
+        sandbox.approve(dir.path());
+        let root = sandbox
+            .set_root(dir.path().to_str().unwrap(), false)
+            .unwrap();
+        let domain = root.join("specs/domains/billing");
+        fs::create_dir_all(domain.join("flows")).unwrap();
+        fs::write(root.join("ddd-project.json"), "{\"name\": \"shop\"}\n").unwrap();
+        fs::write(domain.join("domain.yaml"), "name: Billing # keep\r\n").unwrap();
+        fs::write(domain.join("flows/checkout.yaml"), [0xff, 0xfe, 0x00, b'x']).unwrap();
+        fs::create_dir_all(root.join("specs/domains/legacy/flows")).unwrap();
+        fs::write(
+            root.join("specs/domains/legacy/domain.yaml"),
+            "name: Legacy\n",
+        )
+        .unwrap();
+        (dir, root, sandbox)
+    }
+
+    fn at(root: &Path, relative: &str) -> String {
+        root.join(relative).to_string_lossy().to_string()
+    }
+
+    fn write(path: String, contents: &str) -> FileOperation {
+        FileOperation::Write {
+            path,
+            contents: contents.to_string(),
+            expected_hash: None,
+        }
+    }
+
+    /// One of each operation, all of which succeed on `project()`.
+    fn operations(root: &Path) -> Vec<FileOperation> {
+        vec![
+            write(
+                at(root, "specs/domains/billing/domain.yaml"),
+                "name: Billing\n",
+            ),
+            write(
+                at(root, "specs/domains/shipping/flows/ship.yaml"),
+                "flow: {}\n",
+            ),
+            FileOperation::Rename {
+                from: at(root, "specs/domains/billing/flows/checkout.yaml"),
+                to: at(root, "specs/domains/billing/flows/pay.yaml"),
+                overwrite: false,
+            },
+            write(
+                at(root, "specs/domains/billing/flows/pay.yaml"),
+                "flow: {}\n",
+            ),
+            FileOperation::Delete {
+                path: at(root, "specs/domains/legacy"),
+            },
+            FileOperation::Mkdir {
+                path: at(root, "specs/domains/support/flows"),
+            },
+            FileOperation::Rename {
+                from: at(root, "ddd-project.json"),
+                to: at(root, "specs/domains/billing/domain.yaml"),
+                overwrite: true,
+            },
+        ]
+    }
+
+    /// Fails once every operation before it was applied.
+    fn failing(root: &Path) -> FileOperation {
+        FileOperation::Write {
+            path: at(root, "ddd-project.json"),
+            contents: "{}".to_string(),
+            expected_hash: Some("0".repeat(64)),
+        }
+    }
+
+    #[test]
+    fn failure_on_any_operation_leaves_the_tree_unchanged() {
+        for n in 0..=operations(Path::new("/")).len() {
+            let (_dir, root, sandbox) = project();
+            let before = snapshot(&root);
+            let mut batch = operations(&root);
+            batch.insert(n, failing(&root));
+
+            let report = apply_batch(&sandbox, batch);
+            assert!(!report.committed);
+            assert!(
+                report.rollback_errors.is_empty(),
+                "{:?}",
+                report.rollback_errors
+            );
+            let statuses: Vec<OperationStatus> =
+                report.operations.iter().map(|o| o.status).collect();
+            assert!(statuses[..n]
+                .iter()
+                .all(|s| *s == OperationStatus::RolledBack));
+            assert_eq!(statuses[n], OperationStatus::Failed);
+            assert!(statuses[n + 1..]
+                .iter()
+                .all(|s| *s == OperationStatus::Skipped));
+            assert_eq!(snapshot(&root), before, "failing operation {}", n);
+        }
+    }
+
+    #[test]
+    fn rename_then_write_is_undone() {
+        let (_dir, root, sandbox) = project();
+        let before = snapshot(&root);
+        let batch = vec![
+            FileOperation::Rename {
+                from: at(&root, "specs/domains/billing"),
+                to: at(&root, "specs/domains/payments"),
+                overwrite: false,
+            },
+            write(
+                at(&root, "specs/domains/payments/domain.yaml"),
+                "name: Payments\n",
+            ),
+            failing(&root),
+        ];
+
+        let report = apply_batch(&sandbox, batch);
+        assert!(!report.committed);
+        assert_eq!(snapshot(&root), before);
+    }
+
+    #[test]
+    fn sandbox_violations_fail_before_anything_is_touched() {
+        let (_dir, root, sandbox) = project();
+        let before = snapshot(&root);
+        let mut batch = operations(&root);
+        batch.push(write("/etc/ddd-test".to_string(), ""));
+
+        let report = apply_batch(&sandbox, batch);
+        assert!(!report.committed);
+        let failed = report.operations.last().unwrap();
+        assert!(failed
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("[outside_project]"));
+        assert!(report.operations[..report.operations.len() - 1]
+            .iter()
+            .all(|o| o.status == OperationStatus::Skipped));
+        assert_eq!(snapshot(&root), before);
+    }
+
+    #[test]
+    fn committed_batch_keeps_its_changes_and_drops_the_backups() {
+        let (_dir, root, sandbox) = project();
+        let report = apply_batch(&sandbox, operations(&root));
+        assert!(report.committed);
+        assert!(
+            report.cleanup_errors.is_empty(),
+            "{:?}",
+            report.cleanup_errors
+        );
+
+        let after = snapshot(&root);
+        let domain = root.join("specs/domains/billing/domain.yaml");
+        assert_eq!(
+            fs::read_to_string(domain).unwrap(),
+            "{\"name\": \"shop\"}\n"
+        );
+        assert!(!root.join("ddd-project.json").exists());
+        assert!(!root.join("specs/domains/legacy").exists());
+        assert!(root.join("specs/domains/support/flows").is_dir());
+        // No backup or temporary file is left next to the specs.
+        assert!(after
+            .keys()
+            .filter(|p| !p.starts_with(trash::TRASH_DIR))
+            .all(|p| !p.to_string_lossy().contains("/.")));
+        // The deleted domain and the replaced domain.yaml went to the trash.
+        assert!(after.keys().any(|p| p.starts_with(trash::TRASH_DIR)));
     }
 }
@@ -1,6 +1,17 @@
-use git2::{Repository, StatusOptions, Status};
-use serde::Serialize;
+use super::diff::{read_at_revision, read_bytes_at_revision};
+use super::file::write_atomic;
+use crate::sandbox::ProjectSandbox;
+use git2::build::CheckoutBuilder;
+use git2::{
+    ApplyLocation, ApplyOptions, Branch, BranchType, Commit, Delta, Diff, DiffFindOptions,
+    DiffOptions, ErrorCode, Oid, Patch, Repository, Sort, Status, StatusOptions, Tree,
+};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs;
+use std::path::Path;
 use std::process::Command;
+use tauri::State;
 
 #[derive(Serialize, Clone)]
 pub struct GitFileEntry {
@@ -11,18 +22,99 @@ pub struct GitFileEntry {
 #[derive(Serialize)]
 pub struct GitStatusResult {
     pub branch: String,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
     pub staged: Vec<GitFileEntry>,
     pub unstaged: Vec<GitFileEntry>,
     pub untracked: Vec<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Debug)]
 pub struct GitLogEntry {
     pub oid: String,
     pub message: String,
     pub timestamp: i64,
 }
 
+#[derive(Serialize, Debug)]
+pub struct GitBranch {
+    /// Short name; remote branches keep their remote prefix (`origin/main`).
+    pub name: String,
+    pub is_remote: bool,
+    pub is_head: bool,
+    /// Upstream of a local branch, e.g. `origin/main`.
+    pub upstream: Option<String>,
+    /// Commits not on the upstream, and upstream commits not on the branch.
+    pub ahead: usize,
+    pub behind: usize,
+    pub last_commit: Option<GitLogEntry>,
+}
+
+#[derive(Serialize)]
+pub struct GitFileHistoryEntry {
+    pub oid: String,
+    pub message: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date, seconds since the Unix epoch.
+    pub timestamp: i64,
+    /// The file's path in this commit; differs from the requested one before a rename.
+    pub path: String,
+    /// Path before the commit when it renamed the file.
+    pub old_path: Option<String>,
+    pub status: String, // "new" | "modified" | "deleted" | "renamed"
+}
+
+#[derive(Serialize, Clone)]
+pub struct GitDiffLine {
+    pub origin: String, // "context" | "addition" | "deletion"
+    pub content: String,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct GitDiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<GitDiffLine>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct GitFileDiff {
+    pub path: String,
+    /// Set when the file was renamed or copied.
+    pub old_path: Option<String>,
+    pub status: String, // "new" | "modified" | "deleted" | "renamed" | "copied" | "typechange"
+    pub binary: bool,
+    /// Empty for binary files.
+    pub hunks: Vec<GitDiffHunk>,
+}
+
+/// A hunk as returned by the diff commands. Only its ranges are used to find it again.
+#[derive(Deserialize)]
+pub struct GitHunkRange {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+impl GitHunkRange {
+    fn matches(&self, hunk: &git2::DiffHunk) -> bool {
+        hunk.old_start() == self.old_start
+            && hunk.old_lines() == self.old_lines
+            && hunk.new_start() == self.new_start
+            && hunk.new_lines() == self.new_lines
+    }
+}
+
+const DEFAULT_CONTEXT_LINES: u32 = 3;
+
 #[tauri::command]
 pub fn git_status(path: String) -> Result<GitStatusResult, String> {
     let repo =
@@ -89,8 +181,16 @@ pub fn git_status(path: String) -> Result<GitStatusResult, String> {
         }
     }
 
+    let (upstream, ahead, behind) = match repo.head() {
+        Ok(head) if head.is_branch() => tracking(&repo, &Branch::wrap(head)),
+        _ => (None, 0, 0),
+    };
+
     Ok(GitStatusResult {
         branch,
+        upstream,
+        ahead,
+        behind,
         staged,
         unstaged,
         untracked,
@@ -213,3 +313,1038 @@ pub fn git_clone(url: String, path: String) -> Result<(), String> {
         Err(format!("Clone failed: {}", stderr.trim()))
     }
 }
+
+fn open_repo(path: &str) -> Result<Repository, String> {
+    Repository::open(path).map_err(|e| format!("Failed to open repo at {}: {}", path, e))
+}
+
+fn diff_options(file_path: Option<&str>, context_lines: Option<u32>) -> DiffOptions {
+    let mut opts = DiffOptions::new();
+    opts.context_lines(context_lines.unwrap_or(DEFAULT_CONTEXT_LINES));
+    if let Some(file_path) = file_path {
+        opts.pathspec(file_path).disable_pathspec_match(true);
+    }
+    opts
+}
+
+fn head_tree(repo: &Repository) -> Result<Option<git2::Tree<'_>>, String> {
+    if repo.is_empty().unwrap_or(true) {
+        return Ok(None);
+    }
+    let tree = repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .map_err(|e| format!("Failed to get HEAD tree: {}", e))?;
+    Ok(Some(tree))
+}
+
+fn delta_status(status: Delta) -> &'static str {
+    match status {
+        Delta::Added | Delta::Untracked => "new",
+        Delta::Deleted => "deleted",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        _ => "modified",
+    }
+}
+
+/// Detect renames and copies, then turn every file of the diff into hunks and lines.
+fn collect_diff(diff: &mut Diff) -> Result<Vec<GitFileDiff>, String> {
+    let mut find = DiffFindOptions::new();
+    find.renames(true).copies(true).for_untracked(true);
+    diff.find_similar(Some(&mut find))
+        .map_err(|e| format!("Failed to detect renames: {}", e))?;
+
+    let mut files = Vec::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        let path_of = |file: git2::DiffFile| {
+            file.path()
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default()
+        };
+        let new_path = path_of(delta.new_file());
+        let old_path = path_of(delta.old_file());
+        let status = delta.status();
+        let mut file = GitFileDiff {
+            path: match status {
+                Delta::Deleted => old_path.clone(),
+                _ => new_path.clone(),
+            },
+            old_path: matches!(status, Delta::Renamed | Delta::Copied).then_some(old_path),
+            status: delta_status(status).to_string(),
+            binary: delta.flags().is_binary(),
+            hunks: Vec::new(),
+        };
+        let patch = Patch::from_diff(diff, idx)
+            .map_err(|e| format!("Failed to diff {}: {}", file.path, e))?;
+        if let Some(patch) = patch {
+            // Binary detection may only happen once the content is loaded.
+            file.binary |= patch.delta().flags().is_binary();
+            if !file.binary {
+                file.hunks = patch_hunks(&patch)?;
+            }
+        }
+        files.push(file);
+    }
+    Ok(files)
+}
+
+fn patch_hunks(patch: &Patch) -> Result<Vec<GitDiffHunk>, String> {
+    let mut hunks = Vec::new();
+    for hunk_idx in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch
+            .hunk(hunk_idx)
+            .map_err(|e| format!("Failed to read hunk: {}", e))?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_idx in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_idx, line_idx)
+                .map_err(|e| format!("Failed to read diff line: {}", e))?;
+            let origin = match line.origin() {
+                '+' => "addition",
+                '-' => "deletion",
+                ' ' => "context",
+                // "\ No newline at end of file" markers
+                _ => continue,
+            };
+            let content = String::from_utf8_lossy(line.content());
+            lines.push(GitDiffLine {
+                origin: origin.to_string(),
+                content: content.trim_end_matches(['\n', '\r']).to_string(),
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+            });
+        }
+        let header = String::from_utf8_lossy(hunk.header());
+        hunks.push(GitDiffHunk {
+            header: header.trim_end().to_string(),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+    Ok(hunks)
+}
+
+/// Unstaged changes to one file: the working tree against the index. Untracked files
+/// show as new. `None` when the file has no unstaged changes.
+#[tauri::command]
+pub fn git_diff_file(
+    path: String,
+    file_path: String,
+    context_lines: Option<u32>,
+) -> Result<Option<GitFileDiff>, String> {
+    let repo = open_repo(&path)?;
+    let mut opts = diff_options(Some(&file_path), context_lines);
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    let mut diff = repo
+        .diff_index_to_workdir(None, Some(&mut opts))
+        .map_err(|e| format!("Failed to diff {}: {}", file_path, e))?;
+    Ok(collect_diff(&mut diff)?.into_iter().next())
+}
+
+/// Staged changes: the index against HEAD, for one file or all of them.
+#[tauri::command]
+pub fn git_diff_staged(
+    path: String,
+    file_path: Option<String>,
+    context_lines: Option<u32>,
+) -> Result<Vec<GitFileDiff>, String> {
+    let repo = open_repo(&path)?;
+    let tree = head_tree(&repo)?;
+    let mut opts = diff_options(file_path.as_deref(), context_lines);
+    let mut diff = repo
+        .diff_tree_to_index(tree.as_ref(), None, Some(&mut opts))
+        .map_err(|e| format!("Failed to diff index: {}", e))?;
+    collect_diff(&mut diff)
+}
+
+/// Changes a commit made, against its first parent (or nothing, for a root commit).
+#[tauri::command]
+pub fn git_diff_commit(
+    path: String,
+    oid: String,
+    context_lines: Option<u32>,
+) -> Result<Vec<GitFileDiff>, String> {
+    let repo = open_repo(&path)?;
+    let commit = repo
+        .revparse_single(&oid)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Failed to find commit {}: {}", oid, e))?;
+    let tree = commit
+        .tree()
+        .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(
+            parent
+                .tree()
+                .map_err(|e| format!("Failed to get parent tree: {}", e))?,
+        ),
+        Err(_) => None,
+    };
+    let mut opts = diff_options(None, context_lines);
+    let mut diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))
+        .map_err(|e| format!("Failed to diff commit {}: {}", oid, e))?;
+    collect_diff(&mut diff)
+}
+
+/// Apply the one hunk of `diff` with the ranges of `want` to the index.
+fn apply_hunk_to_index(repo: &Repository, diff: &Diff, want: &GitHunkRange) -> Result<(), String> {
+    let applied = Cell::new(0);
+    let mut apply_opts = ApplyOptions::new();
+    apply_opts.hunk_callback(|hunk| {
+        let Some(hunk) = hunk else {
+            return false;
+        };
+        let matches = want.matches(&hunk);
+        if matches {
+            applied.set(applied.get() + 1);
+        }
+        matches
+    });
+    // Check first: apply reports success even when every hunk was skipped.
+    apply_opts.check(true);
+    repo.apply(diff, ApplyLocation::Index, Some(&mut apply_opts))
+        .map_err(|e| format!("Failed to apply hunk: {}", e))?;
+    if applied.get() == 0 {
+        return Err("Hunk not found; the file changed since the diff was taken".to_string());
+    }
+    apply_opts.check(false);
+    repo.apply(diff, ApplyLocation::Index, Some(&mut apply_opts))
+        .map_err(|e| format!("Failed to apply hunk: {}", e))
+}
+
+/// Stage one hunk of the file's unstaged changes, as returned by `git_diff_file`.
+/// `context_lines` must match the value the diff was taken with.
+#[tauri::command]
+pub fn git_stage_hunk(
+    path: String,
+    file_path: String,
+    hunk: GitHunkRange,
+    context_lines: Option<u32>,
+) -> Result<(), String> {
+    let repo = open_repo(&path)?;
+    let mut opts = diff_options(Some(&file_path), context_lines);
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    let diff = repo
+        .diff_index_to_workdir(None, Some(&mut opts))
+        .map_err(|e| format!("Failed to diff {}: {}", file_path, e))?;
+    apply_hunk_to_index(&repo, &diff, &hunk)
+}
+
+/// Unstage one hunk of the file's staged changes, as returned by `git_diff_staged`.
+/// `context_lines` must match the value the diff was taken with.
+#[tauri::command]
+pub fn git_unstage_hunk(
+    path: String,
+    file_path: String,
+    hunk: GitHunkRange,
+    context_lines: Option<u32>,
+) -> Result<(), String> {
+    let repo = open_repo(&path)?;
+    let tree = head_tree(&repo)?;
+    // The reverse of the staged diff takes the index back towards HEAD; its hunks
+    // are the staged ones with old and new swapped.
+    let mut opts = diff_options(Some(&file_path), context_lines);
+    opts.reverse(true);
+    let diff = repo
+        .diff_tree_to_index(tree.as_ref(), None, Some(&mut opts))
+        .map_err(|e| format!("Failed to diff index: {}", e))?;
+    let reversed = GitHunkRange {
+        old_start: hunk.new_start,
+        old_lines: hunk.new_lines,
+        new_start: hunk.old_start,
+        new_lines: hunk.old_lines,
+    };
+    apply_hunk_to_index(&repo, &diff, &reversed)
+}
+
+/// Upstream name and ahead/behind counts of a local branch.
+fn tracking(repo: &Repository, branch: &Branch) -> (Option<String>, usize, usize) {
+    let Ok(upstream) = branch.upstream() else {
+        return (None, 0, 0);
+    };
+    let name = upstream.name().ok().flatten().map(|s| s.to_string());
+    let (ahead, behind) = match (branch.get().target(), upstream.get().target()) {
+        (Some(local), Some(remote)) => repo.graph_ahead_behind(local, remote).unwrap_or((0, 0)),
+        _ => (0, 0),
+    };
+    (name, ahead, behind)
+}
+
+fn branch_info(repo: &Repository, branch: &Branch, kind: BranchType) -> Result<GitBranch, String> {
+    let name = branch
+        .name()
+        .map_err(|e| format!("Failed to read branch name: {}", e))?
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())?
+        .to_string();
+    let (upstream, ahead, behind) = match kind {
+        BranchType::Local => tracking(repo, branch),
+        BranchType::Remote => (None, 0, 0),
+    };
+    let last_commit = branch
+        .get()
+        .peel_to_commit()
+        .ok()
+        .map(|commit| GitLogEntry {
+            oid: commit.id().to_string(),
+            message: commit.message().unwrap_or("").to_string(),
+            timestamp: commit.time().seconds(),
+        });
+    Ok(GitBranch {
+        name,
+        is_remote: kind == BranchType::Remote,
+        is_head: branch.is_head(),
+        upstream,
+        ahead,
+        behind,
+        last_commit,
+    })
+}
+
+fn find_local<'r>(repo: &'r Repository, name: &str) -> Result<Branch<'r>, String> {
+    repo.find_branch(name, BranchType::Local)
+        .map_err(|e| format!("Branch {} not found: {}", name, e))
+}
+
+fn branch_error(name: &str, e: git2::Error) -> String {
+    if e.code() == ErrorCode::Exists {
+        format!("Branch {} already exists", name)
+    } else {
+        format!("Failed to create branch {}: {}", name, e)
+    }
+}
+
+fn check_branch_name(name: &str) -> Result<(), String> {
+    match Branch::name_is_valid(name) {
+        Ok(true) => Ok(()),
+        _ => Err(format!("{} is not a valid branch name", name)),
+    }
+}
+
+/// Number of tracked files with staged or unstaged changes; untracked files
+/// don't count since a checkout leaves them alone.
+fn tracked_changes(repo: &Repository) -> Result<usize, String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .map_err(|e| format!("Failed to get statuses: {}", e))?;
+    Ok(statuses
+        .iter()
+        .filter(|entry| entry.status() != Status::CURRENT)
+        .count())
+}
+
+fn ensure_clean(repo: &Repository, target: &str) -> Result<(), String> {
+    match tracked_changes(repo)? {
+        0 => Ok(()),
+        n => Err(format!(
+            "Cannot switch to {}: {} file(s) have uncommitted changes. Commit or stash them first",
+            target, n
+        )),
+    }
+}
+
+/// Check out a local branch and point HEAD at it. Without `force` the checkout
+/// refuses to overwrite changes in the working tree.
+fn checkout_local(repo: &Repository, branch: &Branch, force: bool) -> Result<(), String> {
+    let reference = branch.get();
+    let refname = reference
+        .name()
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())?;
+    let commit = reference
+        .peel_to_commit()
+        .map_err(|e| format!("Failed to resolve {}: {}", refname, e))?;
+    let mut checkout = CheckoutBuilder::new();
+    if force {
+        checkout.force();
+    } else {
+        checkout.safe();
+    }
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout))
+        .map_err(|e| format!("Failed to check out {}: {}", refname, e))?;
+    repo.set_head(refname)
+        .map_err(|e| format!("Failed to update HEAD: {}", e))
+}
+
+/// Local and remote branches with their upstreams and ahead/behind counts.
+#[tauri::command]
+pub fn git_list_branches(path: String) -> Result<Vec<GitBranch>, String> {
+    let repo = open_repo(&path)?;
+    let branches = repo
+        .branches(None)
+        .map_err(|e| format!("Failed to list branches: {}", e))?;
+    let mut result = Vec::new();
+    for item in branches {
+        let (branch, kind) = item.map_err(|e| format!("Failed to read branch: {}", e))?;
+        // `origin/HEAD` only points at the remote's default branch
+        if kind == BranchType::Remote && branch.get().symbolic_target().is_some() {
+            continue;
+        }
+        result.push(branch_info(&repo, &branch, kind)?);
+    }
+    Ok(result)
+}
+
+/// Create a local branch at `from` (any revision, defaults to `HEAD`). A branch
+/// created from a remote branch tracks it. With `checkout` the new branch is
+/// checked out too, under the same rules as `git_checkout_branch`.
+#[tauri::command]
+pub fn git_create_branch(
+    path: String,
+    name: String,
+    from: Option<String>,
+    checkout: Option<bool>,
+) -> Result<GitBranch, String> {
+    let repo = open_repo(&path)?;
+    check_branch_name(&name)?;
+    let rev = from.as_deref().unwrap_or("HEAD");
+    let commit = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Failed to resolve revision {}: {}", rev, e))?;
+    if checkout.unwrap_or(false) {
+        ensure_clean(&repo, &name)?;
+    }
+    let mut branch = repo
+        .branch(&name, &commit, false)
+        .map_err(|e| branch_error(&name, e))?;
+    if let Some(from) = from.as_deref() {
+        if repo.find_branch(from, BranchType::Remote).is_ok() {
+            branch
+                .set_upstream(Some(from))
+                .map_err(|e| format!("Failed to set upstream of {}: {}", name, e))?;
+        }
+    }
+    if checkout.unwrap_or(false) {
+        checkout_local(&repo, &branch, false)?;
+    }
+    branch_info(&repo, &branch, BranchType::Local)
+}
+
+/// Switch to a local branch. Naming a remote branch (`origin/feature`) checks out
+/// a local branch of the same short name, creating it to track the remote one if
+/// needed. Refuses while tracked files have uncommitted changes unless `force`,
+/// which discards them.
+#[tauri::command]
+pub fn git_checkout_branch(
+    path: String,
+    name: String,
+    force: Option<bool>,
+) -> Result<GitBranch, String> {
+    let repo = open_repo(&path)?;
+    let force = force.unwrap_or(false);
+    if !force {
+        ensure_clean(&repo, &name)?;
+    }
+    let branch = match repo.find_branch(&name, BranchType::Local) {
+        Ok(branch) => branch,
+        Err(_) => {
+            let remote = repo
+                .find_branch(&name, BranchType::Remote)
+                .map_err(|_| format!("Branch {} not found", name))?;
+            let remote_name = repo
+                .branch_remote_name(&format!("refs/remotes/{}", name))
+                .ok()
+                .and_then(|buf| buf.as_str().map(|s| s.to_string()))
+                .ok_or_else(|| format!("Failed to find the remote of {}", name))?;
+            let local_name = &name[remote_name.len() + 1..];
+            match repo.find_branch(local_name, BranchType::Local) {
+                Ok(branch) => branch,
+                Err(_) => {
+                    let commit = remote
+                        .get()
+                        .peel_to_commit()
+                        .map_err(|e| format!("Failed to resolve {}: {}", name, e))?;
+                    let mut branch = repo
+                        .branch(local_name, &commit, false)
+                        .map_err(|e| branch_error(local_name, e))?;
+                    branch
+                        .set_upstream(Some(&name))
+                        .map_err(|e| format!("Failed to set upstream of {}: {}", local_name, e))?;
+                    branch
+                }
+            }
+        }
+    };
+    checkout_local(&repo, &branch, force)?;
+    branch_info(&repo, &branch, BranchType::Local)
+}
+
+/// Rename a local branch; its upstream setting moves with it.
+#[tauri::command]
+pub fn git_rename_branch(
+    path: String,
+    name: String,
+    new_name: String,
+) -> Result<GitBranch, String> {
+    let repo = open_repo(&path)?;
+    check_branch_name(&new_name)?;
+    let mut branch = find_local(&repo, &name)?;
+    let renamed = branch.rename(&new_name, false).map_err(|e| {
+        if e.code() == ErrorCode::Exists {
+            format!("Branch {} already exists", new_name)
+        } else {
+            format!("Failed to rename {}: {}", name, e)
+        }
+    })?;
+    branch_info(&repo, &renamed, BranchType::Local)
+}
+
+/// Whether every commit on the branch is reachable from its upstream, or from
+/// `HEAD` when it has none.
+fn is_merged(repo: &Repository, branch: &Branch) -> Result<bool, String> {
+    let Some(tip) = branch.get().target() else {
+        return Ok(true);
+    };
+    let into: Option<Oid> = match branch.upstream() {
+        Ok(upstream) => upstream.get().target(),
+        Err(_) => repo.head().ok().and_then(|head| head.target()),
+    };
+    match into {
+        Some(into) if into == tip => Ok(true),
+        Some(into) => repo
+            .graph_descendant_of(into, tip)
+            .map_err(|e| format!("Failed to compare branches: {}", e)),
+        None => Ok(false),
+    }
+}
+
+/// Delete a local branch. The checked-out branch can't be deleted, and one with
+/// unmerged commits only with `force`.
+#[tauri::command]
+pub fn git_delete_branch(path: String, name: String, force: Option<bool>) -> Result<(), String> {
+    let repo = open_repo(&path)?;
+    let mut branch = find_local(&repo, &name)?;
+    if branch.is_head() {
+        return Err(format!("Cannot delete the checked-out branch {}", name));
+    }
+    if !force.unwrap_or(false) && !is_merged(&repo, &branch)? {
+        return Err(format!(
+            "Branch {} is not fully merged; delete it with force to discard its commits",
+            name
+        ));
+    }
+    branch
+        .delete()
+        .map_err(|e| format!("Failed to delete branch {}: {}", name, e))
+}
+
+/// Blob id of `path` in `tree`, if it exists there.
+fn blob_at(tree: &Tree, path: &str) -> Option<Oid> {
+    tree.get_path(Path::new(path)).ok().map(|entry| entry.id())
+}
+
+/// Where `path` in `commit` came from if the commit added it: the old path when
+/// rename detection pairs it with a file removed from `parent`.
+fn renamed_from(
+    repo: &Repository,
+    parent: Option<&Tree>,
+    commit: &Tree,
+    path: &str,
+) -> Result<Option<String>, String> {
+    let mut diff = repo
+        .diff_tree_to_tree(parent, Some(commit), None)
+        .map_err(|e| format!("Failed to diff commit: {}", e))?;
+    let mut find = DiffFindOptions::new();
+    find.renames(true);
+    diff.find_similar(Some(&mut find))
+        .map_err(|e| format!("Failed to detect renames: {}", e))?;
+    let old_path = diff
+        .deltas()
+        .filter(|delta| delta.status() == Delta::Renamed)
+        .find(|delta| delta.new_file().path() == Some(Path::new(path)))
+        .and_then(|delta| {
+            delta
+                .old_file()
+                .path()
+                .map(|p| p.to_string_lossy().to_string())
+        });
+    Ok(old_path)
+}
+
+fn history_entry(
+    commit: &Commit,
+    path: &str,
+    old_path: Option<String>,
+    status: &str,
+) -> GitFileHistoryEntry {
+    let author = commit.author();
+    GitFileHistoryEntry {
+        oid: commit.id().to_string(),
+        message: commit.message().unwrap_or("").to_string(),
+        author_name: author.name().unwrap_or("").to_string(),
+        author_email: author.email().unwrap_or("").to_string(),
+        timestamp: author.when().seconds(),
+        path: path.to_string(),
+        old_path,
+        status: status.to_string(),
+    }
+}
+
+/// Commits that changed a file, newest first, following it across renames like
+/// `git log --follow`. Walks back from `rev` (defaults to `HEAD`); merges that kept
+/// one parent's version are skipped.
+#[tauri::command]
+pub fn git_file_history(
+    path: String,
+    file_path: String,
+    rev: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<GitFileHistoryEntry>, String> {
+    let repo = open_repo(&path)?;
+    if repo.is_empty().unwrap_or(true) {
+        return Ok(Vec::new());
+    }
+    let rev = rev.as_deref().unwrap_or("HEAD");
+    let start = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Failed to resolve revision {}: {}", rev, e))?;
+    let mut revwalk = repo
+        .revwalk()
+        .map_err(|e| format!("Failed to create revwalk: {}", e))?;
+    revwalk
+        .set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+        .and_then(|_| revwalk.push(start.id()))
+        .map_err(|e| format!("Failed to walk history: {}", e))?;
+
+    let limit = limit.unwrap_or(usize::MAX);
+    let mut current = file_path;
+    let mut entries = Vec::new();
+    for oid in revwalk {
+        if entries.len() >= limit {
+            break;
+        }
+        let oid = oid.map_err(|e| format!("Revwalk error: {}", e))?;
+        let commit = repo
+            .find_commit(oid)
+            .map_err(|e| format!("Failed to find commit: {}", e))?;
+        let tree = commit
+            .tree()
+            .map_err(|e| format!("Failed to read commit {}: {}", oid, e))?;
+        let parent_trees = commit
+            .parents()
+            .map(|parent| parent.tree())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read parents of {}: {}", oid, e))?;
+
+        let blob = blob_at(&tree, &current);
+        let parent_blobs: Vec<Option<Oid>> =
+            parent_trees.iter().map(|t| blob_at(t, &current)).collect();
+        if parent_blobs.contains(&blob) {
+            continue;
+        }
+        let first_parent = parent_trees.first();
+        match (parent_blobs.first().copied().flatten(), blob) {
+            (Some(_), Some(_)) => entries.push(history_entry(&commit, &current, None, "modified")),
+            (Some(_), None) => entries.push(history_entry(&commit, &current, None, "deleted")),
+            (None, Some(_)) => match renamed_from(&repo, first_parent, &tree, &current)? {
+                Some(old_path) => {
+                    entries.push(history_entry(
+                        &commit,
+                        &current,
+                        Some(old_path.clone()),
+                        "renamed",
+                    ));
+                    current = old_path;
+                }
+                None => entries.push(history_entry(&commit, &current, None, "new")),
+            },
+            (None, None) => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// A file's contents at any revision, or `None` if it doesn't exist there.
+#[tauri::command]
+pub fn git_show_file_at(
+    path: String,
+    file_path: String,
+    rev: String,
+) -> Result<Option<String>, String> {
+    let repo = open_repo(&path)?;
+    read_at_revision(&repo, &rev, Path::new(&file_path))
+}
+
+fn restore_file(
+    sandbox: &ProjectSandbox,
+    path: &str,
+    file_path: &str,
+    rev: &str,
+    force: bool,
+) -> Result<(), String> {
+    let repo = open_repo(path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "Repository has no working directory".to_string())?;
+    let target = sandbox
+        .resolve(&workdir.join(file_path).to_string_lossy())
+        .map_err(|e| e.to_string())?;
+    let content = read_bytes_at_revision(&repo, rev, Path::new(file_path))?
+        .ok_or_else(|| format!("{} does not exist at {}", file_path, rev))?;
+    if !force {
+        // Untracked counts too: the file would be lost just the same
+        let status = repo
+            .status_file(Path::new(file_path))
+            .unwrap_or(Status::CURRENT);
+        if !status.difference(Status::IGNORED).is_empty() {
+            return Err(format!(
+                "{} has uncommitted changes. Commit or discard them first, or restore with force",
+                file_path
+            ));
+        }
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    write_atomic(&target, &content).map_err(|e| format!("Failed to write {}: {}", file_path, e))
+}
+
+/// Bring a file back to its contents at `rev` in the working tree; the index is
+/// left alone. Refuses to overwrite uncommitted changes to the file unless `force`.
+/// The target must lie inside the open project.
+#[tauri::command]
+pub fn git_restore_file(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    file_path: String,
+    rev: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    restore_file(&sandbox, &path, &file_path, &rev, force.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// A repo on `main` with `file` committed.
+    fn repo_with(file: &str, content: &[u8]) -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-b", "main"]);
+        fs::write(dir.path().join(file), content).unwrap();
+        git(dir.path(), &["add", file]);
+        git(dir.path(), &["commit", "-m", "initial"]);
+        let path = dir.path().to_string_lossy().to_string();
+        (dir, path)
+    }
+
+    fn commit(dir: &Path, file: &str, content: &str) {
+        fs::write(dir.join(file), content).unwrap();
+        git(dir, &["add", file]);
+        git(dir, &["commit", "-m", file]);
+    }
+
+    fn branch<'a>(branches: &'a [GitBranch], name: &str) -> &'a GitBranch {
+        branches.iter().find(|b| b.name == name).unwrap()
+    }
+
+    fn numbered(lines: usize) -> String {
+        (1..=lines).map(|n| format!("line {}\n", n)).collect()
+    }
+
+    fn range(hunk: &GitDiffHunk) -> GitHunkRange {
+        GitHunkRange {
+            old_start: hunk.old_start,
+            old_lines: hunk.old_lines,
+            new_start: hunk.new_start,
+            new_lines: hunk.new_lines,
+        }
+    }
+
+    fn index_blob(path: &str, file: &str) -> String {
+        let repo = open_repo(path).unwrap();
+        let index = repo.index().unwrap();
+        let entry = index.get_path(Path::new(file), 0).unwrap();
+        let blob = repo.find_blob(entry.id).unwrap();
+        String::from_utf8(blob.content().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn stages_and_unstages_one_of_two_hunks() {
+        let original = numbered(20);
+        let (dir, path) = repo_with("a.txt", original.as_bytes());
+        let first = original.replace("line 2\n", "line two\n");
+        let both = first.replace("line 18\n", "line eighteen\n");
+        fs::write(dir.path().join("a.txt"), &both).unwrap();
+
+        let diff = git_diff_file(path.clone(), "a.txt".into(), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.hunks.len(), 2);
+        git_stage_hunk(path.clone(), "a.txt".into(), range(&diff.hunks[0]), None).unwrap();
+        assert_eq!(index_blob(&path, "a.txt"), first);
+
+        // The other hunk is still unstaged, and the working tree is untouched.
+        let unstaged = git_diff_file(path.clone(), "a.txt".into(), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(unstaged.hunks.len(), 1);
+        assert!(unstaged.hunks[0]
+            .lines
+            .iter()
+            .any(|line| line.content == "line eighteen"));
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), both);
+
+        let staged = git_diff_staged(path.clone(), Some("a.txt".into()), None).unwrap();
+        assert_eq!(staged[0].hunks.len(), 1);
+        git_unstage_hunk(
+            path.clone(),
+            "a.txt".into(),
+            range(&staged[0].hunks[0]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(index_blob(&path, "a.txt"), original);
+        assert!(git_diff_staged(path, None, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stale_hunks_are_refused() {
+        let original = numbered(20);
+        let (dir, path) = repo_with("a.txt", original.as_bytes());
+        fs::write(
+            dir.path().join("a.txt"),
+            original.replace("line 2\n", "line two\n"),
+        )
+        .unwrap();
+        let stale = GitHunkRange {
+            old_start: 15,
+            old_lines: 6,
+            new_start: 15,
+            new_lines: 6,
+        };
+        let err = git_stage_hunk(path.clone(), "a.txt".into(), stale, None).unwrap_err();
+        assert!(err.contains("Hunk not found"), "{}", err);
+        assert_eq!(index_blob(&path, "a.txt"), original);
+    }
+
+    #[test]
+    fn staged_renames_keep_the_old_path() {
+        let (dir, path) = repo_with("old.txt", numbered(20).as_bytes());
+        git(dir.path(), &["mv", "old.txt", "new.txt"]);
+
+        let staged = git_diff_staged(path, None, None).unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].status, "renamed");
+        assert_eq!(staged[0].path, "new.txt");
+        assert_eq!(staged[0].old_path.as_deref(), Some("old.txt"));
+        assert!(staged[0].hunks.is_empty());
+    }
+
+    #[test]
+    fn binary_files_have_no_hunks() {
+        let (dir, path) = repo_with("logo.png", &[0x89, b'P', b'N', b'G', 0, 1, 2]);
+        fs::write(
+            dir.path().join("logo.png"),
+            [0x89, b'P', b'N', b'G', 0, 3, 4],
+        )
+        .unwrap();
+
+        let diff = git_diff_file(path, "logo.png".into(), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.status, "modified");
+        assert!(diff.binary);
+        assert!(diff.hunks.is_empty());
+    }
+
+    #[test]
+    fn branches_count_commits_ahead_and_behind_their_upstream() {
+        let (dir, path) = repo_with("a.txt", b"a");
+        git(dir.path(), &["checkout", "-b", "feature"]);
+        git(dir.path(), &["branch", "--set-upstream-to=main"]);
+        commit(dir.path(), "b.txt", "b");
+        commit(dir.path(), "c.txt", "c");
+        git(dir.path(), &["checkout", "main"]);
+        commit(dir.path(), "d.txt", "d");
+        git(dir.path(), &["checkout", "feature"]);
+
+        let branches = git_list_branches(path).unwrap();
+        let feature = branch(&branches, "feature");
+        assert!(feature.is_head);
+        assert!(!feature.is_remote);
+        assert_eq!(feature.upstream.as_deref(), Some("main"));
+        assert_eq!((feature.ahead, feature.behind), (2, 1));
+        let last = feature.last_commit.as_ref().unwrap();
+        assert_eq!(last.message.trim(), "c.txt");
+        let main = branch(&branches, "main");
+        assert!(!main.is_head);
+        assert_eq!(
+            (main.upstream.as_deref(), main.ahead, main.behind),
+            (None, 0, 0)
+        );
+    }
+
+    #[test]
+    fn creates_checks_out_renames_and_deletes_branches() {
+        let (_dir, path) = repo_with("a.txt", b"a");
+
+        let topic = git_create_branch(path.clone(), "topic".into(), None, Some(true)).unwrap();
+        assert!(topic.is_head);
+        let main = git_checkout_branch(path.clone(), "main".into(), None).unwrap();
+        assert!(main.is_head);
+        let renamed = git_rename_branch(path.clone(), "topic".into(), "feature".into()).unwrap();
+        assert_eq!(renamed.name, "feature");
+        assert!(!renamed.is_head);
+
+        let err = git_create_branch(path.clone(), "feature".into(), None, None).unwrap_err();
+        assert_eq!(err, "Branch feature already exists");
+        let err = git_create_branch(path.clone(), "bad..name".into(), None, None).unwrap_err();
+        assert_eq!(err, "bad..name is not a valid branch name");
+
+        // Nothing on `feature` that `main` lacks, so no force needed.
+        git_delete_branch(path.clone(), "feature".into(), None).unwrap();
+        let names: Vec<String> = git_list_branches(path)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.name)
+            .collect();
+        assert_eq!(names, vec!["main"]);
+    }
+
+    #[test]
+    fn uncommitted_changes_block_a_checkout_unless_forced() {
+        let (dir, path) = repo_with("a.txt", b"a");
+        git(dir.path(), &["branch", "topic"]);
+        fs::write(dir.path().join("a.txt"), "edited").unwrap();
+
+        let err = git_checkout_branch(path.clone(), "topic".into(), None).unwrap_err();
+        assert_eq!(
+            err,
+            "Cannot switch to topic: 1 file(s) have uncommitted changes. Commit or stash them first"
+        );
+        let err = git_create_branch(path.clone(), "other".into(), None, Some(true)).unwrap_err();
+        assert!(err.starts_with("Cannot switch to other:"), "{}", err);
+        let repo = open_repo(&path).unwrap();
+        assert_eq!(repo.head().unwrap().shorthand(), Some("main"));
+        assert!(repo.find_branch("other", BranchType::Local).is_err());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "edited"
+        );
+
+        // Untracked files don't count
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("new.txt"), "new").unwrap();
+        git_checkout_branch(path.clone(), "topic".into(), None).unwrap();
+
+        fs::write(dir.path().join("a.txt"), "edited").unwrap();
+        git_checkout_branch(path, "main".into(), Some(true)).unwrap();
+        assert_eq!(repo.head().unwrap().shorthand(), Some("main"));
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a");
+    }
+
+    #[test]
+    fn unmerged_and_checked_out_branches_are_not_deleted() {
+        let (dir, path) = repo_with("a.txt", b"a");
+        git(dir.path(), &["checkout", "-b", "topic"]);
+        commit(dir.path(), "b.txt", "b");
+
+        let err = git_delete_branch(path.clone(), "topic".into(), None).unwrap_err();
+        assert_eq!(err, "Cannot delete the checked-out branch topic");
+
+        git(dir.path(), &["checkout", "main"]);
+        let err = git_delete_branch(path.clone(), "topic".into(), None).unwrap_err();
+        assert_eq!(
+            err,
+            "Branch topic is not fully merged; delete it with force to discard its commits"
+        );
+        let repo = open_repo(&path).unwrap();
+        assert!(repo.find_branch("topic", BranchType::Local).is_ok());
+
+        git_delete_branch(path, "topic".into(), Some(true)).unwrap();
+        assert!(repo.find_branch("topic", BranchType::Local).is_err());
+    }
+
+    #[test]
+    fn file_history_follows_renames() {
+        let (dir, path) = repo_with("old.txt", numbered(20).as_bytes());
+        commit(dir.path(), "old.txt", &numbered(21));
+        commit(dir.path(), "other.txt", "unrelated");
+        git(dir.path(), &["mv", "old.txt", "new.txt"]);
+        commit(
+            dir.path(),
+            "new.txt",
+            &numbered(21).replace("line 1\n", "line one\n"),
+        );
+        commit(dir.path(), "new.txt", &numbered(22));
+
+        let history = git_file_history(path.clone(), "new.txt".into(), None, None).unwrap();
+        let steps: Vec<(&str, &str, Option<&str>)> = history
+            .iter()
+            .map(|e| (e.status.as_str(), e.path.as_str(), e.old_path.as_deref()))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("modified", "new.txt", None),
+                ("renamed", "new.txt", Some("old.txt")),
+                ("modified", "old.txt", None),
+                ("new", "old.txt", None),
+            ]
+        );
+        assert_eq!(history[3].message.trim(), "initial");
+        assert_eq!(history[0].author_email, "test@example.com");
+
+        let limited = git_file_history(path, "new.txt".into(), None, Some(2)).unwrap();
+        assert_eq!(limited.len(), 2);
+    }
+
+    #[test]
+    fn restore_keeps_exact_bytes() {
+        let crlf: &[u8] = b"name: Billing\r\nowner: team\r\n";
+        let binary: &[u8] = &[0x89, b'P', b'N', b'G', 0, b'\r', b'\n', 0xff];
+        let (dir, path) = repo_with("domain.yaml", crlf);
+        fs::write(dir.path().join("logo.png"), binary).unwrap();
+        git(dir.path(), &["add", "logo.png"]);
+        git(dir.path(), &["commit", "-m", "logo"]);
+        fs::write(dir.path().join("domain.yaml"), "name: Billing\n").unwrap();
+        fs::write(dir.path().join("logo.png"), "gone").unwrap();
+        git(dir.path(), &["commit", "-am", "replace"]);
+
+        let sandbox = ProjectSandbox::new(Vec::new());
+        sandbox.approve(dir.path());
+        sandbox.set_root(&path, false).unwrap();
+        restore_file(&sandbox, &path, "domain.yaml", "HEAD~1", false).unwrap();
+        restore_file(&sandbox, &path, "logo.png", "HEAD~1", false).unwrap();
+        assert_eq!(fs::read(dir.path().join("domain.yaml")).unwrap(), crlf);
+        assert_eq!(fs::read(dir.path().join("logo.png")).unwrap(), binary);
+
+        // Now both have uncommitted changes
+        let err = restore_file(&sandbox, &path, "logo.png", "HEAD", false).unwrap_err();
+        assert_eq!(
+            err,
+            "logo.png has uncommitted changes. Commit or discard them first, or restore with force"
+        );
+        restore_file(&sandbox, &path, "logo.png", "HEAD", true).unwrap();
+        assert_eq!(fs::read(dir.path().join("logo.png")).unwrap(), b"gone");
+
+        let err = restore_file(&sandbox, &path, "missing.txt", "HEAD", true).unwrap_err();
+        assert_eq!(err, "missing.txt does not exist at HEAD");
+    }
+}
@@ -0,0 +1,679 @@
+use git2::{Branch, Repository};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+/// Tauri event carrying output lines of a running fetch, pull or push.
+pub const GIT_PROGRESS_EVENT: &str = "git-progress";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Receives each output line of a git run with its progress percentage.
+type LineSink = Arc<dyn Fn(&str, Option<u8>) + Send + Sync>;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GitProgressEvent {
+    pub operation_id: String,
+    /// "fetch" | "pull" | "push"
+    pub operation: String,
+    pub line: String,
+    /// Percentage of a progress line such as `Receiving objects:  45% (9/20)`.
+    pub percent: Option<u8>,
+}
+
+/// Cancel flags of the running remote operations, keyed by operation id.
+#[derive(Default)]
+pub struct GitOperations {
+    active: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PullStrategy {
+    Rebase,
+    Merge,
+}
+
+/// Failure of a remote operation, classified from git's output. `detail` holds
+/// git's own error lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitRemoteError {
+    GitUnavailable { detail: String },
+    InvalidArgument { detail: String },
+    Cancelled,
+    NonFastForward { detail: String },
+    AuthFailed { detail: String },
+    RemoteNotFound { detail: String },
+    NoUpstream { detail: String },
+    DirtyWorkTree { detail: String },
+    Conflict { detail: String },
+    Network { detail: String },
+    Failed { detail: String },
+}
+
+impl GitRemoteError {
+    /// Stable machine-readable code, also used as the message prefix on the wire.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GitRemoteError::GitUnavailable { .. } => "git_unavailable",
+            GitRemoteError::InvalidArgument { .. } => "invalid_argument",
+            GitRemoteError::Cancelled => "cancelled",
+            GitRemoteError::NonFastForward { .. } => "non_fast_forward",
+            GitRemoteError::AuthFailed { .. } => "auth_failed",
+            GitRemoteError::RemoteNotFound { .. } => "remote_not_found",
+            GitRemoteError::NoUpstream { .. } => "no_upstream",
+            GitRemoteError::DirtyWorkTree { .. } => "dirty_work_tree",
+            GitRemoteError::Conflict { .. } => "conflict",
+            GitRemoteError::Network { .. } => "network",
+            GitRemoteError::Failed { .. } => "failed",
+        }
+    }
+
+    /// Classify a failed git run from its error output.
+    fn from_output(detail: String) -> Self {
+        let has = |needles: &[&str]| needles.iter().any(|n| detail.contains(n));
+        if has(&[
+            "(non-fast-forward)",
+            "(fetch first)",
+            "Updates were rejected",
+        ]) {
+            GitRemoteError::NonFastForward { detail }
+        } else if has(&[
+            "Authentication failed",
+            "Permission denied",
+            "could not read Username",
+            "could not read Password",
+            "terminal prompts disabled",
+            "returned error: 401",
+            "returned error: 403",
+        ]) {
+            GitRemoteError::AuthFailed { detail }
+        } else if has(&[
+            "does not appear to be a git repository",
+            "Repository not found",
+            "repository not found",
+            "returned error: 404",
+        ]) {
+            GitRemoteError::RemoteNotFound { detail }
+        } else if has(&["has no upstream branch", "There is no tracking information"]) {
+            GitRemoteError::NoUpstream { detail }
+        } else if has(&[
+            "would be overwritten",
+            "You have unstaged changes",
+            "Your index contains uncommitted changes",
+            "commit or stash them",
+        ]) {
+            GitRemoteError::DirtyWorkTree { detail }
+        } else if has(&["CONFLICT", "could not apply", "Automatic merge failed"]) {
+            GitRemoteError::Conflict { detail }
+        } else if has(&[
+            "Could not resolve host",
+            "Connection refused",
+            "Connection timed out",
+            "Network is unreachable",
+            "unable to access",
+            "Could not read from remote repository",
+        ]) {
+            GitRemoteError::Network { detail }
+        } else {
+            GitRemoteError::Failed { detail }
+        }
+    }
+}
+
+impl fmt::Display for GitRemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.kind())?;
+        let (summary, detail) = match self {
+            GitRemoteError::GitUnavailable { detail } => {
+                ("Failed to run git. Is git installed?", detail)
+            }
+            GitRemoteError::InvalidArgument { detail } => {
+                ("Invalid remote or branch name.", detail)
+            }
+            GitRemoteError::Cancelled => return write!(f, "Cancelled"),
+            GitRemoteError::NonFastForward { detail } => (
+                "Push rejected: the remote has commits you don't have. Pull, then push again.",
+                detail,
+            ),
+            GitRemoteError::AuthFailed { detail } => (
+                "Authentication with the remote failed. Check your credentials or SSH key.",
+                detail,
+            ),
+            GitRemoteError::RemoteNotFound { detail } => ("Remote repository not found.", detail),
+            GitRemoteError::NoUpstream { detail } => {
+                ("The current branch has no upstream branch.", detail)
+            }
+            GitRemoteError::DirtyWorkTree { detail } => (
+                "Local changes would be overwritten. Commit or stash them first.",
+                detail,
+            ),
+            GitRemoteError::Conflict { detail } => (
+                "Pull stopped on conflicts. Resolve them and continue, or abort.",
+                detail,
+            ),
+            GitRemoteError::Network { detail } => ("Could not reach the remote.", detail),
+            GitRemoteError::Failed { detail } => ("Git failed.", detail),
+        };
+        if detail.is_empty() {
+            write!(f, "{}", summary)
+        } else {
+            write!(f, "{}\n{}", summary, detail)
+        }
+    }
+}
+
+impl std::error::Error for GitRemoteError {}
+
+impl Serialize for GitRemoteError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// The percentage in a progress line, e.g. 45 for `Receiving objects:  45% (9/20)`.
+fn progress_percent(line: &str) -> Option<u8> {
+    let end = line.find('%')?;
+    let digits = line[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    line[digits..end].parse().ok()
+}
+
+/// Read `stream` to the end, splitting on `\r` as well as `\n` since git redraws
+/// progress lines in place. Each non-empty line goes to `on_line`.
+fn read_lines(mut stream: impl Read, mut on_line: impl FnMut(String)) -> io::Result<()> {
+    let mut buf = [0u8; 4096];
+    let mut pending = Vec::new();
+    loop {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for &byte in &buf[..n] {
+            if byte == b'\r' || byte == b'\n' {
+                let line = String::from_utf8_lossy(&pending).trim_end().to_string();
+                if !line.is_empty() {
+                    on_line(line);
+                }
+                pending.clear();
+            } else {
+                pending.push(byte);
+            }
+        }
+    }
+    let line = String::from_utf8_lossy(&pending).trim_end().to_string();
+    if !line.is_empty() {
+        on_line(line);
+    }
+    Ok(())
+}
+
+/// Read `stream` on its own thread, passing each line to `on_line` and keeping
+/// the ones that aren't progress in `output`.
+fn spawn_reader(
+    stream: impl Read + Send + 'static,
+    output: Arc<Mutex<Vec<String>>>,
+    on_line: LineSink,
+) -> thread::JoinHandle<io::Result<()>> {
+    thread::spawn(move || {
+        read_lines(stream, |line| {
+            let percent = progress_percent(&line);
+            on_line(&line, percent);
+            if percent.is_none() {
+                output.lock().unwrap_or_else(|e| e.into_inner()).push(line);
+            }
+        })
+    })
+}
+
+/// Stop git along with the helpers it started (ssh, git-remote-https), which on
+/// unix share its process group.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill(2) has no memory effects; a negative pid names the group.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Run system git in `path`, calling `on_line` with every output line as it arrives,
+/// until it exits or `cancel` is set. Returns git's output without the progress lines.
+fn run_git(
+    path: &str,
+    args: &[String],
+    cancel: &AtomicBool,
+    on_line: impl Fn(&str, Option<u8>) + Send + Sync + 'static,
+) -> Result<String, GitRemoteError> {
+    // System git inherits the user's auth setup (keychain, SSH agent, credential
+    // helpers). Prompts are disabled since nobody can answer them, and messages are
+    // kept in English so failures can be classified.
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .current_dir(path)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .spawn()
+        .map_err(|e| GitRemoteError::GitUnavailable {
+            detail: e.to_string(),
+        })?;
+
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let on_line: LineSink = Arc::new(on_line);
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(spawn_reader(
+            stdout,
+            Arc::clone(&output),
+            Arc::clone(&on_line),
+        ));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(spawn_reader(
+            stderr,
+            Arc::clone(&output),
+            Arc::clone(&on_line),
+        ));
+    }
+    // On failure the readers are left to finish on their own: a helper outliving
+    // git can hold the pipes open indefinitely.
+    let status = loop {
+        if cancel.load(Ordering::Relaxed) {
+            kill(&mut child);
+            return Err(GitRemoteError::Cancelled);
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                kill(&mut child);
+                return Err(GitRemoteError::Failed {
+                    detail: e.to_string(),
+                });
+            }
+        }
+    };
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    let output = output.lock().unwrap_or_else(|e| e.into_inner()).join("\n");
+    if status.success() {
+        Ok(output)
+    } else {
+        Err(GitRemoteError::from_output(output))
+    }
+}
+
+/// Run a remote operation registered under `operation_id`, emitting its output as
+/// `git-progress` events.
+async fn run_operation(
+    app: AppHandle,
+    operations: &GitOperations,
+    operation: &'static str,
+    operation_id: String,
+    path: String,
+    args: Vec<String>,
+) -> Result<String, GitRemoteError> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut active = operations.active.lock().unwrap_or_else(|e| e.into_inner());
+        if active.contains_key(&operation_id) {
+            return Err(GitRemoteError::Failed {
+                detail: format!("Operation {} is already running", operation_id),
+            });
+        }
+        active.insert(operation_id.clone(), Arc::clone(&cancel));
+    }
+
+    let id = operation_id.clone();
+    let flag = Arc::clone(&cancel);
+    let result = tokio::task::spawn_blocking(move || {
+        run_git(&path, &args, &flag, move |line, percent| {
+            let _ = app.emit(
+                GIT_PROGRESS_EVENT,
+                GitProgressEvent {
+                    operation_id: id.clone(),
+                    operation: operation.to_string(),
+                    line: line.to_string(),
+                    percent,
+                },
+            );
+        })
+    })
+    .await
+    .map_err(|e| GitRemoteError::Failed {
+        detail: format!("{} failed: {}", operation, e),
+    });
+
+    operations
+        .active
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&operation_id);
+    result?
+}
+
+/// Check the remote and branch a caller passed before they go into git's argv,
+/// where a value such as `--upload-pack=<command>` would be taken as an option.
+/// `remote` must be configured in the repo at `path`.
+fn check_target(
+    path: &str,
+    remote: Option<&str>,
+    branch: Option<&str>,
+) -> Result<(), GitRemoteError> {
+    let invalid = |detail: String| GitRemoteError::InvalidArgument { detail };
+    if let Some(remote) = remote {
+        if remote.starts_with('-') {
+            return Err(invalid(format!("Remote name {} starts with '-'", remote)));
+        }
+        let repo = Repository::discover(path)
+            .map_err(|e| invalid(format!("Failed to open repo at {}: {}", path, e)))?;
+        if repo.find_remote(remote).is_err() {
+            return Err(GitRemoteError::RemoteNotFound {
+                detail: format!("No remote named {}", remote),
+            });
+        }
+    }
+    if let Some(branch) = branch {
+        if branch.starts_with('-') || !Branch::name_is_valid(branch).unwrap_or(false) {
+            return Err(invalid(format!("{} is not a valid branch name", branch)));
+        }
+    }
+    Ok(())
+}
+
+/// Append `remote` and `branch` after `--` so git never reads them as options.
+fn push_target(args: &mut Vec<String>, remote: Option<String>, branch: Option<String>) {
+    if let Some(remote) = remote {
+        args.push("--".into());
+        args.push(remote);
+        args.extend(branch);
+    }
+}
+
+/// Fetch from `remote` (the current branch's remote or `origin` when absent), or
+/// from every remote with `all`. Returns git's output.
+#[tauri::command]
+pub async fn git_fetch(
+    app: AppHandle,
+    operations: State<'_, GitOperations>,
+    path: String,
+    operation_id: String,
+    remote: Option<String>,
+    all: Option<bool>,
+    prune: Option<bool>,
+) -> Result<String, GitRemoteError> {
+    let mut args: Vec<String> = vec!["fetch".into(), "--progress".into()];
+    if prune.unwrap_or(false) {
+        args.push("--prune".into());
+    }
+    if all.unwrap_or(false) {
+        args.push("--all".into());
+    } else {
+        check_target(&path, remote.as_deref(), None)?;
+        push_target(&mut args, remote, None);
+    }
+    run_operation(app, &operations, "fetch", operation_id, path, args).await
+}
+
+/// Pull into the current branch, rebasing onto or merging the fetched commits.
+/// Without `remote` the branch's upstream is used. Conflicts leave the rebase or
+/// merge in progress, as on the command line.
+#[tauri::command]
+pub async fn git_pull(
+    app: AppHandle,
+    operations: State<'_, GitOperations>,
+    path: String,
+    operation_id: String,
+    strategy: PullStrategy,
+    remote: Option<String>,
+    branch: Option<String>,
+) -> Result<String, GitRemoteError> {
+    let mut args: Vec<String> = vec!["pull".into(), "--progress".into()];
+    match strategy {
+        PullStrategy::Rebase => args.push("--rebase".into()),
+        // No editor for the merge commit message
+        PullStrategy::Merge => args.extend(["--no-rebase".into(), "--no-edit".into()]),
+    }
+    check_target(&path, remote.as_deref(), branch.as_deref())?;
+    push_target(&mut args, remote, branch);
+    run_operation(app, &operations, "pull", operation_id, path, args).await
+}
+
+/// Push the current branch, or `branch` to `remote`. `set_upstream` pushes `HEAD`
+/// to a branch of the same name (on `origin` unless `remote` is given) and tracks
+/// it. `force_with_lease` overwrites the remote branch only if it is where we
+/// last saw it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn git_push(
+    app: AppHandle,
+    operations: State<'_, GitOperations>,
+    path: String,
+    operation_id: String,
+    remote: Option<String>,
+    branch: Option<String>,
+    set_upstream: Option<bool>,
+    force_with_lease: Option<bool>,
+) -> Result<String, GitRemoteError> {
+    let mut args: Vec<String> = vec!["push".into(), "--progress".into()];
+    if force_with_lease.unwrap_or(false) {
+        args.push("--force-with-lease".into());
+    }
+    check_target(&path, remote.as_deref(), branch.as_deref())?;
+    if set_upstream.unwrap_or(false) {
+        args.extend(["--set-upstream".into(), "--".into()]);
+        args.push(remote.unwrap_or_else(|| "origin".to_string()));
+        args.push(branch.unwrap_or_else(|| "HEAD".to_string()));
+    } else {
+        push_target(&mut args, remote, branch);
+    }
+    run_operation(app, &operations, "push", operation_id, path, args).await
+}
+
+/// Stop a running fetch, pull or push. A pull cancelled mid-way may leave a rebase
+/// or merge in progress.
+#[tauri::command]
+pub fn cancel_git_operation(
+    operations: State<'_, GitOperations>,
+    operation_id: String,
+) -> Result<(), String> {
+    let cancel = operations
+        .active
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&operation_id)
+        .cloned()
+        .ok_or_else(|| format!("Git operation {} is not running", operation_id))?;
+    cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::time::Instant;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// A clone of a fresh bare repo with one pushed commit on `main`.
+    fn clone_of_bare(root: &Path, name: &str) -> std::path::PathBuf {
+        let remote = root.join("remote.git");
+        if !remote.exists() {
+            git(root, &["init", "--bare", "-b", "main", "remote.git"]);
+        }
+        git(root, &["clone", "remote.git", name]);
+        let clone = root.join(name);
+        git(&clone, &["checkout", "-B", "main"]);
+        clone
+    }
+
+    fn commit(dir: &Path, file: &str) {
+        std::fs::write(dir.join(file), file).unwrap();
+        git(dir, &["add", file]);
+        git(dir, &["commit", "-m", file]);
+    }
+
+    fn run(dir: &Path, args: Vec<String>) -> Result<String, GitRemoteError> {
+        run_git(
+            dir.to_str().unwrap(),
+            &args,
+            &AtomicBool::new(false),
+            |_, _| {},
+        )
+    }
+
+    fn push_args(remote: &str, branch: &str) -> Vec<String> {
+        let mut args = vec!["push".to_string()];
+        push_target(&mut args, Some(remote.into()), Some(branch.into()));
+        args
+    }
+
+    #[test]
+    fn progress_percent_reads_the_percentage() {
+        assert_eq!(progress_percent("Receiving objects:  45% (9/20)"), Some(45));
+        assert_eq!(
+            progress_percent("remote: Counting objects: 100% (3/3), done."),
+            Some(100)
+        );
+        assert_eq!(progress_percent("Already up to date."), None);
+        assert_eq!(progress_percent("100%"), Some(100));
+    }
+
+    #[test]
+    fn read_lines_splits_on_carriage_returns() {
+        let input: &[u8] = b"Receiving:  10%\rReceiving:  50%\r\nDone  \n\nlast";
+        let mut lines = Vec::new();
+        read_lines(input, |line| lines.push(line)).unwrap();
+        assert_eq!(
+            lines,
+            ["Receiving:  10%", "Receiving:  50%", "Done", "last"]
+        );
+    }
+
+    #[test]
+    fn failures_are_classified_from_git_output() {
+        let kind = |detail: &str| GitRemoteError::from_output(detail.to_string()).kind();
+        assert_eq!(
+            kind(" ! [rejected]        main -> main (fetch first)"),
+            "non_fast_forward"
+        );
+        assert_eq!(
+            kind("fatal: Authentication failed for 'https://example.com/'"),
+            "auth_failed"
+        );
+        assert_eq!(
+            kind("fatal: 'nope' does not appear to be a git repository"),
+            "remote_not_found"
+        );
+        assert_eq!(
+            kind("fatal: The current branch topic has no upstream branch."),
+            "no_upstream"
+        );
+        assert_eq!(
+            kind("error: cannot pull with rebase: You have unstaged changes."),
+            "dirty_work_tree"
+        );
+        assert_eq!(
+            kind("CONFLICT (content): Merge conflict in a.txt"),
+            "conflict"
+        );
+        assert_eq!(
+            kind("fatal: unable to access 'https://example.invalid/': Could not resolve host"),
+            "network"
+        );
+        assert_eq!(kind("fatal: something else"), "failed");
+    }
+
+    #[test]
+    fn option_like_targets_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let clone = clone_of_bare(dir.path(), "clone");
+        let path = clone.to_str().unwrap();
+
+        assert!(check_target(path, Some("origin"), Some("main")).is_ok());
+        for (remote, branch) in [
+            (Some("--upload-pack=touch pwned"), None),
+            (Some("origin"), Some("--receive-pack=touch pwned")),
+            (Some("origin"), Some("bad..name")),
+        ] {
+            let err = check_target(path, remote, branch).unwrap_err();
+            assert_eq!(err.kind(), "invalid_argument");
+        }
+        let err = check_target(path, Some("upstream"), None).unwrap_err();
+        assert_eq!(err.kind(), "remote_not_found");
+    }
+
+    #[test]
+    fn push_behind_the_remote_is_non_fast_forward() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = clone_of_bare(dir.path(), "first");
+        let second = clone_of_bare(dir.path(), "second");
+        commit(&first, "a.txt");
+        run(&first, push_args("origin", "main")).unwrap();
+
+        commit(&second, "b.txt");
+        let err = run(&second, push_args("origin", "main")).unwrap_err();
+        assert_eq!(err.kind(), "non_fast_forward");
+        assert!(err.to_string().starts_with("[non_fast_forward] "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancel_returns_while_a_helper_holds_the_pipes() {
+        let dir = tempfile::tempdir().unwrap();
+        let clone = clone_of_bare(dir.path(), "clone");
+        // The ssh "helper" sleeps with git's stderr still open.
+        git(&clone, &["config", "ssh.variant", "simple"]);
+        git(&clone, &["config", "core.sshCommand", "sleep 30;:"]);
+        git(
+            &clone,
+            &["remote", "add", "slow", "ssh://example.invalid/repo.git"],
+        );
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            flag.store(true, Ordering::Relaxed);
+        });
+        let started = Instant::now();
+        let args = vec!["fetch".to_string(), "--".to_string(), "slow".to_string()];
+        let result = run_git(clone.to_str().unwrap(), &args, &cancel, |_, _| {});
+
+        assert_eq!(result, Err(GitRemoteError::Cancelled));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
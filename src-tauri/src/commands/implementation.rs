@@ -9,13 +9,18 @@ pub struct CommandOutput {
     pub exit_code: i32,
 }
 
+/// Hex-encoded SHA-256 of `content`, the format stored in `.ddd/mapping.yaml`.
+pub fn hash_bytes(content: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content);
+    let result = hasher.finalize();
+    format!("{:x}", result)
+}
+
 #[tauri::command]
 pub fn compute_file_hash(path: String) -> Result<String, String> {
     let content = fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))?;
-    let mut hasher = Sha256::new();
-    hasher.update(&content);
-    let result = hasher.finalize();
-    Ok(format!("{:x}", result))
+    Ok(hash_bytes(&content))
 }
 
 #[tauri::command]
@@ -0,0 +1,591 @@
+use crate::sandbox::ProjectSandbox;
+use chrono::{DateTime, Local};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+/// Tauri event carrying new lines from a followed log.
+pub const LOG_TAIL_EVENT: &str = "log-tail";
+
+const DEFAULT_PAGE_SIZE: usize = 500;
+const DEFAULT_POLL_MS: u64 = 500;
+
+static TAIL_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// One log record as written by the frontend's `pushError`:
+/// `[<ISO timestamp>] [<LEVEL>] [<component>] <message> — <detail>`.
+/// Lines that don't follow the format are kept with only `message` and `raw` set.
+#[derive(Serialize, Clone, Debug)]
+pub struct LogEntry {
+    pub timestamp: Option<String>,
+    /// `timestamp` in milliseconds since the Unix epoch.
+    pub timestamp_ms: Option<i64>,
+    pub level: Option<String>,
+    pub component: Option<String>,
+    pub message: String,
+    pub detail: Option<String>,
+    pub raw: String,
+    /// File name of the segment the entry was read from.
+    pub segment: String,
+    /// 1-based line number within the segment.
+    pub line: usize,
+}
+
+#[derive(Deserialize, Default, Clone)]
+pub struct LogQuery {
+    /// Accepted levels, case-insensitive. All levels when empty.
+    #[serde(default)]
+    pub levels: Vec<String>,
+    /// Accepted components, case-insensitive. All components when empty.
+    #[serde(default)]
+    pub components: Vec<String>,
+    pub since_ms: Option<i64>,
+    pub until_ms: Option<i64>,
+    /// Case-insensitive substring matched against the whole entry.
+    pub contains: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// Page from the most recent entry backwards.
+    #[serde(default)]
+    pub newest_first: bool,
+}
+
+#[derive(Serialize)]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    /// Number of entries matching the filters across all segments.
+    pub total: usize,
+    pub offset: usize,
+    pub has_more: bool,
+    /// Segment file names read, oldest first.
+    pub segments: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct LogTailEvent {
+    pub tail_id: String,
+    pub entries: Vec<LogEntry>,
+    /// Why the log couldn't be read on this poll; the tail keeps following it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Follow threads of the running tails, keyed by tail id. Setting the flag stops the thread.
+#[derive(Default)]
+pub struct LogTails {
+    active: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// Rotated segments of `path` (`<name>.N` … `<name>.1`, plain or `.gz`) followed by
+/// the live file, oldest first. Missing files are skipped.
+pub fn log_segments(path: &Path) -> Vec<PathBuf> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let mut rotated: Vec<(u32, PathBuf)> = fs::read_dir(parent)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let file_name = e.file_name().to_string_lossy().to_string();
+                    let suffix = file_name.strip_prefix(&prefix)?;
+                    let index = suffix.strip_suffix(".gz").unwrap_or(suffix);
+                    Some((index.parse::<u32>().ok()?, e.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    rotated.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+
+    let mut segments: Vec<PathBuf> = rotated.into_iter().map(|(_, p)| p).collect();
+    if path.is_file() {
+        segments.push(path.to_path_buf());
+    }
+    segments
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "gz")
+}
+
+/// Contents of a segment, decompressed when it is a `.gz` file.
+fn read_segment(path: &Path) -> io::Result<Vec<u8>> {
+    let file = File::open(path)?;
+    let mut bytes = Vec::new();
+    if is_gzip(path) {
+        GzDecoder::new(file).read_to_end(&mut bytes)?;
+    } else {
+        io::BufReader::new(file).read_to_end(&mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+fn rotated_path(path: &Path, index: u32, gzip: bool) -> PathBuf {
+    PathBuf::from(format!(
+        "{}.{}{}",
+        path.to_string_lossy(),
+        index,
+        if gzip { ".gz" } else { "" }
+    ))
+}
+
+/// The rotated segment at `index`, whichever of the plain and compressed forms exists.
+fn existing_segment(path: &Path, index: u32) -> Option<PathBuf> {
+    [false, true]
+        .into_iter()
+        .map(|gzip| rotated_path(path, index, gzip))
+        .find(|p| p.is_file())
+}
+
+/// Optional rotation settings of `append_log`, on top of its size and file limits.
+#[derive(Deserialize, Default, Clone, Copy)]
+pub struct LogRotationOptions {
+    #[serde(default)]
+    pub compress: bool,
+    #[serde(default)]
+    pub daily: bool,
+    pub max_total_bytes: Option<u64>,
+}
+
+/// How `append_log` rotates a log file.
+#[derive(Clone, Copy, Debug)]
+pub struct RotationPolicy {
+    /// Rotate once the live file reaches this size.
+    pub max_bytes: u64,
+    /// Total number of files kept, the live file included.
+    pub max_files: u32,
+    /// Gzip segments as they are rotated out.
+    pub compress: bool,
+    /// Also rotate when the live file was last written on an earlier (local) day.
+    pub daily: bool,
+    /// Drop the oldest segments until all files together fit in this many bytes.
+    pub max_total_bytes: Option<u64>,
+}
+
+fn needs_rotation(path: &Path, policy: &RotationPolicy) -> io::Result<bool> {
+    let meta = match fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if meta.len() == 0 {
+        return Ok(false);
+    }
+    if meta.len() >= policy.max_bytes {
+        return Ok(true);
+    }
+    if policy.daily {
+        let written: DateTime<Local> = meta.modified()?.into();
+        return Ok(written.date_naive() != Local::now().date_naive());
+    }
+    Ok(false)
+}
+
+/// Compress `plain` to `<plain>.gz` and remove the original. The `.gz` only appears
+/// once complete, so readers never see a truncated archive.
+fn gzip_segment(plain: &Path) -> io::Result<()> {
+    let target = PathBuf::from(format!("{}.gz", plain.to_string_lossy()));
+    let partial = PathBuf::from(format!("{}.gz.tmp", plain.to_string_lossy()));
+    let result = (|| {
+        let mut input = File::open(plain)?;
+        let mut encoder = GzEncoder::new(File::create(&partial)?, Compression::default());
+        io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?.sync_all()?;
+        fs::rename(&partial, &target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result?;
+    fs::remove_file(plain)
+}
+
+/// Shift `.1..N` up by one, dropping the oldest, and move the live file to `.1`.
+fn rotate(path: &Path, policy: &RotationPolicy) -> io::Result<()> {
+    let keep = policy.max_files.saturating_sub(1);
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    for gzip in [false, true] {
+        let oldest = rotated_path(path, keep, gzip);
+        if oldest.is_file() {
+            fs::remove_file(&oldest)?;
+        }
+    }
+    for index in (1..keep).rev() {
+        if let Some(from) = existing_segment(path, index) {
+            fs::rename(&from, rotated_path(path, index + 1, is_gzip(&from)))?;
+        }
+    }
+    let first = rotated_path(path, 1, false);
+    fs::rename(path, &first)?;
+    if policy.compress {
+        // The plain segment is still readable, so a failed compression loses nothing.
+        let _ = gzip_segment(&first);
+    }
+    Ok(())
+}
+
+/// Remove the oldest rotated segments until the log fits in `budget` bytes.
+/// The live file is never removed.
+fn enforce_budget(path: &Path, budget: u64) -> io::Result<()> {
+    let segments = log_segments(path);
+    let mut total: u64 = segments
+        .iter()
+        .filter_map(|s| fs::metadata(s).ok())
+        .map(|m| m.len())
+        .sum();
+    for segment in segments.iter().filter(|s| s.as_path() != path) {
+        if total <= budget {
+            break;
+        }
+        let len = fs::metadata(segment).map(|m| m.len()).unwrap_or(0);
+        fs::remove_file(segment)?;
+        total = total.saturating_sub(len);
+    }
+    Ok(())
+}
+
+/// Append `line` to `path`, rotating first when the policy asks for it. Rotation and
+/// the write happen under an exclusive lock on `<path>.lock`, so concurrent writers
+/// (other windows or app instances) can't interleave renames and lose lines.
+pub fn append_rotating(path: &Path, line: &str, policy: &RotationPolicy) -> io::Result<()> {
+    let lock_path = PathBuf::from(format!("{}.lock", path.to_string_lossy()));
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    lock.lock()?;
+
+    if needs_rotation(path, policy)? {
+        rotate(path, policy)?;
+        if let Some(budget) = policy.max_total_bytes {
+            enforce_budget(path, budget)?;
+        }
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+fn segment_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Take a leading `[...]` group off `s`, returning its contents and the rest.
+fn take_bracket(s: &str) -> Option<(&str, &str)> {
+    let rest = s.strip_prefix('[')?;
+    let end = rest.find(']')?;
+    Some((&rest[..end], rest[end + 1..].trim_start()))
+}
+
+/// Parse a structured line; `None` when it doesn't start with a timestamp, in which
+/// case it continues the previous entry (multi-line details).
+pub fn parse_log_line(raw: &str, segment: &str, line: usize) -> Option<LogEntry> {
+    let (timestamp, rest) = take_bracket(raw)?;
+    let parsed = DateTime::parse_from_rfc3339(timestamp).ok()?;
+    let (level, rest) = match take_bracket(rest) {
+        Some((level, rest)) => (Some(level.to_string()), rest),
+        None => (None, rest),
+    };
+    let (component, rest) = match level.as_ref().and_then(|_| take_bracket(rest)) {
+        Some((component, rest)) => (Some(component.to_string()), rest),
+        None => (None, rest),
+    };
+    let (message, detail) = match rest.split_once(" — ") {
+        Some((message, detail)) => (message.to_string(), Some(detail.to_string())),
+        None => (rest.to_string(), None),
+    };
+    Some(LogEntry {
+        timestamp: Some(timestamp.to_string()),
+        timestamp_ms: Some(parsed.timestamp_millis()),
+        level,
+        component,
+        message,
+        detail,
+        raw: raw.to_string(),
+        segment: segment.to_string(),
+        line,
+    })
+}
+
+fn unstructured_entry(raw: &str, segment: &str, line: usize) -> LogEntry {
+    LogEntry {
+        timestamp: None,
+        timestamp_ms: None,
+        level: None,
+        component: None,
+        message: raw.to_string(),
+        detail: None,
+        raw: raw.to_string(),
+        segment: segment.to_string(),
+        line,
+    }
+}
+
+/// Parse a chunk of log text into entries, folding continuation lines into the entry
+/// before them.
+pub fn parse_log_text(text: &str, segment: &str, first_line: usize) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = first_line + index;
+        if raw.is_empty() {
+            continue;
+        }
+        if let Some(entry) = parse_log_line(raw, segment, line) {
+            entries.push(entry);
+            continue;
+        }
+        match entries.last_mut() {
+            Some(previous) => {
+                previous.raw.push('\n');
+                previous.raw.push_str(raw);
+                match previous.detail.as_mut() {
+                    Some(detail) => {
+                        detail.push('\n');
+                        detail.push_str(raw);
+                    }
+                    None => {
+                        previous.message.push('\n');
+                        previous.message.push_str(raw);
+                    }
+                }
+            }
+            None => entries.push(unstructured_entry(raw, segment, line)),
+        }
+    }
+    entries
+}
+
+impl LogQuery {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        let in_list = |list: &[String], value: &Option<String>| {
+            list.is_empty()
+                || value
+                    .as_deref()
+                    .is_some_and(|v| list.iter().any(|l| l.eq_ignore_ascii_case(v)))
+        };
+        if !in_list(&self.levels, &entry.level) || !in_list(&self.components, &entry.component) {
+            return false;
+        }
+        if let Some(since) = self.since_ms {
+            if entry.timestamp_ms.is_none_or(|t| t < since) {
+                return false;
+            }
+        }
+        if let Some(until) = self.until_ms {
+            if entry.timestamp_ms.is_none_or(|t| t > until) {
+                return false;
+            }
+        }
+        match &self.contains {
+            Some(needle) if !needle.is_empty() => {
+                entry.raw.to_lowercase().contains(&needle.to_lowercase())
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Read every segment of `path` oldest first and return the requested page of
+/// matching entries.
+pub fn read_log_page(path: &Path, query: &LogQuery) -> Result<LogPage, String> {
+    let segments = log_segments(path);
+    let mut matching = Vec::new();
+    for segment in &segments {
+        let bytes = read_segment(segment)
+            .map_err(|e| format!("Failed to read log {}: {}", segment.display(), e))?;
+        let text = String::from_utf8_lossy(&bytes);
+        matching.extend(
+            parse_log_text(&text, &segment_name(segment), 1)
+                .into_iter()
+                .filter(|entry| query.matches(entry)),
+        );
+    }
+
+    let total = matching.len();
+    if query.newest_first {
+        matching.reverse();
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let entries: Vec<LogEntry> = matching
+        .into_iter()
+        .skip(query.offset)
+        .take(limit)
+        .collect();
+    Ok(LogPage {
+        has_more: query.offset + entries.len() < total,
+        entries,
+        total,
+        offset: query.offset,
+        segments: segments.iter().map(|s| segment_name(s)).collect(),
+    })
+}
+
+/// Read a log written by `append_log` across its rotated segments, filtered by level,
+/// component, time range and substring, one page at a time.
+#[tauri::command]
+pub async fn read_logs(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    query: Option<LogQuery>,
+) -> Result<LogPage, String> {
+    let log_path = sandbox.resolve(&path).map_err(|e| e.to_string())?;
+    let query = query.unwrap_or_default();
+    tokio::task::spawn_blocking(move || read_log_page(&log_path, &query))
+        .await
+        .map_err(|e| format!("Log read failed: {}", e))?
+}
+
+/// Read complete lines of `path` from byte `from`. Returns the text and the offset just
+/// past the last newline; a trailing partial line is left for the next poll.
+fn read_complete_lines(path: &Path, from: u64) -> io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(from))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let complete = bytes.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    bytes.truncate(complete);
+    Ok((
+        String::from_utf8_lossy(&bytes).to_string(),
+        from + complete as u64,
+    ))
+}
+
+/// Which file `meta` belongs to: device and inode on Unix, creation time elsewhere.
+/// Lets `follow_log` notice a rotation even when the new file has already grown past
+/// the old read position.
+#[cfg(unix)]
+fn file_identity(meta: &fs::Metadata) -> Option<u128> {
+    use std::os::unix::fs::MetadataExt;
+    Some(((meta.dev() as u128) << 64) | meta.ino() as u128)
+}
+
+#[cfg(not(unix))]
+fn file_identity(meta: &fs::Metadata) -> Option<u128> {
+    let created = meta.created().ok()?;
+    created
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_nanos())
+}
+
+fn follow_log(
+    app: AppHandle,
+    tail_id: String,
+    path: PathBuf,
+    query: LogQuery,
+    poll: Duration,
+    stop: Arc<AtomicBool>,
+) {
+    let segment = segment_name(&path);
+    let meta = fs::metadata(&path).ok();
+    let mut position = meta.as_ref().map_or(0, |m| m.len());
+    let mut identity = meta.as_ref().and_then(file_identity);
+    let mut line = fs::read(&path)
+        .map(|bytes| bytes.iter().filter(|b| **b == b'\n').count())
+        .unwrap_or(0)
+        + 1;
+
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(poll);
+        let meta = fs::metadata(&path).ok();
+        let len = meta.as_ref().map_or(0, |m| m.len());
+        let current = meta.as_ref().and_then(file_identity);
+        let replaced = identity.is_some() && current.is_some() && current != identity;
+        if current.is_some() {
+            identity = current;
+        }
+        let mut entries = Vec::new();
+        let mut error = None;
+
+        if len < position || replaced {
+            // Rotated: pick up what was appended to the old file before the rename.
+            if let Some(rotated) = existing_segment(&path, 1) {
+                if let Ok(bytes) = read_segment(&rotated) {
+                    let rest = bytes.get(position as usize..).unwrap_or_default();
+                    let text = String::from_utf8_lossy(rest);
+                    entries.extend(parse_log_text(&text, &segment_name(&rotated), line));
+                }
+            }
+            position = 0;
+            line = 1;
+        }
+        if len > position {
+            match read_complete_lines(&path, position) {
+                Ok((text, next)) => {
+                    entries.extend(parse_log_text(&text, &segment, line));
+                    line += text.lines().count();
+                    position = next;
+                }
+                Err(e) => error = Some(format!("Failed to follow log {}: {}", path.display(), e)),
+            }
+        }
+
+        entries.retain(|entry| query.matches(entry));
+        if !entries.is_empty() || error.is_some() {
+            let _ = app.emit(
+                LOG_TAIL_EVENT,
+                LogTailEvent {
+                    tail_id: tail_id.clone(),
+                    entries,
+                    error,
+                },
+            );
+        }
+    }
+}
+
+/// Follow a log and emit `log-tail` events with new entries matching `query`
+/// (pagination fields are ignored). Returns the tail id for `stop_log_tail`.
+#[tauri::command]
+pub fn start_log_tail(
+    app: AppHandle,
+    sandbox: State<'_, ProjectSandbox>,
+    tails: State<'_, LogTails>,
+    path: String,
+    query: Option<LogQuery>,
+    poll_ms: Option<u64>,
+) -> Result<String, String> {
+    let log_path = sandbox.resolve(&path).map_err(|e| e.to_string())?;
+    let tail_id = format!("tail-{}", TAIL_COUNTER.fetch_add(1, Ordering::Relaxed));
+    let stop = Arc::new(AtomicBool::new(false));
+    tails
+        .active
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(tail_id.clone(), Arc::clone(&stop));
+
+    let id = tail_id.clone();
+    let poll = Duration::from_millis(poll_ms.unwrap_or(DEFAULT_POLL_MS));
+    let query = query.unwrap_or_default();
+    thread::spawn(move || follow_log(app, id, log_path, query, poll, stop));
+    Ok(tail_id)
+}
+
+#[tauri::command]
+pub fn stop_log_tail(tails: State<'_, LogTails>, tail_id: String) -> Result<(), String> {
+    let stop = tails
+        .active
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&tail_id)
+        .ok_or_else(|| format!("Log tail {} is not running", tail_id))?;
+    stop.store(true, Ordering::Relaxed);
+    Ok(())
+}
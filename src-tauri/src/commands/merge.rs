@@ -0,0 +1,100 @@
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use crate::spec::merge::{merge_specs, MergeKind, MergeOutcome};
+use git2::Repository;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Name of the merge driver in git config and `.gitattributes`.
+const DRIVER: &str = "ddd-spec";
+
+/// Spec files the driver handles, relative to the project root.
+const DRIVER_PATTERNS: [&str; 2] = [
+    "specs/domains/*/domain.yaml",
+    "specs/domains/*/flows/*.yaml",
+];
+
+/// Three-way merge of a flow or domain spec. `path` only tells which kind of spec
+/// the contents are (`specs/domains/<domain>/flows/<flow>.yaml` or `…/domain.yaml`);
+/// nothing is read or written.
+#[tauri::command]
+pub fn merge_spec(
+    path: String,
+    base: String,
+    ours: String,
+    theirs: String,
+) -> Result<MergeOutcome, String> {
+    let kind = MergeKind::for_path(Path::new(&path))
+        .ok_or_else(|| format!("{} is not a flow or domain spec", path))?;
+    merge_specs(kind, &base, &ours, &theirs)
+}
+
+/// The `ddd-merge-driver` binary next to the app executable. It is built from the
+/// tauri-free `ddd-spec` crate (`npm run build:merge-driver`, run before `tauri dev`
+/// and `tauri build`) into the same target directory as the app.
+fn driver_binary() -> Result<PathBuf, String> {
+    let exe = env::current_exe().map_err(|e| format!("Failed to locate the app: {}", e))?;
+    let driver = exe.with_file_name(format!("ddd-merge-driver{}", env::consts::EXE_SUFFIX));
+    if !driver.is_file() {
+        return Err(format!(
+            "Merge driver not found at {}. Build it with npm run build:merge-driver",
+            driver.display()
+        ));
+    }
+    Ok(driver)
+}
+
+fn register_at(root: &Path, driver: &Path) -> Result<String, String> {
+    let repo = Repository::discover(root)
+        .map_err(|e| format!("Failed to open repo at {}: {}", root.display(), e))?;
+    let command = format!(
+        "\"{}\" %O %A %B %P",
+        driver.to_string_lossy().replace('\\', "/")
+    );
+    let mut config = repo
+        .config()
+        .map_err(|e| format!("Failed to open git config: {}", e))?;
+    config
+        .set_str(&format!("merge.{}.name", DRIVER), "DDD spec merge")
+        .and_then(|_| config.set_str(&format!("merge.{}.driver", DRIVER), &command))
+        .map_err(|e| format!("Failed to write git config: {}", e))?;
+
+    let attributes = root.join(".gitattributes");
+    let mut content = match fs::read_to_string(&attributes) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read .gitattributes: {}", e)),
+    };
+    let missing: Vec<String> = DRIVER_PATTERNS
+        .iter()
+        .map(|pattern| format!("{} merge={}", pattern, DRIVER))
+        .filter(|line| !content.lines().any(|l| l.trim() == line))
+        .collect();
+    if !missing.is_empty() {
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        for line in missing {
+            content.push_str(&line);
+            content.push('\n');
+        }
+        fs::write(&attributes, content)
+            .map_err(|e| format!("Failed to write .gitattributes: {}", e))?;
+    }
+    Ok(command)
+}
+
+/// Register `ddd-merge-driver` for the open project: the driver in the repository's
+/// git config and the flow and domain patterns in the project's `.gitattributes`.
+/// Returns the driver command configured.
+#[tauri::command]
+pub fn register_merge_driver(sandbox: State<'_, ProjectSandbox>) -> Result<String, String> {
+    let root = sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: ".gitattributes".to_string(),
+        }
+        .to_string()
+    })?;
+    register_at(&root, &driver_binary()?)
+}
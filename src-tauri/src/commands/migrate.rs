@@ -0,0 +1,406 @@
+use super::file::write_atomic;
+use super::project::{commit_signature, create_commit, CommitOptions, SpecParseError};
+use super::search::{spec_files, SpecFileKind};
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use crate::spec::migrate::{migrate, DocumentKind, SPEC_VERSION};
+use git2::build::TreeUpdateBuilder;
+use git2::{FileMode, Repository, Status};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Where pre-migration copies go when git can't hold them.
+pub const BACKUP_DIR: &str = ".ddd/backups";
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FileMigration {
+    /// Relative to the project root.
+    pub path: String,
+    pub kind: DocumentKind,
+    pub from: u64,
+    pub to: u64,
+    pub changes: Vec<String>,
+}
+
+/// How the pre-migration state can be recovered.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Safeguard {
+    /// The migrated files were clean in git; this commit holds the migration and its
+    /// parent the previous contents.
+    Commit { id: String },
+    /// The previous contents were copied here, relative to the project root.
+    Backup { path: String },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MigrationReport {
+    pub spec_version: u64,
+    pub dry_run: bool,
+    /// Files that are (or, in a dry run, would be) upgraded.
+    pub files: Vec<FileMigration>,
+    /// Files that couldn't be read or migrated; they are left untouched.
+    pub errors: Vec<SpecParseError>,
+    /// `None` in a dry run or when nothing needed migrating.
+    pub safeguard: Option<Safeguard>,
+    /// Why the migration couldn't be committed, when it fell back to a backup.
+    pub commit_error: Option<String>,
+}
+
+struct Planned {
+    path: PathBuf,
+    relative: String,
+    original: String,
+    content: String,
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn project_documents(root: &Path) -> Vec<(PathBuf, DocumentKind)> {
+    let mut documents = Vec::new();
+    let project_json = root.join("ddd-project.json");
+    if project_json.is_file() {
+        documents.push((project_json, DocumentKind::Project));
+    }
+    let layout = root.join("specs").join("system-layout.yaml");
+    if layout.is_file() {
+        documents.push((layout, DocumentKind::SystemLayout));
+    }
+    for file in spec_files(root) {
+        let kind = match file.kind {
+            SpecFileKind::Domain => DocumentKind::Domain,
+            SpecFileKind::Flow => DocumentKind::Flow,
+        };
+        documents.push((file.path, kind));
+    }
+    documents
+}
+
+fn plan(root: &Path, report: &mut MigrationReport) -> Vec<Planned> {
+    let mut planned = Vec::new();
+    for (path, kind) in project_documents(root) {
+        let relative = relative_path(root, &path);
+        let error = |message: String| SpecParseError {
+            path: relative.clone(),
+            message,
+            line: None,
+            column: None,
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                report.errors.push(error(format!("Failed to read: {}", e)));
+                continue;
+            }
+        };
+        match migrate(kind, &content) {
+            Ok(Some(migrated)) => {
+                report.files.push(FileMigration {
+                    path: relative.clone(),
+                    kind,
+                    from: migrated.from,
+                    to: migrated.to,
+                    changes: migrated.changes,
+                });
+                planned.push(Planned {
+                    path,
+                    relative,
+                    original: content,
+                    content: migrated.content,
+                });
+            }
+            Ok(None) => {}
+            Err(e) => report.errors.push(error(e)),
+        }
+    }
+    planned
+}
+
+/// The repository and HEAD commit if every planned file is tracked and unmodified,
+/// so HEAD already holds its previous contents.
+fn clean_repo(root: &Path, planned: &[Planned]) -> Option<Repository> {
+    let repo = Repository::discover(root).ok()?;
+    let workdir = repo.workdir()?.canonicalize().ok()?;
+    repo.head().ok()?.peel_to_commit().ok()?;
+    let clean = planned.iter().all(|p| {
+        let Ok(relative) = p.path.strip_prefix(&workdir) else {
+            return false;
+        };
+        repo.status_file(relative) == Ok(Status::CURRENT)
+    });
+    clean.then_some(repo)
+}
+
+fn backup(root: &Path, planned: &[Planned]) -> Result<String, String> {
+    let name = format!(
+        "spec-v{}-{}",
+        SPEC_VERSION,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let relative_dir = format!("{}/{}", BACKUP_DIR, name);
+    let dir = root.join(&relative_dir);
+    for p in planned {
+        let target = dir.join(&p.relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+        }
+        fs::copy(&p.path, &target)
+            .map_err(|e| format!("Failed to back up {}: {}", p.relative, e))?;
+    }
+    Ok(relative_dir)
+}
+
+/// Put the previous contents of `planned` back.
+fn restore(planned: &[Planned]) -> Result<(), String> {
+    let failed: Vec<String> = planned
+        .iter()
+        .filter_map(|p| {
+            write_atomic(&p.path, p.original.as_bytes())
+                .err()
+                .map(|e| format!("Failed to restore {}: {}", p.relative, e))
+        })
+        .collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(failed.join("; "))
+    }
+}
+
+/// Rewrite every planned file, or none: when a write fails the files already
+/// rewritten get their previous contents back.
+fn write_all(planned: &[Planned]) -> Result<(), String> {
+    for (i, p) in planned.iter().enumerate() {
+        if let Err(e) = write_atomic(&p.path, p.content.as_bytes()) {
+            let mut message = format!("Failed to write {}: {}", p.relative, e);
+            if let Err(e) = restore(&planned[..i]) {
+                message = format!("{}; {}", message, e);
+            }
+            return Err(message);
+        }
+    }
+    Ok(())
+}
+
+/// Commit only the migrated files on top of HEAD, leaving anything else staged as is.
+fn commit_migration(
+    repo: &Repository,
+    options: &CommitOptions,
+    planned: &[Planned],
+) -> Result<String, String> {
+    let workdir = workdir(repo)?;
+    let config = repo
+        .config()
+        .and_then(|mut config| config.snapshot())
+        .map_err(|e| format!("Failed to read git config: {}", e))?;
+    let sig = commit_signature(&config, options)?;
+    let head = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .map_err(|e| format!("Failed to read HEAD: {}", e))?;
+    let head_tree = head
+        .tree()
+        .map_err(|e| format!("Failed to read HEAD tree: {}", e))?;
+    let mut update = TreeUpdateBuilder::new();
+    for p in planned {
+        let relative = p.path.strip_prefix(&workdir).unwrap_or(&p.path);
+        let blob = repo
+            .blob(p.content.as_bytes())
+            .map_err(|e| format!("Failed to store {}: {}", p.relative, e))?;
+        update.upsert(relative, blob, FileMode::Blob);
+    }
+    let tree_oid = update
+        .create_updated(repo, &head_tree)
+        .map_err(|e| format!("Failed to write tree: {}", e))?;
+    let tree = repo
+        .find_tree(tree_oid)
+        .map_err(|e| format!("Failed to find tree: {}", e))?;
+    let message = format!(
+        "Migrate specs to spec_version {}\n\n{}\n",
+        SPEC_VERSION,
+        planned
+            .iter()
+            .map(|p| format!("- {}", p.relative))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    let oid = create_commit(repo, &config, options, &sig, &message, &tree, &[&head])?;
+    Ok(oid.to_string())
+}
+
+fn workdir(repo: &Repository) -> Result<PathBuf, String> {
+    repo.workdir()
+        .and_then(|w| w.canonicalize().ok())
+        .ok_or_else(|| "Repository has no working directory".to_string())
+}
+
+/// Stage the planned files as they are on disk.
+fn stage(repo: &Repository, planned: &[Planned]) -> Result<(), String> {
+    let workdir = workdir(repo)?;
+    let mut index = repo
+        .index()
+        .map_err(|e| format!("Failed to get index: {}", e))?;
+    for p in planned {
+        let relative = p.path.strip_prefix(&workdir).unwrap_or(&p.path);
+        index
+            .add_path(relative)
+            .map_err(|e| format!("Failed to stage {}: {}", p.relative, e))?;
+    }
+    index
+        .write()
+        .map_err(|e| format!("Failed to write index: {}", e))
+}
+
+fn migrate_project_at(
+    root: &Path,
+    dry_run: bool,
+    options: &CommitOptions,
+) -> Result<MigrationReport, String> {
+    let mut report = MigrationReport {
+        spec_version: SPEC_VERSION,
+        dry_run,
+        files: Vec::new(),
+        errors: Vec::new(),
+        safeguard: None,
+        commit_error: None,
+    };
+    let planned = plan(root, &mut report);
+    if dry_run || planned.is_empty() {
+        return Ok(report);
+    }
+    // Rewrite and stage the files, then commit them on top of HEAD, which holds the
+    // previous contents. HEAD only moves once the working tree and index match it.
+    if let Some(repo) = clean_repo(root, &planned) {
+        write_all(&planned)?;
+        let committed =
+            stage(&repo, &planned).and_then(|()| commit_migration(&repo, options, &planned));
+        match committed {
+            Ok(id) => {
+                report.safeguard = Some(Safeguard::Commit { id });
+                return Ok(report);
+            }
+            // No identity, signing failed, …: undo and fall back to a backup.
+            Err(e) => {
+                restore(&planned)
+                    .and_then(|()| stage(&repo, &planned))
+                    .map_err(|undo| format!("{}; {}", e, undo))?;
+                report.commit_error = Some(e);
+            }
+        }
+    }
+    let path = backup(root, &planned)?;
+    write_all(&planned)?;
+    report.safeguard = Some(Safeguard::Backup { path });
+    Ok(report)
+}
+
+/// Upgrade `ddd-project.json`, the system layout, every `domain.yaml` and every flow
+/// of the open project to the current `spec_version`, one step at a time. With
+/// `dry_run` nothing is written and the report lists what would change.
+///
+/// The previous contents are secured: if the files are all committed and unmodified in
+/// git, the migration is committed on its own so its parent holds the old versions;
+/// otherwise, or when that commit fails, they are copied to `.ddd/backups/` first. The
+/// commit uses the identity and signing settings in `options`, as `git_commit` does.
+#[tauri::command]
+pub async fn migrate_project(
+    sandbox: State<'_, ProjectSandbox>,
+    dry_run: bool,
+    options: Option<CommitOptions>,
+) -> Result<MigrationReport, String> {
+    let root = sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: "ddd-project.json".to_string(),
+        }
+        .to_string()
+    })?;
+    let options = options.unwrap_or_default();
+    tokio::task::spawn_blocking(move || migrate_project_at(&root, dry_run, &options))
+        .await
+        .map_err(|e| format!("Migration failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    const V1_PROJECT: &str = "{\n  \"name\": \"shop\",\n  \"domains\": []\n}\n";
+
+    /// A repo with a v1 `ddd-project.json` committed.
+    fn project() -> (tempfile::TempDir, PathBuf, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let repo = Repository::init(&root).unwrap();
+        fs::write(root.join("ddd-project.json"), V1_PROJECT).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("ddd-project.json")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+        drop(tree);
+        (dir, root, repo)
+    }
+
+    fn options(sign: bool) -> CommitOptions {
+        CommitOptions {
+            author_name: Some("Test".to_string()),
+            author_email: Some("test@example.com".to_string()),
+            trailers: Vec::new(),
+            sign: Some(sign),
+        }
+    }
+
+    fn head_blob(repo: &Repository) -> String {
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let entry = tree.get_path(Path::new("ddd-project.json")).unwrap();
+        let blob = repo.find_blob(entry.id()).unwrap();
+        String::from_utf8(blob.content().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn migration_is_committed_once_files_and_index_are_updated() {
+        let (_dir, root, repo) = project();
+        let report = migrate_project_at(&root, false, &options(false)).unwrap();
+
+        assert!(matches!(report.safeguard, Some(Safeguard::Commit { .. })));
+        assert_eq!(report.commit_error, None);
+        let written = fs::read_to_string(root.join("ddd-project.json")).unwrap();
+        assert!(written.contains("spec_version"));
+        assert_eq!(head_blob(&repo), written);
+        let status = repo.status_file(Path::new("ddd-project.json")).unwrap();
+        assert_eq!(status, Status::CURRENT);
+    }
+
+    #[test]
+    fn failed_commit_is_undone_and_falls_back_to_a_backup() {
+        let (_dir, root, repo) = project();
+        repo.config()
+            .unwrap()
+            .set_str("gpg.program", "/nonexistent/gpg")
+            .unwrap();
+        let head = repo.head().unwrap().target().unwrap();
+        let report = migrate_project_at(&root, false, &options(true)).unwrap();
+
+        assert!(report.commit_error.unwrap().contains("signing program"));
+        let Some(Safeguard::Backup { path }) = report.safeguard else {
+            panic!("expected a backup");
+        };
+        let backed_up = fs::read_to_string(root.join(path).join("ddd-project.json")).unwrap();
+        assert_eq!(backed_up, V1_PROJECT);
+        assert_eq!(repo.head().unwrap().target().unwrap(), head);
+        assert_eq!(head_blob(&repo), V1_PROJECT);
+        // The index still matches HEAD; only the working tree holds the migration.
+        let status = repo.status_file(Path::new("ddd-project.json")).unwrap();
+        assert_eq!(status, Status::WT_MODIFIED);
+    }
+}
@@ -1,5 +1,20 @@
+pub mod diff;
+pub mod encoding;
+pub mod events;
 pub mod file;
 pub mod git;
+pub mod git_remote;
 pub mod implementation;
 pub mod llm;
+pub mod logs;
+pub mod merge;
+pub mod migrate;
 pub mod project;
+pub mod refactor;
+pub mod schema;
+pub mod search;
+pub mod signing;
+pub mod trash;
+pub mod tree;
+pub mod validation;
+pub mod watcher;
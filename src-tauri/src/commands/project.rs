@@ -1,4 +1,339 @@
-use git2::{Repository, Signature};
+use super::search::{spec_files, SpecFileKind};
+use super::signing::sign_commit;
+use crate::sandbox::{ProjectSandbox, SandboxError, PROJECT_MARKER};
+use crate::spec::{
+    self, DomainConfig, Extra, FlowDocument, FlowNode, NodeSpec, NodeType, ProjectConfig,
+    SystemLayout,
+};
+use git2::{Config, Oid, Repository, Signature};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+
+/// Record the opened project as the root the file commands are confined to.
+/// Returns the canonical root path. `create` makes the directory of a new project,
+/// which must lie in a directory picked with `pick_directory`.
+#[tauri::command]
+pub fn set_project_root(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    create: Option<bool>,
+) -> Result<String, SandboxError> {
+    let root = sandbox.set_root(&path, create.unwrap_or(false))?;
+    Ok(root.to_string_lossy().to_string())
+}
+
+/// Let the user pick a directory in a native dialog. The choice, and everything
+/// below it, may then become the project root.
+#[tauri::command]
+pub async fn pick_directory(
+    app: AppHandle,
+    sandbox: State<'_, ProjectSandbox>,
+) -> Result<Option<String>, String> {
+    let picked = tokio::task::spawn_blocking(move || app.dialog().file().blocking_pick_folder())
+        .await
+        .map_err(|e| format!("Directory dialog failed: {}", e))?;
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let path = picked
+        .into_path()
+        .map_err(|e| format!("Unsupported directory: {}", e))?;
+    sandbox.approve(&path);
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Where the sample project goes (`~/ddd-sample-project`), approved as a project root.
+#[tauri::command]
+pub fn sample_project_path(
+    app: AppHandle,
+    sandbox: State<'_, ProjectSandbox>,
+) -> Result<String, String> {
+    let home = app
+        .path()
+        .home_dir()
+        .map_err(|e| format!("Failed to locate the home directory: {}", e))?;
+    let path = home.join("ddd-sample-project");
+    sandbox.approve(&path);
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Whether `path` is a DDD project, for pruning the recent projects list.
+#[tauri::command]
+pub fn is_ddd_project(path: String) -> bool {
+    Path::new(&path).join(PROJECT_MARKER).is_file()
+}
+
+#[tauri::command]
+pub fn clear_project_root(sandbox: State<'_, ProjectSandbox>) {
+    sandbox.clear_root();
+}
+
+/// A project file that could not be read or parsed. `line`/`column` are 1-based and
+/// present when the parser reported a position.
+#[derive(Serialize, Clone, Debug)]
+pub struct SpecParseError {
+    /// Relative to the project root.
+    pub path: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct LoadedDomain {
+    /// `None` when the domain has no `domain.yaml` or it failed to parse.
+    pub config: Option<DomainConfig>,
+    /// Flow documents keyed by file stem (the flow id).
+    pub flows: IndexMap<String, FlowDocument>,
+}
+
+/// Everything `load_project` read, keyed by domain directory name in directory order.
+#[derive(Serialize, Clone, Debug)]
+pub struct ProjectGraph {
+    pub root: String,
+    /// `None` when `ddd-project.json` is missing or failed to parse.
+    pub project: Option<ProjectConfig>,
+    /// `None` when `specs/system-layout.yaml` is missing or failed to parse.
+    pub system_layout: Option<SystemLayout>,
+    pub domains: IndexMap<String, LoadedDomain>,
+    pub errors: Vec<SpecParseError>,
+    pub file_count: usize,
+    pub elapsed_ms: u64,
+}
+
+enum ProjectFile {
+    Project,
+    SystemLayout,
+    Domain(String),
+    Flow(String, String),
+}
+
+enum Parsed {
+    Project(ProjectConfig),
+    SystemLayout(SystemLayout),
+    Domain(Box<DomainConfig>),
+    Flow(Box<FlowDocument>),
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// serde_yaml can only place an error inside a node at the start of the enclosing
+/// mapping or sequence. Re-check the trigger and nodes one at a time and return the
+/// JSON pointer of the first that fails, down to its `spec` when that is the problem.
+fn failing_node_pointer(content: &str) -> Option<String> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+    let trigger = doc
+        .get("trigger")
+        .map(|node| ("/trigger".to_string(), node));
+    let nodes = doc
+        .get("nodes")
+        .and_then(|nodes| nodes.as_sequence())
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, node)| (format!("/nodes/{}", i), node));
+    let (pointer, node) = trigger
+        .into_iter()
+        .chain(nodes)
+        .find(|(_, node)| serde_yaml::from_value::<FlowNode>((*node).clone()).is_err())?;
+
+    let spec_fails = node
+        .get("type")
+        .and_then(|t| t.as_str())
+        .map(NodeType::parse)
+        .is_some_and(|node_type| {
+            let spec = match node.get("spec") {
+                None | Some(serde_yaml::Value::Null) => Ok(serde_json::Value::Object(Extra::new())),
+                Some(spec) => serde_yaml::from_value(spec.clone()),
+            };
+            spec.map_or(true, |spec| NodeSpec::from_value(&node_type, spec).is_err())
+        });
+    Some(if spec_fails {
+        format!("{}/spec", pointer)
+    } else {
+        pointer
+    })
+}
+
+fn yaml_error(path: String, content: &str, e: serde_yaml::Error) -> SpecParseError {
+    let location = failing_node_pointer(content)
+        .and_then(|pointer| spec::patch::locate(content, &pointer))
+        .or_else(|| e.location().map(|l| (l.line(), l.column())));
+    SpecParseError {
+        path,
+        message: e.to_string(),
+        line: location.map(|(line, _)| line),
+        column: location.map(|(_, column)| column),
+    }
+}
+
+fn parse_project_file(
+    root: &Path,
+    path: &Path,
+    file: &ProjectFile,
+) -> Result<Parsed, SpecParseError> {
+    let rel = relative_path(root, path);
+    let content = fs::read_to_string(path).map_err(|e| SpecParseError {
+        path: rel.clone(),
+        message: format!("Failed to read: {}", e),
+        line: None,
+        column: None,
+    })?;
+    match file {
+        ProjectFile::Project => serde_json::from_str(&content)
+            .map(Parsed::Project)
+            .map_err(|e| SpecParseError {
+                path: rel,
+                message: e.to_string(),
+                line: Some(e.line()).filter(|&l| l > 0),
+                column: Some(e.column()).filter(|&c| c > 0),
+            }),
+        ProjectFile::SystemLayout => spec::from_yaml(&content)
+            .map(Parsed::SystemLayout)
+            .map_err(|e| yaml_error(rel, &content, e)),
+        ProjectFile::Domain(_) => spec::from_yaml(&content)
+            .map(|c| Parsed::Domain(Box::new(c)))
+            .map_err(|e| yaml_error(rel, &content, e)),
+        ProjectFile::Flow(..) => spec::from_yaml(&content)
+            .map(|d| Parsed::Flow(Box::new(d)))
+            .map_err(|e| yaml_error(rel, &content, e)),
+    }
+}
+
+/// Read and parse every file in `jobs` on a small pool of threads. Results come back
+/// in job order.
+fn parse_parallel(
+    root: &Path,
+    jobs: &[(PathBuf, ProjectFile)],
+) -> Vec<Result<Parsed, SpecParseError>> {
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(jobs.len())
+        .max(1);
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<Parsed, SpecParseError>>>> =
+        Mutex::new((0..jobs.len()).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some((path, file)) = jobs.get(i) else {
+                    break;
+                };
+                let parsed = parse_project_file(root, path, file);
+                results.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(parsed);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+pub(crate) fn load_project_graph(root: &Path) -> ProjectGraph {
+    let started = Instant::now();
+    let mut jobs: Vec<(PathBuf, ProjectFile)> = Vec::new();
+    let project_json = root.join("ddd-project.json");
+    if project_json.is_file() {
+        jobs.push((project_json, ProjectFile::Project));
+    }
+    let layout = root.join("specs").join("system-layout.yaml");
+    if layout.is_file() {
+        jobs.push((layout, ProjectFile::SystemLayout));
+    }
+
+    let mut domains: IndexMap<String, LoadedDomain> = IndexMap::new();
+    for file in spec_files(root) {
+        domains.entry(file.domain_id.clone()).or_default();
+        let job = match file.kind {
+            SpecFileKind::Domain => ProjectFile::Domain(file.domain_id),
+            SpecFileKind::Flow => {
+                let stem = file
+                    .path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                ProjectFile::Flow(file.domain_id, stem)
+            }
+        };
+        jobs.push((file.path, job));
+    }
+
+    let mut graph = ProjectGraph {
+        root: root.to_string_lossy().to_string(),
+        project: None,
+        system_layout: None,
+        domains,
+        errors: Vec::new(),
+        file_count: jobs.len(),
+        elapsed_ms: 0,
+    };
+    let results = parse_parallel(root, &jobs);
+    for ((_, file), result) in jobs.into_iter().zip(results) {
+        let parsed = match result {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                graph.errors.push(e);
+                continue;
+            }
+        };
+        match (file, parsed) {
+            (ProjectFile::Project, Parsed::Project(config)) => graph.project = Some(config),
+            (ProjectFile::SystemLayout, Parsed::SystemLayout(layout)) => {
+                graph.system_layout = Some(layout)
+            }
+            (ProjectFile::Domain(id), Parsed::Domain(config)) => {
+                graph.domains.entry(id).or_default().config = Some(*config);
+            }
+            (ProjectFile::Flow(id, flow_id), Parsed::Flow(doc)) => {
+                graph
+                    .domains
+                    .entry(id)
+                    .or_default()
+                    .flows
+                    .insert(flow_id, *doc);
+            }
+            _ => {}
+        }
+    }
+    graph.elapsed_ms = started.elapsed().as_millis() as u64;
+    graph
+}
+
+/// Load `ddd-project.json`, `specs/system-layout.yaml`, every `domain.yaml` and every
+/// flow file of the open project in one call. Files are read and parsed in parallel;
+/// a file that fails is reported in `errors` and the rest still load.
+#[tauri::command]
+pub async fn load_project(sandbox: State<'_, ProjectSandbox>) -> Result<ProjectGraph, String> {
+    let root = sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: "ddd-project.json".to_string(),
+        }
+        .to_string()
+    })?;
+    tokio::task::spawn_blocking(move || load_project_graph(&root))
+        .await
+        .map_err(|e| format!("Project load failed: {}", e))
+}
 
 #[tauri::command]
 pub fn git_init(path: String) -> Result<(), String> {
@@ -22,10 +357,101 @@ pub fn git_add_all(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Options of `git_commit`. The author fields override `user.name` and `user.email`
+/// from git config, e.g. with a project's own identity.
+#[derive(Deserialize, Default)]
+pub struct CommitOptions {
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    /// Appended to the message as `Key: value` lines, e.g. `DDD-Flow: billing/checkout`.
+    #[serde(default)]
+    pub trailers: Vec<CommitTrailer>,
+    /// Sign the commit; follows `commit.gpgsign` when absent.
+    pub sign: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct CommitTrailer {
+    pub key: String,
+    pub value: String,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+/// Author and committer from the options, falling back to the repository's and the
+/// global git config. Fails rather than committing under a made-up identity.
+pub(crate) fn commit_signature(
+    config: &Config,
+    options: &CommitOptions,
+) -> Result<Signature<'static>, String> {
+    let name = non_empty(options.author_name.clone())
+        .or_else(|| non_empty(config.get_string("user.name").ok()));
+    let email = non_empty(options.author_email.clone())
+        .or_else(|| non_empty(config.get_string("user.email").ok()));
+    match (name, email) {
+        (Some(name), Some(email)) => {
+            Signature::now(name.trim(), email.trim()).map_err(|e| format!("Signature: {}", e))
+        }
+        _ => Err(
+            "No commit identity: set user.name and user.email in git config or an author in the project's git settings"
+                .to_string(),
+        ),
+    }
+}
+
+/// `message` with the trailers appended, joining an existing trailer block if the
+/// message ends with one. Trailers already present are not repeated.
+fn with_trailers(message: &str, trailers: &[CommitTrailer]) -> String {
+    let lines: Vec<String> = trailers
+        .iter()
+        .map(|t| format!("{}: {}", t.key.trim(), t.value.trim()))
+        .filter(|line| !message.lines().any(|l| l.trim() == line))
+        .collect();
+    if lines.is_empty() {
+        return message.to_string();
+    }
+    let message = message.trim_end();
+    let has_block = git2::message_trailers_strs(message).is_ok_and(|t| t.len() > 0);
+    let separator = if has_block { "\n" } else { "\n\n" };
+    format!("{}{}{}\n", message, separator, lines.join("\n"))
+}
+
+/// Point HEAD (or the branch it is on) at a commit created outside `Repository::commit`.
+fn advance_head(repo: &Repository, oid: Oid, message: &str, initial: bool) -> Result<(), String> {
+    let summary = message.lines().next().unwrap_or("");
+    let log = if initial {
+        format!("commit (initial): {}", summary)
+    } else {
+        format!("commit: {}", summary)
+    };
+    let head = repo
+        .find_reference("HEAD")
+        .map_err(|e| format!("Failed to get HEAD: {}", e))?;
+    match head.symbolic_target() {
+        Some(branch) => repo.reference(branch, oid, true, &log).map(|_| ()),
+        None => repo.set_head_detached(oid),
+    }
+    .map_err(|e| format!("Failed to update HEAD: {}", e))
+}
+
+/// Commit the index. The identity comes from `options` or git config, and the commit
+/// is signed with the configured program when `options.sign` or `commit.gpgsign`
+/// ask for it.
 #[tauri::command]
-pub fn git_commit(path: String, message: String) -> Result<String, String> {
+pub fn git_commit(
+    path: String,
+    message: String,
+    options: Option<CommitOptions>,
+) -> Result<String, String> {
     let repo =
         Repository::open(&path).map_err(|e| format!("Failed to open repo at {}: {}", path, e))?;
+    let options = options.unwrap_or_default();
+    let config = repo
+        .config()
+        .and_then(|mut config| config.snapshot())
+        .map_err(|e| format!("Failed to read git config: {}", e))?;
     let mut index = repo
         .index()
         .map_err(|e| format!("Failed to get index: {}", e))?;
@@ -35,15 +461,45 @@ pub fn git_commit(path: String, message: String) -> Result<String, String> {
     let tree = repo
         .find_tree(tree_oid)
         .map_err(|e| format!("Failed to find tree: {}", e))?;
-    let sig =
-        Signature::now("DDD Tool", "ddd-tool@local").map_err(|e| format!("Signature: {}", e))?;
+    let sig = commit_signature(&config, &options)?;
+    let message = with_trailers(&message, &options.trailers);
 
     let parent_commit = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
     let parents: Vec<&git2::Commit> = parent_commit.iter().collect();
+    let oid = create_commit(&repo, &config, &options, &sig, &message, &tree, &parents)?;
+    Ok(oid.to_string())
+}
 
+/// Commit `tree` on top of `parents` and move HEAD to it, signing it when
+/// `options.sign` or `commit.gpgsign` ask for it. `config` must be a snapshot.
+pub(crate) fn create_commit(
+    repo: &Repository,
+    config: &Config,
+    options: &CommitOptions,
+    sig: &Signature,
+    message: &str,
+    tree: &git2::Tree,
+    parents: &[&git2::Commit],
+) -> Result<Oid, String> {
+    let sign = options
+        .sign
+        .unwrap_or_else(|| config.get_bool("commit.gpgsign").unwrap_or(false));
+    if !sign {
+        return repo
+            .commit(Some("HEAD"), sig, sig, message, tree, parents)
+            .map_err(|e| format!("Failed to commit: {}", e));
+    }
+
+    let buffer = repo
+        .commit_create_buffer(sig, sig, message, tree, parents)
+        .map_err(|e| format!("Failed to create commit: {}", e))?;
+    let content = buffer
+        .as_str()
+        .ok_or_else(|| "Commit is not valid UTF-8".to_string())?;
+    let signature = sign_commit(config, content.as_bytes(), sig)?;
     let oid = repo
-        .commit(Some("HEAD"), &sig, &sig, &message, &tree, &parents)
+        .commit_signed(content, &signature, None)
         .map_err(|e| format!("Failed to commit: {}", e))?;
-
-    Ok(oid.to_string())
+    advance_head(repo, oid, message, parents.is_empty())?;
+    Ok(oid)
 }
@@ -0,0 +1,595 @@
+use super::file::{apply_batch, FileOperation};
+use super::implementation::hash_bytes;
+use super::project::SpecParseError;
+use super::search::{spec_files, SpecFileKind};
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use crate::spec::project::domain_id;
+use crate::spec::refactor::{
+    edit_json, edit_yaml, rename_in_domain, rename_in_flow, rename_in_layout, rename_in_mapping,
+    rename_in_project, Rename,
+};
+use crate::spec::{self, FlowDocument};
+use git2::Patch;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// One file the rename rewrites, moves, or both.
+#[derive(Serialize, Clone, Debug)]
+pub struct RenameEdit {
+    /// Relative to the project root, before the rename.
+    pub path: String,
+    /// Where the file ends up when the rename moves it.
+    pub new_path: Option<String>,
+    /// Number of references updated in the file.
+    pub references: usize,
+    /// Unified diff of the file's contents; empty when only its path changes.
+    pub diff: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RenamePlan {
+    pub rename: Rename,
+    pub edits: Vec<RenameEdit>,
+    /// Files that couldn't be read or parsed. References in them are left as they are.
+    pub skipped: Vec<SpecParseError>,
+}
+
+enum Document {
+    Project,
+    SystemLayout,
+    Domain(String),
+    Flow(String),
+    Mapping,
+}
+
+struct Rewrite {
+    /// Relative to the project root, after the move.
+    relative: String,
+    /// Hash of the contents the rewrite was computed from.
+    hash: String,
+    content: String,
+}
+
+/// A file or directory the rename moves, relative to the project root.
+struct Move {
+    from: String,
+    to: String,
+}
+
+fn project_documents(root: &Path) -> Vec<(PathBuf, Document)> {
+    let mut documents = Vec::new();
+    let project_json = root.join("ddd-project.json");
+    if project_json.is_file() {
+        documents.push((project_json, Document::Project));
+    }
+    let layout = root.join("specs").join("system-layout.yaml");
+    if layout.is_file() {
+        documents.push((layout, Document::SystemLayout));
+    }
+    for file in spec_files(root) {
+        let document = match file.kind {
+            SpecFileKind::Domain => Document::Domain(file.domain_id),
+            SpecFileKind::Flow => Document::Flow(file.domain_id),
+        };
+        documents.push((file.path, document));
+    }
+    let mapping = root.join(".ddd").join("mapping.yaml");
+    if mapping.is_file() {
+        documents.push((mapping, Document::Mapping));
+    }
+    documents
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// An id that can be used as a file or directory name.
+fn check_id(what: &str, id: &str) -> Result<(), String> {
+    if id.trim().is_empty() {
+        return Err(format!("{} id cannot be empty", what));
+    }
+    if id != id.trim() || id.contains(['/', '\\']) || id == "." || id == ".." {
+        return Err(format!("Invalid {} id: {:?}", what.to_lowercase(), id));
+    }
+    Ok(())
+}
+
+fn read_flow(root: &Path, domain: &str, flow: &str) -> Result<FlowDocument, String> {
+    let relative = format!("specs/domains/{}/flows/{}.yaml", domain, flow);
+    let content = fs::read_to_string(root.join(&relative))
+        .map_err(|_| format!("Flow {}/{} not found", domain, flow))?;
+    spec::from_yaml(&content).map_err(|e| format!("Failed to parse {}: {}", relative, e))
+}
+
+/// Check that the rename makes sense in this project and return what it moves.
+fn check(root: &Path, rename: &Rename) -> Result<Option<Move>, String> {
+    let domains = root.join("specs").join("domains");
+    match rename {
+        Rename::Domain { from, to } => {
+            if !domains.join(from).is_dir() {
+                return Err(format!("Domain {} not found", from));
+            }
+            let id = domain_id(to.trim());
+            check_id("Domain", &id)?;
+            if id == *from {
+                return Ok(None);
+            }
+            if domains.join(&id).exists() {
+                return Err(format!("Domain {} already exists", id));
+            }
+            Ok(Some(Move {
+                from: format!("specs/domains/{}", from),
+                to: format!("specs/domains/{}", id),
+            }))
+        }
+        Rename::Flow { domain, from, to } => {
+            check_id("Flow", to)?;
+            let flows = domains.join(domain).join("flows");
+            if !flows.join(format!("{}.yaml", from)).is_file() {
+                return Err(format!("Flow {}/{} not found", domain, from));
+            }
+            if from == to {
+                return Ok(None);
+            }
+            if flows.join(format!("{}.yaml", to)).exists() {
+                return Err(format!("Flow {}/{} already exists", domain, to));
+            }
+            Ok(Some(Move {
+                from: format!("specs/domains/{}/flows/{}.yaml", domain, from),
+                to: format!("specs/domains/{}/flows/{}.yaml", domain, to),
+            }))
+        }
+        Rename::Event { to, .. } => {
+            if to.trim().is_empty() {
+                return Err("Event name cannot be empty".to_string());
+            }
+            Ok(None)
+        }
+        Rename::Node {
+            domain,
+            flow,
+            from,
+            to,
+        } => {
+            if to.trim().is_empty() {
+                return Err("Node id cannot be empty".to_string());
+            }
+            let doc = read_flow(root, domain, flow)?;
+            if doc.node(from).is_none() {
+                return Err(format!("Node {} not found in {}/{}", from, domain, flow));
+            }
+            if from != to && doc.node(to).is_some() {
+                return Err(format!("Node {} already exists in {}/{}", to, domain, flow));
+            }
+            Ok(None)
+        }
+    }
+}
+
+fn rewrite(
+    rename: &Rename,
+    document: &Document,
+    content: &str,
+) -> Result<Option<(String, usize)>, String> {
+    match document {
+        Document::Project => edit_json(content, |p| rename_in_project(rename, p)),
+        Document::SystemLayout => edit_yaml(content, |l| rename_in_layout(rename, l)),
+        Document::Domain(domain) => edit_yaml(content, |c| rename_in_domain(rename, domain, c)),
+        Document::Flow(domain) => edit_yaml(content, |d| rename_in_flow(rename, domain, d)),
+        Document::Mapping => edit_yaml::<Value>(content, |m| rename_in_mapping(rename, m)),
+    }
+}
+
+/// Where a file ends up after `moved`, if it is or lies under the moved path.
+fn moved_path(relative: &str, moved: Option<&Move>) -> Option<String> {
+    let moved = moved?;
+    if relative == moved.from {
+        return Some(moved.to.clone());
+    }
+    relative
+        .strip_prefix(&moved.from)
+        .filter(|rest| rest.starts_with('/'))
+        .map(|rest| format!("{}{}", moved.to, rest))
+}
+
+fn unified_diff(old_path: &str, new_path: &str, old: &str, new: &str) -> String {
+    Patch::from_buffers(
+        old.as_bytes(),
+        Some(Path::new(old_path)),
+        new.as_bytes(),
+        Some(Path::new(new_path)),
+        None,
+    )
+    .and_then(|mut patch| patch.to_buf())
+    .map(|buf| String::from_utf8_lossy(&buf).into_owned())
+    .unwrap_or_default()
+}
+
+fn plan_rename(
+    root: &Path,
+    rename: &Rename,
+) -> Result<(RenamePlan, Vec<Rewrite>, Option<Move>), String> {
+    let moved = check(root, rename)?;
+    let mut plan = RenamePlan {
+        rename: rename.clone(),
+        edits: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut rewrites = Vec::new();
+    for (path, document) in project_documents(root) {
+        let relative = relative_path(root, &path);
+        let new_path = moved_path(&relative, moved.as_ref());
+        let skip = |message: String| SpecParseError {
+            path: relative.clone(),
+            message,
+            line: None,
+            column: None,
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                plan.skipped.push(skip(format!("Failed to read: {}", e)));
+                continue;
+            }
+        };
+        let rewritten = match rewrite(rename, &document, &content) {
+            Ok(rewritten) => rewritten,
+            Err(e) => {
+                plan.skipped.push(skip(e));
+                continue;
+            }
+        };
+        let Some((new_content, references)) = rewritten else {
+            if new_path.is_some() {
+                plan.edits.push(RenameEdit {
+                    path: relative,
+                    new_path,
+                    references: 0,
+                    diff: String::new(),
+                });
+            }
+            continue;
+        };
+        let target = new_path.clone().unwrap_or_else(|| relative.clone());
+        plan.edits.push(RenameEdit {
+            diff: unified_diff(&relative, &target, &content, &new_content),
+            path: relative,
+            new_path,
+            references,
+        });
+        rewrites.push(Rewrite {
+            relative: target,
+            hash: hash_bytes(content.as_bytes()),
+            content: new_content,
+        });
+    }
+    Ok((plan, rewrites, moved))
+}
+
+fn project_root(sandbox: &ProjectSandbox) -> Result<PathBuf, String> {
+    sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: "specs".to_string(),
+        }
+        .to_string()
+    })
+}
+
+/// Perform the move and write every rewrite in one batch.
+fn write_rename(
+    sandbox: &ProjectSandbox,
+    root: &Path,
+    rewrites: &[Rewrite],
+    moved: Option<&Move>,
+) -> Result<(), String> {
+    let absolute = |relative: &str| root.join(relative).to_string_lossy().to_string();
+    // Move first so the writes' backups don't travel with a renamed directory.
+    let mut operations: Vec<FileOperation> = moved
+        .map(|moved| FileOperation::Rename {
+            from: absolute(&moved.from),
+            to: absolute(&moved.to),
+            overwrite: false,
+        })
+        .into_iter()
+        .collect();
+    operations.extend(rewrites.iter().map(|r| FileOperation::Write {
+        path: absolute(&r.relative),
+        contents: r.content.clone(),
+        expected_hash: Some(r.hash.clone()),
+    }));
+    let report = apply_batch(sandbox, operations);
+    if !report.committed {
+        let failure = report
+            .operations
+            .iter()
+            .find_map(|o| o.error.clone())
+            .unwrap_or_else(|| "unknown error".to_string());
+        let mut message = format!("Rename failed: {}", failure);
+        if !report.rollback_errors.is_empty() {
+            message.push_str(&format!(
+                "; rollback: {}",
+                report.rollback_errors.join("; ")
+            ));
+        }
+        return Err(message);
+    }
+    Ok(())
+}
+
+/// Find every reference to a domain, flow, event or node id across the project and
+/// show the edits a rename would make, without writing anything.
+///
+/// References are found through the typed spec model: event wiring, trigger events,
+/// event nodes, sub_flow, handoff, orchestrator and agent group targets, domain
+/// layouts and groups, the system layout, `ddd-project.json` and the keys of
+/// `.ddd/mapping.yaml`.
+#[tauri::command]
+pub async fn preview_rename(
+    sandbox: State<'_, ProjectSandbox>,
+    rename: Rename,
+) -> Result<RenamePlan, String> {
+    let root = project_root(&sandbox)?;
+    tokio::task::spawn_blocking(move || plan_rename(&root, &rename).map(|(plan, _, _)| plan))
+        .await
+        .map_err(|e| format!("Rename failed: {}", e))?
+}
+
+/// Apply a rename as one transaction: every file is rewritten and moved, or, if any
+/// step fails or a file changed since it was read, none is. Returns what was applied.
+#[tauri::command]
+pub async fn apply_rename(
+    sandbox: State<'_, ProjectSandbox>,
+    rename: Rename,
+) -> Result<RenamePlan, String> {
+    let root = project_root(&sandbox)?;
+    let planning_root = root.clone();
+    let (plan, rewrites, moved) =
+        tokio::task::spawn_blocking(move || plan_rename(&planning_root, &rename))
+            .await
+            .map_err(|e| format!("Rename failed: {}", e))??;
+
+    write_rename(&sandbox, &root, &rewrites, moved.as_ref())?;
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow_yaml(id: &str, domain: &str, nodes: &str) -> String {
+        format!(
+            "flow:\n  id: {}\n  name: {}\n  domain: {}\ntrigger:\n  id: trigger-1\n  type: trigger\n  label: Start\nnodes:\n{}",
+            id, id, domain, nodes
+        )
+    }
+
+    /// A project with two domains, where `users/signup` calls `billing/payment`.
+    fn project() -> (tempfile::TempDir, PathBuf, ProjectSandbox) {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = ProjectSandbox::new(Vec::new());
+        sandbox.approve(dir.path());
+        let root = sandbox
+            .set_root(dir.path().to_str().unwrap(), false)
+            .unwrap();
+        let files = [
+            (
+                "ddd-project.json",
+                "{\n  \"name\": \"Shop\",\n  \"domains\": [\n    {\n      \"name\": \"Billing\"\n    },\n    {\n      \"name\": \"Users\"\n    }\n  ]\n}\n".to_string(),
+            ),
+            (
+                "specs/system-layout.yaml",
+                "domains:\n  billing: {x: 0, y: 0}\n  users: {x: 100, y: 0}\n".to_string(),
+            ),
+            (
+                "specs/domains/billing/domain.yaml",
+                "name: Billing # payments and invoices\nflows:\n  - id: payment\n    name: Payment\nlayout:\n  flows:\n    payment: {x: 1, y: 2}\n".to_string(),
+            ),
+            (
+                "specs/domains/billing/flows/payment.yaml",
+                flow_yaml("payment", "billing", "  - id: charge\n    type: process\n    label: Charge\n"),
+            ),
+            (
+                "specs/domains/users/domain.yaml",
+                "name: Users\n".to_string(),
+            ),
+            (
+                "specs/domains/users/flows/signup.yaml",
+                flow_yaml(
+                    "signup",
+                    "users",
+                    "  - id: pay\n    type: sub_flow\n    spec:\n      flow_ref: billing/payment # first charge\n    label: Pay\n",
+                ),
+            ),
+            (
+                ".ddd/mapping.yaml",
+                "flows:\n  billing/payment:\n    spec: specs/domains/billing/flows/payment.yaml\n".to_string(),
+            ),
+        ];
+        for (relative, content) in files {
+            let path = root.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        (dir, root, sandbox)
+    }
+
+    fn read(root: &Path, relative: &str) -> String {
+        fs::read_to_string(root.join(relative)).unwrap()
+    }
+
+    fn apply(sandbox: &ProjectSandbox, root: &Path, rename: &Rename) -> RenamePlan {
+        let (plan, rewrites, moved) = plan_rename(root, rename).unwrap();
+        write_rename(sandbox, root, &rewrites, moved.as_ref()).unwrap();
+        plan
+    }
+
+    fn edits(plan: &RenamePlan) -> Vec<(&str, Option<&str>, usize)> {
+        plan.edits
+            .iter()
+            .map(|e| (e.path.as_str(), e.new_path.as_deref(), e.references))
+            .collect()
+    }
+
+    #[test]
+    fn flow_renames_move_the_file_and_update_references() {
+        let (_dir, root, sandbox) = project();
+        let rename = Rename::Flow {
+            domain: "billing".to_string(),
+            from: "payment".to_string(),
+            to: "checkout".to_string(),
+        };
+        let plan = apply(&sandbox, &root, &rename);
+        assert_eq!(
+            edits(&plan),
+            vec![
+                ("specs/domains/billing/domain.yaml", None, 2),
+                (
+                    "specs/domains/billing/flows/payment.yaml",
+                    Some("specs/domains/billing/flows/checkout.yaml"),
+                    1
+                ),
+                ("specs/domains/users/flows/signup.yaml", None, 1),
+                (".ddd/mapping.yaml", None, 1),
+            ]
+        );
+        assert!(plan.skipped.is_empty());
+        assert!(plan.edits[2].diff.contains(
+            "-      flow_ref: billing/payment # first charge\n+      flow_ref: billing/checkout # first charge\n"
+        ));
+
+        assert!(!root
+            .join("specs/domains/billing/flows/payment.yaml")
+            .exists());
+        assert!(
+            read(&root, "specs/domains/billing/flows/checkout.yaml").contains("  id: checkout\n")
+        );
+        assert_eq!(
+            read(&root, "specs/domains/billing/domain.yaml"),
+            "name: Billing # payments and invoices\nflows:\n  - id: checkout\n    name: Payment\nlayout:\n  flows:\n    checkout: {x: 1, y: 2}\n"
+        );
+        assert_eq!(
+            read(&root, ".ddd/mapping.yaml"),
+            "flows:\n  billing/checkout:\n    spec: specs/domains/billing/flows/checkout.yaml\n"
+        );
+    }
+
+    #[test]
+    fn domain_renames_move_the_directory() {
+        let (_dir, root, sandbox) = project();
+        let rename = Rename::Domain {
+            from: "billing".to_string(),
+            to: "Billing Ops".to_string(),
+        };
+        let plan = apply(&sandbox, &root, &rename);
+        let moved: Vec<_> = plan
+            .edits
+            .iter()
+            .filter_map(|e| e.new_path.as_deref())
+            .collect();
+        assert_eq!(
+            moved,
+            vec![
+                "specs/domains/billing-ops/domain.yaml",
+                "specs/domains/billing-ops/flows/payment.yaml",
+            ]
+        );
+
+        assert!(!root.join("specs/domains/billing").exists());
+        assert!(read(&root, "specs/domains/billing-ops/domain.yaml")
+            .starts_with("name: Billing Ops # payments and invoices\n"));
+        assert!(read(&root, "specs/domains/billing-ops/flows/payment.yaml")
+            .contains("  domain: billing-ops\n"));
+        assert!(read(&root, "specs/domains/users/flows/signup.yaml")
+            .contains("flow_ref: billing-ops/payment # first charge\n"));
+        assert_eq!(
+            read(&root, "specs/system-layout.yaml"),
+            "domains:\n  billing-ops: {x: 0, y: 0}\n  users: {x: 100, y: 0}\n"
+        );
+        assert!(read(&root, "ddd-project.json").contains("\"name\": \"Billing Ops\""));
+        assert!(read(&root, ".ddd/mapping.yaml").contains("  billing-ops/payment:\n"));
+    }
+
+    #[test]
+    fn renames_that_clash_or_miss_are_refused() {
+        let (_dir, root, _sandbox) = project();
+        let cases = [
+            (
+                Rename::Flow {
+                    domain: "billing".to_string(),
+                    from: "payment".to_string(),
+                    to: "a/b".to_string(),
+                },
+                "Invalid flow id: \"a/b\"",
+            ),
+            (
+                Rename::Flow {
+                    domain: "users".to_string(),
+                    from: "payment".to_string(),
+                    to: "signup".to_string(),
+                },
+                "Flow users/payment not found",
+            ),
+            (
+                Rename::Domain {
+                    from: "users".to_string(),
+                    to: "Billing".to_string(),
+                },
+                "Domain billing already exists",
+            ),
+            (
+                Rename::Node {
+                    domain: "billing".to_string(),
+                    flow: "payment".to_string(),
+                    from: "missing".to_string(),
+                    to: "charge-2".to_string(),
+                },
+                "Node missing not found in billing/payment",
+            ),
+            (
+                Rename::Node {
+                    domain: "billing".to_string(),
+                    flow: "payment".to_string(),
+                    from: "charge".to_string(),
+                    to: "trigger-1".to_string(),
+                },
+                "Node trigger-1 already exists in billing/payment",
+            ),
+        ];
+        for (rename, message) in cases {
+            assert_eq!(plan_rename(&root, &rename).err().as_deref(), Some(message));
+        }
+    }
+
+    #[test]
+    fn files_changed_after_planning_abort_the_rename() {
+        let (_dir, root, sandbox) = project();
+        let rename = Rename::Event {
+            from: "Unused".to_string(),
+            to: "Other".to_string(),
+        };
+        let (plan, rewrites, _) = plan_rename(&root, &rename).unwrap();
+        assert!(plan.edits.is_empty() && rewrites.is_empty());
+
+        let rename = Rename::Flow {
+            domain: "billing".to_string(),
+            from: "payment".to_string(),
+            to: "checkout".to_string(),
+        };
+        let (_, rewrites, moved) = plan_rename(&root, &rename).unwrap();
+        fs::write(root.join(".ddd/mapping.yaml"), "flows: {}\n").unwrap();
+        let err = write_rename(&sandbox, &root, &rewrites, moved.as_ref()).unwrap_err();
+        assert!(err.starts_with("Rename failed: "), "{}", err);
+        assert!(root
+            .join("specs/domains/billing/flows/payment.yaml")
+            .exists());
+        assert!(read(&root, "specs/domains/users/flows/signup.yaml").contains("billing/payment"));
+        assert_eq!(read(&root, ".ddd/mapping.yaml"), "flows: {}\n");
+    }
+}
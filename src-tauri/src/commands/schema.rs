@@ -0,0 +1,184 @@
+use super::file::write_atomic;
+use super::search::spec_files;
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use crate::spec::schema::{SchemaKind, SCHEMA_DIR};
+use crate::validation::{validate_schema, SchemaIssue};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+fn project_root(sandbox: &ProjectSandbox, path: &str) -> Result<PathBuf, String> {
+    sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: path.to_string(),
+        }
+        .to_string()
+    })
+}
+
+/// Write the flow, domain and system-layout schemas to `.ddd/schema/`. Files whose
+/// content is unchanged are left alone. Returns the project-relative paths written.
+#[tauri::command]
+pub fn write_spec_schemas(sandbox: State<'_, ProjectSandbox>) -> Result<Vec<String>, String> {
+    let root = project_root(&sandbox, SCHEMA_DIR)?;
+    let dir = root.join(SCHEMA_DIR);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create directory {}: {}", SCHEMA_DIR, e))?;
+    let mut written = Vec::new();
+    for kind in SchemaKind::ALL {
+        let path = dir.join(kind.file_name());
+        let mut content = serde_json::to_string_pretty(&kind.schema())
+            .map_err(|e| format!("Failed to serialize schema: {}", e))?;
+        content.push('\n');
+        if fs::read_to_string(&path).ok().as_deref() == Some(content.as_str()) {
+            continue;
+        }
+        write_atomic(&path, content.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        written.push(format!("{}/{}", SCHEMA_DIR, kind.file_name()));
+    }
+    Ok(written)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FileSchemaIssues {
+    /// Relative to the project root.
+    pub path: String,
+    pub kind: SchemaKind,
+    pub issues: Vec<SchemaIssue>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SchemaValidation {
+    /// Number of spec files checked.
+    pub checked: usize,
+    /// Only files with at least one issue.
+    pub files: Vec<FileSchemaIssues>,
+}
+
+fn validate_files(root: &Path, paths: Vec<PathBuf>) -> SchemaValidation {
+    let mut report = SchemaValidation {
+        checked: 0,
+        files: Vec::new(),
+    };
+    for path in paths {
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let Some(kind) = SchemaKind::for_path(relative) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        report.checked += 1;
+        let issues = match fs::read_to_string(&path) {
+            Ok(content) => validate_schema(kind, &content),
+            Err(e) => vec![SchemaIssue {
+                instance_path: String::new(),
+                message: format!("Failed to read: {}", e),
+                line: None,
+                column: None,
+            }],
+        };
+        if !issues.is_empty() {
+            report.files.push(FileSchemaIssues {
+                path: relative,
+                kind,
+                issues,
+            });
+        }
+    }
+    report
+}
+
+/// Check spec files against the generated schemas. With no `paths`, every flow,
+/// domain and system-layout file of the project is checked; paths that aren't spec
+/// files are skipped.
+#[tauri::command]
+pub async fn validate_spec_schemas(
+    sandbox: State<'_, ProjectSandbox>,
+    paths: Option<Vec<String>>,
+) -> Result<SchemaValidation, String> {
+    let root = project_root(&sandbox, "specs")?;
+    let paths = match paths {
+        Some(paths) => paths
+            .iter()
+            .map(|p| sandbox.resolve(p).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => {
+            let layout = root.join("specs").join("system-layout.yaml");
+            layout
+                .is_file()
+                .then_some(layout)
+                .into_iter()
+                .chain(spec_files(&root).into_iter().map(|f| f.path))
+                .collect()
+        }
+    };
+    tokio::task::spawn_blocking(move || validate_files(&root, paths))
+        .await
+        .map_err(|e| format!("Schema validation failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_project() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/sample-project")
+    }
+
+    #[test]
+    fn sample_project_matches_the_schemas() {
+        let root = sample_project();
+        let paths = spec_files(&root).into_iter().map(|f| f.path).collect();
+        let report = validate_files(&root, paths);
+        // Three domains and five flows
+        assert_eq!(report.checked, 8);
+        assert!(report.files.is_empty(), "{:?}", report.files);
+    }
+
+    #[test]
+    fn only_spec_files_are_checked_and_issues_name_their_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let flows = dir.path().join("specs/domains/billing/flows");
+        fs::create_dir_all(&flows).unwrap();
+        let flow = include_str!(
+            "../../fixtures/sample-project/specs/domains/billing/flows/payment-processing.yaml"
+        );
+        fs::write(
+            flows.join("payment-processing.yaml"),
+            flow.replace("timeout_ms: 10000", "timeout_ms: soon"),
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.yaml"), "not: [a spec").unwrap();
+
+        let report = validate_files(
+            dir.path(),
+            vec![
+                flows.join("payment-processing.yaml"),
+                dir.path().join("notes.yaml"),
+                dir.path().join("specs/domains/billing/domain.yaml"),
+            ],
+        );
+        assert_eq!(report.checked, 2);
+        let paths: Vec<(&str, SchemaKind)> = report
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.kind))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                (
+                    "specs/domains/billing/flows/payment-processing.yaml",
+                    SchemaKind::Flow
+                ),
+                ("specs/domains/billing/domain.yaml", SchemaKind::Domain),
+            ]
+        );
+        // Node specs are matched by type as a whole
+        assert_eq!(report.files[0].issues[0].instance_path, "/nodes/1/spec");
+        assert!(report.files[1].issues[0]
+            .message
+            .starts_with("Failed to read: "));
+    }
+}
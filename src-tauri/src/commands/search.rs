@@ -0,0 +1,783 @@
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+use tauri::State;
+
+const DEFAULT_LIMIT: usize = 200;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SpecFileKind {
+    Domain,
+    Flow,
+}
+
+/// A spec file found under `specs/domains/`.
+pub struct SpecFile {
+    pub path: PathBuf,
+    pub domain_id: String,
+    pub kind: SpecFileKind,
+}
+
+/// Every `domain.yaml` and flow file of the project, in directory order.
+pub fn spec_files(root: &Path) -> Vec<SpecFile> {
+    let domains_dir = root.join("specs").join("domains");
+    let mut files = Vec::new();
+    let Ok(domains) = fs::read_dir(&domains_dir) else {
+        return files;
+    };
+    let mut domain_dirs: Vec<PathBuf> = domains
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    domain_dirs.sort();
+
+    for dir in domain_dirs {
+        let domain_id = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let domain_yaml = dir.join("domain.yaml");
+        if domain_yaml.is_file() {
+            files.push(SpecFile {
+                path: domain_yaml,
+                domain_id: domain_id.clone(),
+                kind: SpecFileKind::Domain,
+            });
+        }
+        let Ok(flows) = fs::read_dir(dir.join("flows")) else {
+            continue;
+        };
+        let mut flow_paths: Vec<PathBuf> = flows
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.is_file()
+                    && matches!(
+                        p.extension().and_then(|e| e.to_str()),
+                        Some("yaml") | Some("yml")
+                    )
+            })
+            .collect();
+        flow_paths.sort();
+        files.extend(flow_paths.into_iter().map(|path| SpecFile {
+            path,
+            domain_id: domain_id.clone(),
+            kind: SpecFileKind::Flow,
+        }));
+    }
+    files
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    Domain,
+    Flow,
+    Node,
+}
+
+/// One searchable unit: a domain, a flow header or a single node.
+struct SearchRecord {
+    kind: RecordKind,
+    domain: String,
+    flow_id: Option<String>,
+    node_id: Option<String>,
+    node_type: Option<String>,
+    label: Option<String>,
+    /// Flattened string leaves as (dotted path without array indices, value).
+    fields: Vec<(String, String)>,
+    /// Lowercased copy of each `fields` value, in the same order.
+    lowered: Vec<String>,
+    consumes: Vec<String>,
+    publishes: Vec<String>,
+    /// Lowercased concatenation of all field values, for free-text matching.
+    haystack: String,
+}
+
+struct IndexedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    records: Vec<SearchRecord>,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct IndexState {
+    root: Option<PathBuf>,
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+/// In-memory index of the open project's specs, refreshed incrementally by mtime/size
+/// before each query. Managed as Tauri state; clones share the same index.
+#[derive(Default, Clone)]
+pub struct SpecIndex {
+    state: Arc<Mutex<IndexState>>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldMatchMode {
+    Contains,
+    Equals,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FieldFilter {
+    /// Dotted field path such as `spec.url`; also matches as a suffix (`url`).
+    pub path: String,
+    pub value: String,
+    pub mode: Option<FieldMatchMode>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SearchQuery {
+    /// Free text; every whitespace-separated term must appear (case-insensitive).
+    pub text: Option<String>,
+    pub node_type: Option<String>,
+    pub domain: Option<String>,
+    pub flow: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<FieldFilter>,
+    pub consumes_event: Option<String>,
+    pub publishes_event: Option<String>,
+    pub kinds: Option<Vec<String>>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct FieldHit {
+    pub field: String,
+    pub value: String,
+}
+
+#[derive(Serialize)]
+pub struct SearchHit {
+    pub kind: RecordKind,
+    pub domain: String,
+    pub flow_id: Option<String>,
+    pub node_id: Option<String>,
+    pub node_type: Option<String>,
+    pub label: Option<String>,
+    pub file: String,
+    pub matches: Vec<FieldHit>,
+    pub score: u32,
+}
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub hits: Vec<SearchHit>,
+    pub total: usize,
+    pub indexed_files: usize,
+    pub reindexed_files: usize,
+    pub elapsed_ms: u64,
+    /// Spec files that could not be parsed and were left out of the index.
+    pub errors: Vec<String>,
+}
+
+fn flatten(value: &Value, prefix: &str, out: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(child, &path, out);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                flatten(item, prefix, out);
+            }
+        }
+        Value::String(s) => out.push((prefix.to_string(), s.clone())),
+        Value::Number(n) => out.push((prefix.to_string(), n.to_string())),
+        Value::Bool(b) => out.push((prefix.to_string(), b.to_string())),
+        Value::Null => {}
+    }
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// Event names from a wiring list, accepting both `- OrderPlaced` and `- event: OrderPlaced`.
+fn wiring_events(list: Option<&Value>) -> Vec<(String, Option<String>, Option<String>)> {
+    let Some(Value::Array(items)) = list else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Value::String(name) => Some((name.clone(), None, None)),
+            Value::Object(_) => str_field(item, "event").map(|name| {
+                (
+                    name,
+                    str_field(item, "from_flow"),
+                    str_field(item, "handled_by_flow"),
+                )
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn make_record(
+    kind: RecordKind,
+    domain: &str,
+    flow_id: Option<String>,
+    node: Option<&Value>,
+    fields: Vec<(String, String)>,
+) -> SearchRecord {
+    let lowered: Vec<String> = fields.iter().map(|(_, v)| v.to_lowercase()).collect();
+    let haystack = lowered.join("\n");
+    SearchRecord {
+        kind,
+        domain: domain.to_string(),
+        flow_id,
+        node_id: node.and_then(|n| str_field(n, "id")),
+        node_type: node.and_then(|n| str_field(n, "type")),
+        label: node.and_then(|n| str_field(n, "label")),
+        fields,
+        lowered,
+        consumes: Vec::new(),
+        publishes: Vec::new(),
+        haystack,
+    }
+}
+
+fn index_domain(domain_id: &str, doc: &Value) -> Vec<SearchRecord> {
+    let mut fields = Vec::new();
+    for key in [
+        "name",
+        "description",
+        "role",
+        "flows",
+        "groups",
+        "owns_schemas",
+    ] {
+        if let Some(v) = doc.get(key) {
+            flatten(v, key, &mut fields);
+        }
+    }
+    let mut record = make_record(RecordKind::Domain, domain_id, None, None, fields);
+    record.label = str_field(doc, "name");
+    record.consumes = wiring_events(doc.get("consumes_events"))
+        .into_iter()
+        .map(|(name, _, _)| name)
+        .collect();
+    record.publishes = wiring_events(doc.get("publishes_events"))
+        .into_iter()
+        .map(|(name, _, _)| name)
+        .collect();
+    vec![record]
+}
+
+/// Events a trigger listens to: `spec.event` may be a string or a list.
+fn trigger_events(trigger: &Value) -> Vec<String> {
+    match trigger.get("spec").and_then(|s| s.get("event")) {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn index_flow(
+    domain_id: &str,
+    file_stem: &str,
+    doc: &Value,
+    domain_doc: Option<&Value>,
+) -> Vec<SearchRecord> {
+    let header = doc.get("flow").cloned().unwrap_or(Value::Null);
+    let flow_id = str_field(&header, "id").unwrap_or_else(|| file_stem.to_string());
+
+    let mut nodes: Vec<&Value> = Vec::new();
+    if let Some(trigger) = doc.get("trigger").filter(|t| t.is_object()) {
+        nodes.push(trigger);
+    }
+    if let Some(Value::Array(items)) = doc.get("nodes") {
+        nodes.extend(items.iter());
+    }
+
+    let mut records = Vec::with_capacity(nodes.len() + 1);
+    let mut flow_consumes = Vec::new();
+    let mut flow_publishes = Vec::new();
+
+    for node in nodes {
+        let mut fields = Vec::new();
+        for key in ["label", "type", "spec", "observability", "security"] {
+            if let Some(v) = node.get(key) {
+                flatten(v, key, &mut fields);
+            }
+        }
+        let mut record = make_record(
+            RecordKind::Node,
+            domain_id,
+            Some(flow_id.clone()),
+            Some(node),
+            fields,
+        );
+        match record.node_type.as_deref() {
+            Some("trigger") => record.consumes = trigger_events(node),
+            Some("event") => {
+                let spec = node.get("spec");
+                let name = spec.and_then(|s| str_field(s, "event_name"));
+                let direction = spec.and_then(|s| str_field(s, "direction"));
+                if let Some(name) = name {
+                    if direction.as_deref() == Some("consume") {
+                        record.consumes.push(name);
+                    } else {
+                        record.publishes.push(name);
+                    }
+                }
+            }
+            _ => {}
+        }
+        flow_consumes.extend(record.consumes.iter().cloned());
+        flow_publishes.extend(record.publishes.iter().cloned());
+        records.push(record);
+    }
+
+    // Domain-level wiring can attribute events to this flow explicitly.
+    if let Some(domain_doc) = domain_doc {
+        for (name, _, handled_by) in wiring_events(domain_doc.get("consumes_events")) {
+            if handled_by.as_deref() == Some(flow_id.as_str()) {
+                flow_consumes.push(name);
+            }
+        }
+        for (name, from_flow, _) in wiring_events(domain_doc.get("publishes_events")) {
+            if from_flow.as_deref() == Some(flow_id.as_str()) {
+                flow_publishes.push(name);
+            }
+        }
+    }
+
+    let mut fields = Vec::new();
+    flatten(&header, "flow", &mut fields);
+    let mut flow_record = make_record(
+        RecordKind::Flow,
+        domain_id,
+        Some(flow_id.clone()),
+        None,
+        fields,
+    );
+    flow_record.label = str_field(&header, "name");
+    flow_consumes.sort();
+    flow_consumes.dedup();
+    flow_publishes.sort();
+    flow_publishes.dedup();
+    flow_record.consumes = flow_consumes;
+    flow_record.publishes = flow_publishes;
+    records.insert(0, flow_record);
+    records
+}
+
+fn parse_yaml(path: &Path) -> Result<Value, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_yaml::from_str::<Value>(&content).map_err(|e| e.to_string())
+}
+
+impl SpecIndex {
+    /// Bring the index in line with the files on disk, re-parsing only files whose
+    /// mtime or size changed. Returns how many files were re-parsed.
+    fn refresh(state: &mut IndexState, root: &Path) -> usize {
+        if state.root.as_deref() != Some(root) {
+            state.root = Some(root.to_path_buf());
+            state.files.clear();
+        }
+
+        let files = spec_files(root);
+        let mut seen = HashSet::with_capacity(files.len());
+        let mut changed: HashSet<&Path> = HashSet::new();
+        for file in &files {
+            seen.insert(file.path.clone());
+            let meta = fs::metadata(&file.path).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.as_ref().map(|m| m.len()).unwrap_or(0);
+            let fresh = state
+                .files
+                .get(&file.path)
+                .is_some_and(|f| f.modified == modified && f.len == len);
+            if !fresh {
+                changed.insert(&file.path);
+            }
+        }
+        state.files.retain(|path, _| seen.contains(path));
+
+        // Flow records depend on their domain.yaml wiring, so a changed domain
+        // re-indexes its flows too.
+        let changed_domains: HashSet<&str> = files
+            .iter()
+            .filter(|f| f.kind == SpecFileKind::Domain && changed.contains(f.path.as_path()))
+            .map(|f| f.domain_id.as_str())
+            .collect();
+        let to_index: Vec<&SpecFile> = files
+            .iter()
+            .filter(|f| {
+                changed.contains(f.path.as_path())
+                    || (f.kind == SpecFileKind::Flow
+                        && changed_domains.contains(f.domain_id.as_str()))
+            })
+            .collect();
+
+        let mut domain_docs: HashMap<&str, Option<Value>> = HashMap::new();
+        for file in &to_index {
+            let meta = fs::metadata(&file.path).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.as_ref().map(|m| m.len()).unwrap_or(0);
+
+            let (records, error) = match parse_yaml(&file.path) {
+                Ok(doc) => {
+                    let records = match file.kind {
+                        SpecFileKind::Domain => index_domain(&file.domain_id, &doc),
+                        SpecFileKind::Flow => {
+                            let domain_doc = domain_docs
+                                .entry(file.domain_id.as_str())
+                                .or_insert_with(|| {
+                                    parse_yaml(
+                                        &root
+                                            .join("specs")
+                                            .join("domains")
+                                            .join(&file.domain_id)
+                                            .join("domain.yaml"),
+                                    )
+                                    .ok()
+                                });
+                            let stem = file
+                                .path
+                                .file_stem()
+                                .map(|s| s.to_string_lossy().to_string())
+                                .unwrap_or_default();
+                            index_flow(&file.domain_id, &stem, &doc, domain_doc.as_ref())
+                        }
+                    };
+                    (records, None)
+                }
+                Err(e) => (Vec::new(), Some(e)),
+            };
+            state.files.insert(
+                file.path.clone(),
+                IndexedFile {
+                    modified,
+                    len,
+                    records,
+                    error,
+                },
+            );
+        }
+        to_index.len()
+    }
+
+    pub fn search(&self, root: &Path, query: &SearchQuery) -> SearchResult {
+        let started = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let reindexed_files = Self::refresh(&mut state, root);
+
+        let terms: Vec<String> = query
+            .text
+            .as_deref()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|t| t.to_lowercase())
+            .collect();
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+
+        let mut hits = Vec::new();
+        let mut errors = Vec::new();
+        for (path, file) in state.files.iter() {
+            if let Some(error) = &file.error {
+                errors.push(format!("{}: {}", relative(root, path), error));
+            }
+            for record in &file.records {
+                if let Some(hit) = match_record(record, query, &terms) {
+                    hits.push(SearchHit {
+                        file: relative(root, path),
+                        ..hit
+                    });
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.domain.cmp(&b.domain))
+                .then_with(|| a.flow_id.cmp(&b.flow_id))
+                .then_with(|| a.node_id.cmp(&b.node_id))
+        });
+        errors.sort();
+        let total = hits.len();
+        hits.truncate(limit);
+
+        SearchResult {
+            hits,
+            total,
+            indexed_files: state.files.len(),
+            reindexed_files,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            errors,
+        }
+    }
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn path_matches(field: &str, filter: &str) -> bool {
+    field == filter || field.ends_with(&format!(".{}", filter))
+}
+
+fn match_record(record: &SearchRecord, query: &SearchQuery, terms: &[String]) -> Option<SearchHit> {
+    if let Some(kinds) = &query.kinds {
+        let kind = match record.kind {
+            RecordKind::Domain => "domain",
+            RecordKind::Flow => "flow",
+            RecordKind::Node => "node",
+        };
+        if !kinds.iter().any(|k| k == kind) {
+            return None;
+        }
+    }
+    if query.domain.as_deref().is_some_and(|d| d != record.domain) {
+        return None;
+    }
+    if let Some(flow) = &query.flow {
+        if record.flow_id.as_deref() != Some(flow.as_str()) {
+            return None;
+        }
+    }
+    if let Some(node_type) = &query.node_type {
+        if record.node_type.as_deref() != Some(node_type.as_str()) {
+            return None;
+        }
+    }
+    if let Some(event) = &query.consumes_event {
+        if !record.consumes.contains(event) {
+            return None;
+        }
+    }
+    if let Some(event) = &query.publishes_event {
+        if !record.publishes.contains(event) {
+            return None;
+        }
+    }
+
+    let mut matches = Vec::new();
+    for filter in &query.fields {
+        let needle = filter.value.to_lowercase();
+        let found: Vec<&(String, String)> = record
+            .fields
+            .iter()
+            .zip(&record.lowered)
+            .filter(|((path, _), lower)| {
+                path_matches(path, &filter.path)
+                    && match filter.mode {
+                        Some(FieldMatchMode::Equals) => **lower == needle,
+                        _ => lower.contains(&needle),
+                    }
+            })
+            .map(|(field, _)| field)
+            .collect();
+        if found.is_empty() {
+            return None;
+        }
+        matches.extend(found.into_iter().map(|(field, value)| FieldHit {
+            field: field.clone(),
+            value: value.clone(),
+        }));
+    }
+
+    let mut score = matches.len() as u32;
+    if !terms.is_empty() {
+        if !terms.iter().all(|t| record.haystack.contains(t.as_str())) {
+            return None;
+        }
+        for ((field, value), lower) in record.fields.iter().zip(&record.lowered) {
+            if terms.iter().any(|t| lower.contains(t.as_str())) {
+                // Label and name hits rank above matches deep inside a spec.
+                score += if field == "label" || field == "name" || field == "flow.name" {
+                    5
+                } else {
+                    1
+                };
+                matches.push(FieldHit {
+                    field: field.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+
+    Some(SearchHit {
+        kind: record.kind,
+        domain: record.domain.clone(),
+        flow_id: record.flow_id.clone(),
+        node_id: record.node_id.clone(),
+        node_type: record.node_type.clone(),
+        label: record.label.clone(),
+        file: String::new(),
+        matches,
+        score,
+    })
+}
+
+/// Search every flow and domain spec of the open project by free text and/or
+/// structured filters (node type, field values, consumed/published events).
+#[tauri::command]
+pub async fn search_specs(
+    sandbox: State<'_, ProjectSandbox>,
+    index: State<'_, SpecIndex>,
+    query: SearchQuery,
+) -> Result<SearchResult, String> {
+    let root = sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: "specs".to_string(),
+        }
+        .to_string()
+    })?;
+    // Refreshing walks and parses the specs while holding the index lock
+    let index = SpecIndex::clone(&index);
+    tokio::task::spawn_blocking(move || index.search(&root, &query))
+        .await
+        .map_err(|e| format!("Search failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const DOMAIN: &str = "name: Orders\ndescription: Order handling\n";
+
+    fn flow(label: &str) -> String {
+        format!(
+            "flow:\n  id: place-order\n  name: Place Order\ntrigger:\n  id: t\n  type: trigger\nnodes:\n- id: n1\n  type: process\n  label: {}\n",
+            label
+        )
+    }
+
+    fn project(flow_yaml: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let domain = dir.path().join("specs/domains/orders");
+        fs::create_dir_all(domain.join("flows")).unwrap();
+        fs::write(domain.join("domain.yaml"), DOMAIN).unwrap();
+        let flow_path = domain.join("flows/place-order.yaml");
+        fs::write(&flow_path, flow_yaml).unwrap();
+        (dir, flow_path)
+    }
+
+    fn text(text: &str) -> SearchQuery {
+        SearchQuery {
+            text: Some(text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn node_ids(result: &SearchResult) -> Vec<&str> {
+        result
+            .hits
+            .iter()
+            .filter_map(|h| h.node_id.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_files_are_not_reparsed() {
+        let (dir, _) = project(&flow("Reserve stock"));
+        let index = SpecIndex::default();
+
+        let first = index.search(dir.path(), &text("stock"));
+        assert_eq!(first.indexed_files, 2);
+        assert_eq!(first.reindexed_files, 2);
+        assert_eq!(node_ids(&first), vec!["n1"]);
+
+        let second = index.search(dir.path(), &text("stock"));
+        assert_eq!(second.reindexed_files, 0);
+        assert_eq!(node_ids(&second), vec!["n1"]);
+    }
+
+    #[test]
+    fn a_file_whose_length_changed_is_reindexed() {
+        let (dir, flow_path) = project(&flow("Reserve stock"));
+        let index = SpecIndex::default();
+        index.search(dir.path(), &text("stock"));
+
+        fs::write(&flow_path, flow("Charge the customer card")).unwrap();
+        let result = index.search(dir.path(), &text("stock"));
+        assert_eq!(result.reindexed_files, 1);
+        assert!(result.hits.is_empty());
+        assert_eq!(
+            node_ids(&index.search(dir.path(), &text("card"))),
+            vec!["n1"]
+        );
+    }
+
+    #[test]
+    fn a_file_whose_mtime_changed_is_reindexed() {
+        let (dir, flow_path) = project(&flow("Reserve stock"));
+        let index = SpecIndex::default();
+        index.search(dir.path(), &text("stock"));
+
+        // Same length, so only the modification time tells the edit apart.
+        let before = fs::metadata(&flow_path).unwrap().modified().unwrap();
+        fs::write(&flow_path, flow("Release stock")).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&flow_path)
+            .unwrap()
+            .set_modified(before + Duration::from_secs(10))
+            .unwrap();
+
+        let result = index.search(dir.path(), &text("release"));
+        assert_eq!(result.reindexed_files, 1);
+        assert_eq!(node_ids(&result), vec!["n1"]);
+    }
+
+    #[test]
+    fn deleted_files_drop_out_of_the_index() {
+        let (dir, flow_path) = project(&flow("Reserve stock"));
+        let index = SpecIndex::default();
+        assert_eq!(index.search(dir.path(), &text("stock")).hits.len(), 1);
+
+        fs::remove_file(&flow_path).unwrap();
+        let result = index.search(dir.path(), &text("stock"));
+        assert_eq!(result.indexed_files, 1);
+        assert_eq!(result.reindexed_files, 0);
+        assert!(result.hits.is_empty());
+    }
+
+    #[test]
+    fn field_filters_and_terms_ignore_case() {
+        let (dir, _) = project(&flow("Reserve Stock"));
+        let index = SpecIndex::default();
+        let query = SearchQuery {
+            fields: vec![FieldFilter {
+                path: "label".to_string(),
+                value: "RESERVE stock".to_string(),
+                mode: Some(FieldMatchMode::Equals),
+            }],
+            ..text("STOCK")
+        };
+        let result = index.search(dir.path(), &query);
+        assert_eq!(node_ids(&result), vec!["n1"]);
+        assert_eq!(result.hits[0].matches[0].value, "Reserve Stock");
+    }
+}
@@ -0,0 +1,116 @@
+//! Commit signing through the program git is configured with: `gpg.format` picks
+//! OpenPGP (`gpg`), X.509 (`gpgsm`) or SSH (`ssh-keygen`), `gpg.program` and
+//! friends override the binary and `user.signingkey` names the key.
+
+use git2::{Config, Signature};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn config_string(config: &Config, key: &str) -> Option<String> {
+    config
+        .get_string(key)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// `~/…` paths in `user.signingkey` are relative to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Run `program` with `buffer` on stdin and return what it printed.
+fn run_signer(program: &str, args: &[String], buffer: &[u8]) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run signing program {}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(buffer)
+            .map_err(|e| format!("Failed to pass the commit to {}: {}", program, e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Signing program {} failed: {}", program, e))?;
+    let signature = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() || signature.trim().is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to sign commit: {}", stderr.trim()));
+    }
+    Ok(signature)
+}
+
+/// Sign with `ssh-keygen -Y sign`. A literal public key in `user.signingkey`
+/// (`key::…` or `ssh-…`) is written to a temporary file and signed through the
+/// SSH agent, as git does.
+fn sign_ssh(config: &Config, buffer: &[u8]) -> Result<String, String> {
+    let program =
+        config_string(config, "gpg.ssh.program").unwrap_or_else(|| "ssh-keygen".to_string());
+    let key = config_string(config, "user.signingkey")
+        .ok_or_else(|| "SSH signing needs user.signingkey in git config".to_string())?;
+    let literal = key
+        .strip_prefix("key::")
+        .or_else(|| key.starts_with("ssh-").then_some(key.as_str()));
+
+    let mut args: Vec<String> = ["-Y", "sign", "-n", "git", "-f"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let Some(public_key) = literal else {
+        args.push(expand_home(&key).to_string_lossy().to_string());
+        return run_signer(&program, &args, buffer);
+    };
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let key_file = env::temp_dir().join(format!(".ddd-signing-key-{}.pub", nanos));
+    fs::write(&key_file, format!("{}\n", public_key.trim()))
+        .map_err(|e| format!("Failed to write signing key: {}", e))?;
+    args.push(key_file.to_string_lossy().to_string());
+    args.push("-U".to_string());
+    let result = run_signer(&program, &args, buffer);
+    let _ = fs::remove_file(&key_file);
+    result
+}
+
+/// Signature for the `gpgsig` header of the commit in `buffer`, as created by
+/// `Repository::commit_create_buffer`. `config` must be a snapshot.
+pub fn sign_commit(
+    config: &Config,
+    buffer: &[u8],
+    committer: &Signature,
+) -> Result<String, String> {
+    let format = config_string(config, "gpg.format").unwrap_or_else(|| "openpgp".to_string());
+    let program = match format.as_str() {
+        "ssh" => return sign_ssh(config, buffer),
+        "openpgp" => config_string(config, "gpg.openpgp.program")
+            .or_else(|| config_string(config, "gpg.program"))
+            .unwrap_or_else(|| "gpg".to_string()),
+        "x509" => config_string(config, "gpg.x509.program").unwrap_or_else(|| "gpgsm".to_string()),
+        other => return Err(format!("Unsupported gpg.format {}", other)),
+    };
+    // Without a configured key, sign as the committer like git does
+    let key = config_string(config, "user.signingkey").unwrap_or_else(|| {
+        format!(
+            "{} <{}>",
+            committer.name().unwrap_or(""),
+            committer.email().unwrap_or("")
+        )
+    });
+    let args = vec!["--status-fd=2".to_string(), "-bsau".to_string(), key];
+    run_signer(&program, &args, buffer)
+}
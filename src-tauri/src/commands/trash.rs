@@ -0,0 +1,322 @@
+use crate::commands::file::is_temp_sibling;
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// Trash location relative to the project root.
+pub const TRASH_DIR: &str = ".ddd/trash";
+const MANIFEST_FILE: &str = "manifest.json";
+const FILES_DIR: &str = "files";
+
+/// Applied after every move into the trash.
+pub const DEFAULT_RETENTION: TrashRetention = TrashRetention {
+    max_age_days: Some(30),
+    max_entries: Some(200),
+};
+
+static ENTRY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashItemKind {
+    File,
+    Dir,
+}
+
+/// Contents of `.ddd/trash/<id>/manifest.json`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrashEntry {
+    pub id: String,
+    /// Original location, relative to the project root with `/` separators.
+    pub original_path: String,
+    pub kind: TrashItemKind,
+    pub deleted_at_ms: i64,
+    /// Total size in bytes of the trashed file or directory.
+    pub size: u64,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct TrashRetention {
+    pub max_age_days: Option<u64>,
+    pub max_entries: Option<usize>,
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn trash_root(project_root: &Path) -> PathBuf {
+    project_root.join(TRASH_DIR)
+}
+
+fn project_root(sandbox: &ProjectSandbox) -> Result<PathBuf, String> {
+    sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: TRASH_DIR.to_string(),
+        }
+        .to_string()
+    })
+}
+
+/// Crash-recovery autosaves, kept only until the flow is saved or recovered.
+const AUTOSAVE_DIR: &str = ".ddd/autosave";
+
+/// Disposable tool state that is removed outright instead of trashed: autosaves, hidden
+/// temp files directly in `.ddd/` (generated prompts) and atomic-write temp siblings.
+pub fn is_scratch(project_root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(project_root) else {
+        return false;
+    };
+    let name = relative
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    (relative.starts_with(AUTOSAVE_DIR) && relative != Path::new(AUTOSAVE_DIR))
+        || (relative.parent() == Some(Path::new(".ddd")) && name.starts_with('.'))
+        || is_temp_sibling(&name)
+}
+
+/// Deletions inside the project go to the trash, except the trash itself (and its
+/// ancestors) and scratch state (see `is_scratch`).
+pub fn should_trash(project_root: &Path, path: &Path) -> bool {
+    match path.strip_prefix(project_root) {
+        Ok(relative) => {
+            relative.components().next().is_some()
+                && !relative.starts_with(TRASH_DIR)
+                && !Path::new(TRASH_DIR).starts_with(relative)
+                && !is_scratch(project_root, path)
+        }
+        Err(_) => false,
+    }
+}
+
+fn size_of(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| size_of(&e.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Move `source` into a new trash entry recording `original` as its location.
+/// `source` and `original` differ when the item was already moved aside (batch deletes).
+pub(crate) fn move_to_trash(
+    project_root: &Path,
+    source: &Path,
+    original: &Path,
+) -> io::Result<TrashEntry> {
+    let relative = original
+        .strip_prefix(project_root)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path is outside the project"))?;
+    let name = original
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+
+    let deleted_at_ms = now_ms();
+    let id = format!(
+        "{}-{}",
+        deleted_at_ms,
+        ENTRY_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let entry_dir = trash_root(project_root).join(&id);
+    let files_dir = entry_dir.join(FILES_DIR);
+    fs::create_dir_all(&files_dir)?;
+
+    let kind = if fs::symlink_metadata(source)?.is_dir() {
+        TrashItemKind::Dir
+    } else {
+        TrashItemKind::File
+    };
+    let entry = TrashEntry {
+        id,
+        original_path: relative.to_string_lossy().replace('\\', "/"),
+        kind,
+        deleted_at_ms,
+        size: size_of(source),
+    };
+
+    if let Err(e) = fs::rename(source, files_dir.join(name)) {
+        let _ = fs::remove_dir_all(&entry_dir);
+        return Err(e);
+    }
+    let manifest = serde_json::to_string_pretty(&entry).map_err(io::Error::other)?;
+    fs::write(entry_dir.join(MANIFEST_FILE), manifest)?;
+
+    // The move itself succeeded; retention that fails now runs again after the next one.
+    let _ = apply_retention(project_root, DEFAULT_RETENTION);
+    Ok(entry)
+}
+
+fn read_entries(project_root: &Path) -> io::Result<Vec<TrashEntry>> {
+    let root = trash_root(project_root);
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for dir in fs::read_dir(&root)?.filter_map(|e| e.ok()) {
+        let manifest = dir.path().join(MANIFEST_FILE);
+        let Ok(content) = fs::read_to_string(&manifest) else {
+            continue;
+        };
+        if let Ok(entry) = serde_json::from_str::<TrashEntry>(&content) {
+            // The directory name is authoritative; never trust an id that points elsewhere.
+            if dir.file_name().to_string_lossy() == entry.id {
+                entries.push(entry);
+            }
+        }
+    }
+    entries.sort_by_key(|e| std::cmp::Reverse(e.deleted_at_ms));
+    Ok(entries)
+}
+
+fn remove_entry(project_root: &Path, id: &str) -> io::Result<()> {
+    fs::remove_dir_all(trash_root(project_root).join(id))
+}
+
+/// Purge entries older than `max_age_days` and beyond the newest `max_entries`.
+/// Returns the number of entries removed.
+pub(crate) fn apply_retention(project_root: &Path, policy: TrashRetention) -> io::Result<usize> {
+    let entries = read_entries(project_root)?;
+    let cutoff = policy
+        .max_age_days
+        .map(|days| now_ms() - (days as i64) * 24 * 60 * 60 * 1000);
+
+    let mut removed = 0;
+    for (index, entry) in entries.iter().enumerate() {
+        let too_old = cutoff.is_some_and(|c| entry.deleted_at_ms < c);
+        let too_many = policy.max_entries.is_some_and(|max| index >= max);
+        if too_old || too_many {
+            remove_entry(project_root, &entry.id)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn find_entry(project_root: &Path, id: &str) -> Result<TrashEntry, String> {
+    read_entries(project_root)
+        .map_err(|e| format!("Failed to read trash: {}", e))?
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Trash entry {} not found", id))
+}
+
+/// List trash entries of the open project, newest first.
+#[tauri::command]
+pub fn list_trash(sandbox: State<'_, ProjectSandbox>) -> Result<Vec<TrashEntry>, String> {
+    let root = project_root(&sandbox)?;
+    read_entries(&root).map_err(|e| format!("Failed to read trash: {}", e))
+}
+
+/// Move a trashed file or directory back to its original location. Fails if something
+/// now exists there unless `overwrite` is set, in which case that item is trashed first.
+#[tauri::command]
+pub fn restore_trash_entry(
+    sandbox: State<'_, ProjectSandbox>,
+    id: String,
+    overwrite: Option<bool>,
+) -> Result<String, String> {
+    let root = project_root(&sandbox)?;
+    let entry = find_entry(&root, &id)?;
+
+    let original = root.join(&entry.original_path);
+    let target = sandbox
+        .resolve_removable(&original.to_string_lossy())
+        .map_err(|e| e.to_string())?;
+    let name = target
+        .file_name()
+        .ok_or_else(|| format!("Invalid original path {}", entry.original_path))?;
+    let stored = trash_root(&root).join(&id).join(FILES_DIR).join(name);
+
+    if fs::symlink_metadata(&target).is_ok() {
+        if !overwrite.unwrap_or(false) {
+            return Err(format!(
+                "Cannot restore {}: a file already exists there",
+                entry.original_path
+            ));
+        }
+        move_to_trash(&root, &target, &target)
+            .map_err(|e| format!("Failed to move {} aside: {}", entry.original_path, e))?;
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            format!(
+                "Failed to create directories for {}: {}",
+                entry.original_path, e
+            )
+        })?;
+    }
+    fs::rename(&stored, &target)
+        .map_err(|e| format!("Failed to restore {}: {}", entry.original_path, e))?;
+    remove_entry(&root, &id).map_err(|e| format!("Failed to clean up trash entry: {}", e))?;
+
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Permanently delete trash entries: one entry by `id`, or everything matching the
+/// given retention limits, or the whole trash when neither is given.
+#[tauri::command]
+pub fn purge_trash(
+    sandbox: State<'_, ProjectSandbox>,
+    id: Option<String>,
+    retention: Option<TrashRetention>,
+) -> Result<usize, String> {
+    let root = project_root(&sandbox)?;
+    if let Some(id) = id {
+        find_entry(&root, &id)?;
+        remove_entry(&root, &id).map_err(|e| format!("Failed to purge {}: {}", id, e))?;
+        return Ok(1);
+    }
+    let policy = retention.unwrap_or(TrashRetention {
+        max_age_days: None,
+        max_entries: Some(0),
+    });
+    apply_retention(&root, policy).map_err(|e| format!("Failed to purge trash: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_files_and_tool_config_go_to_the_trash() {
+        let root = Path::new("/p");
+        assert!(should_trash(root, Path::new("/p/specs/a/domain.yaml")));
+        assert!(should_trash(root, Path::new("/p/.ddd/config.yaml")));
+        assert!(should_trash(root, Path::new("/p/.ddd/mapping.yaml")));
+        assert!(!should_trash(root, Path::new("/p")));
+        assert!(!should_trash(root, Path::new("/elsewhere/file.yaml")));
+    }
+
+    #[test]
+    fn trash_and_scratch_state_are_not_trashed() {
+        let root = Path::new("/p");
+        assert!(!should_trash(root, Path::new("/p/.ddd")));
+        assert!(!should_trash(root, Path::new("/p/.ddd/trash")));
+        assert!(!should_trash(root, Path::new("/p/.ddd/trash/1-0/files")));
+
+        assert!(is_scratch(root, Path::new("/p/.ddd/autosave/flow.yaml")));
+        assert!(is_scratch(root, Path::new("/p/.ddd/.impl-prompt.md")));
+        assert!(is_scratch(root, Path::new("/p/specs/.flow.yaml.tmp-1-2")));
+        assert!(!should_trash(root, Path::new("/p/.ddd/autosave/flow.yaml")));
+
+        assert!(!is_scratch(root, Path::new("/p/.ddd/autosave")));
+        assert!(!is_scratch(root, Path::new("/p/.ddd/config.yaml")));
+        assert!(!is_scratch(root, Path::new("/p/.ddd/trash")));
+    }
+}
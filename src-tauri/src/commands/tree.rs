@@ -0,0 +1,287 @@
+use crate::commands::implementation::hash_bytes;
+use crate::sandbox::ProjectSandbox;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tauri::State;
+
+/// Project-specific ignore file, same syntax as `.gitignore`.
+pub const DDD_IGNORE_FILE: &str = ".dddignore";
+
+#[derive(Deserialize, Default)]
+pub struct ListTreeOptions {
+    /// Maximum depth below the root (1 = direct children only). Unlimited when absent.
+    pub max_depth: Option<usize>,
+    /// Only files matching one of these globs (relative to the root) are returned.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Files and directories matching these globs are skipped entirely.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Compute a SHA-256 for every file.
+    #[serde(default)]
+    pub hash: bool,
+    /// Include dotfiles. `.git` is always skipped.
+    #[serde(default)]
+    pub hidden: bool,
+    /// Honour `.gitignore`, `.git/info/exclude` and `.dddignore` (default true).
+    pub respect_ignore: Option<bool>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TreeEntry {
+    pub name: String,
+    pub path: String,
+    pub relative_path: String,
+    pub kind: EntryKind,
+    pub size: u64,
+    /// Last modification time in milliseconds since the Unix epoch.
+    pub modified_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<TreeEntry>>,
+}
+
+#[derive(Serialize)]
+pub struct TreeListing {
+    pub root: String,
+    pub entries: Vec<TreeEntry>,
+    pub file_count: usize,
+    pub dir_count: usize,
+    /// Entries that could not be read; the rest of the walk still completes.
+    pub errors: Vec<String>,
+}
+
+fn build_globset(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| format!("Invalid glob {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Failed to build glob set: {}", e))
+}
+
+fn relative_slash(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Walk `root` honouring ignore files and return a flat, unsorted list of entries.
+/// Shared by `list_tree` and other backend scans that need the same ignore rules.
+pub fn walk_project(
+    root: &Path,
+    options: &ListTreeOptions,
+) -> Result<(Vec<TreeEntry>, Vec<String>), String> {
+    let include = build_globset(&options.include)?;
+    let exclude = build_globset(&options.exclude)?;
+    let respect_ignore = options.respect_ignore.unwrap_or(true);
+
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(!options.hidden)
+        .git_ignore(respect_ignore)
+        .git_exclude(respect_ignore)
+        .git_global(respect_ignore)
+        .ignore(false)
+        .parents(respect_ignore)
+        .require_git(false)
+        .follow_links(false)
+        .max_depth(options.max_depth);
+    if respect_ignore {
+        builder.add_custom_ignore_filename(DDD_IGNORE_FILE);
+    }
+
+    let filter_root = root.to_path_buf();
+    let filter_exclude = exclude.clone();
+    builder.filter_entry(move |entry| {
+        if entry.file_name() == ".git" {
+            return false;
+        }
+        match &filter_exclude {
+            Some(set) => !set.is_match(relative_slash(&filter_root, entry.path())),
+            None => true,
+        }
+    });
+
+    let entries = Arc::new(Mutex::new(Vec::new()));
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let want_hash = options.hash;
+
+    builder.build_parallel().run(|| {
+        let entries = Arc::clone(&entries);
+        let errors = Arc::clone(&errors);
+        let include = include.clone();
+        Box::new(move |result| {
+            let entry = match result {
+                Ok(entry) => entry,
+                Err(e) => {
+                    errors.lock().unwrap_or_else(|e| e.into_inner()).push(e.to_string());
+                    return WalkState::Continue;
+                }
+            };
+            // Depth 0 is the root itself
+            if entry.depth() == 0 {
+                return WalkState::Continue;
+            }
+
+            let path = entry.path();
+            let relative_path = relative_slash(root, path);
+            let file_type = entry.file_type();
+            let kind = match file_type {
+                Some(t) if t.is_symlink() => EntryKind::Symlink,
+                Some(t) if t.is_dir() => EntryKind::Dir,
+                _ => EntryKind::File,
+            };
+            if kind != EntryKind::Dir {
+                if let Some(set) = &include {
+                    if !set.is_match(&relative_path) {
+                        return WalkState::Continue;
+                    }
+                }
+            }
+
+            let metadata = entry.metadata().ok();
+            let size = match (&metadata, kind) {
+                (Some(meta), EntryKind::File) => meta.len(),
+                _ => 0,
+            };
+            let modified_ms = metadata
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64);
+            let hash = if want_hash && kind == EntryKind::File {
+                match fs::read(path) {
+                    Ok(bytes) => Some(hash_bytes(&bytes)),
+                    Err(e) => {
+                        errors
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .push(format!("Failed to hash {}: {}", path.display(), e));
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            entries.lock().unwrap_or_else(|e| e.into_inner()).push(TreeEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: path.to_string_lossy().to_string(),
+                relative_path,
+                kind,
+                size,
+                modified_ms,
+                hash,
+                children: if kind == EntryKind::Dir { Some(Vec::new()) } else { None },
+            });
+            WalkState::Continue
+        })
+    });
+
+    let entries = std::mem::take(&mut *entries.lock().unwrap_or_else(|e| e.into_inner()));
+    let errors = std::mem::take(&mut *errors.lock().unwrap_or_else(|e| e.into_inner()));
+    Ok((entries, errors))
+}
+
+/// Nest a flat entry list under their parent directories, deepest first. When
+/// `prune_empty_dirs` is set, directories left without children are dropped.
+fn assemble_tree(mut flat: Vec<TreeEntry>, prune_empty_dirs: bool) -> Vec<TreeEntry> {
+    let depth = |e: &TreeEntry| e.relative_path.matches('/').count();
+    flat.sort_by_key(|e| std::cmp::Reverse(depth(e)));
+
+    let mut children_of: HashMap<String, Vec<TreeEntry>> = HashMap::new();
+    for mut entry in flat {
+        if entry.kind == EntryKind::Dir {
+            let children = children_of.remove(&entry.relative_path).unwrap_or_default();
+            if prune_empty_dirs && children.is_empty() {
+                continue;
+            }
+            entry.children = Some(children);
+        }
+        let parent = match entry.relative_path.rfind('/') {
+            Some(idx) => entry.relative_path[..idx].to_string(),
+            None => String::new(),
+        };
+        children_of.entry(parent).or_default().push(entry);
+    }
+
+    let mut roots = children_of.remove("").unwrap_or_default();
+    sort_entries(&mut roots);
+    roots
+}
+
+fn sort_entries(entries: &mut [TreeEntry]) {
+    entries.sort_by(|a, b| {
+        let a_dir = a.kind == EntryKind::Dir;
+        let b_dir = b.kind == EntryKind::Dir;
+        b_dir.cmp(&a_dir).then_with(|| a.name.cmp(&b.name))
+    });
+    for entry in entries.iter_mut() {
+        if let Some(children) = entry.children.as_mut() {
+            sort_entries(children);
+        }
+    }
+}
+
+fn count_entries(entries: &[TreeEntry]) -> (usize, usize) {
+    entries.iter().fold((0, 0), |(files, dirs), entry| match &entry.children {
+        Some(children) => {
+            let (f, d) = count_entries(children);
+            (files + f, dirs + d + 1)
+        }
+        None => (files + 1, dirs),
+    })
+}
+
+/// Recursively list `path` in one call: nested entries with kind, size, mtime and an
+/// optional SHA-256, honouring `.gitignore`/`.dddignore`, depth limits and glob filters.
+#[tauri::command]
+pub async fn list_tree(
+    sandbox: State<'_, ProjectSandbox>,
+    path: String,
+    options: Option<ListTreeOptions>,
+) -> Result<TreeListing, String> {
+    let root: PathBuf = sandbox.resolve(&path).map_err(|e| e.to_string())?;
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    let options = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || {
+        let (flat, errors) = walk_project(&root, &options)?;
+        let entries = assemble_tree(flat, !options.include.is_empty());
+        let (file_count, dir_count) = count_entries(&entries);
+        Ok(TreeListing {
+            root: root.to_string_lossy().to_string(),
+            entries,
+            file_count,
+            dir_count,
+            errors,
+        })
+    })
+    .await
+    .map_err(|e| format!("Directory scan failed: {}", e))?
+}
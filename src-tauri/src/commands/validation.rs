@@ -0,0 +1,90 @@
+use super::project::{load_project_graph, SpecParseError};
+use crate::sandbox::{ProjectSandbox, SandboxError};
+use crate::spec::DomainConfig;
+use crate::validation::{self, ValidationResult};
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use tauri::State;
+
+/// Validate a flow document as the editor holds it. A document that doesn't match
+/// the flow format yields an issue rather than an error.
+#[tauri::command]
+pub fn validate_flow(flow: Value) -> ValidationResult {
+    validation::validate_flow_value(&flow)
+}
+
+#[tauri::command]
+pub fn validate_domain(domain_id: String, config: DomainConfig) -> ValidationResult {
+    validation::validate_domain(&domain_id, &config)
+}
+
+/// Results for every flow, domain and the system as a whole, keyed like the
+/// validation store (`<domain>/<flow>` for flows, the domain id for domains).
+#[derive(Serialize, Clone, Debug)]
+pub struct ProjectValidation {
+    pub flows: IndexMap<String, ValidationResult>,
+    pub domains: IndexMap<String, ValidationResult>,
+    pub system: ValidationResult,
+    /// Spec files that could not be read or parsed and so were not validated.
+    pub parse_errors: Vec<SpecParseError>,
+    pub error_count: usize,
+    pub warning_count: usize,
+    /// No errors and no unreadable spec files.
+    pub is_valid: bool,
+}
+
+fn validate_project_at(root: &Path) -> ProjectValidation {
+    let graph = load_project_graph(root);
+    let mut flows = IndexMap::new();
+    let mut domains = IndexMap::new();
+    for (domain_id, domain) in &graph.domains {
+        if let Some(config) = &domain.config {
+            domains.insert(
+                domain_id.clone(),
+                validation::validate_domain(domain_id, config),
+            );
+        }
+        for flow in domain.flows.values() {
+            let result = validation::validate_flow(flow);
+            flows.insert(result.target_id.clone(), result);
+        }
+    }
+    let system = validation::validate_system(
+        graph
+            .domains
+            .iter()
+            .filter_map(|(id, d)| Some((id.as_str(), d.config.as_ref()?))),
+    );
+
+    let all = || flows.values().chain(domains.values()).chain([&system]);
+    let error_count = all().map(|r| r.error_count).sum();
+    let warning_count = all().map(|r| r.warning_count).sum();
+    ProjectValidation {
+        is_valid: error_count == 0 && graph.errors.is_empty(),
+        flows,
+        domains,
+        system,
+        parse_errors: graph.errors,
+        error_count,
+        warning_count,
+    }
+}
+
+/// Load every spec of the open project and validate it, including cross-domain
+/// event wiring.
+#[tauri::command]
+pub async fn validate_project(
+    sandbox: State<'_, ProjectSandbox>,
+) -> Result<ProjectValidation, String> {
+    let root = sandbox.root().ok_or_else(|| {
+        SandboxError::NoProjectOpen {
+            path: "ddd-project.json".to_string(),
+        }
+        .to_string()
+    })?;
+    tokio::task::spawn_blocking(move || validate_project_at(&root))
+        .await
+        .map_err(|e| format!("Project validation failed: {}", e))
+}
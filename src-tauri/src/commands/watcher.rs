@@ -0,0 +1,312 @@
+use crate::commands::file::is_temp_sibling;
+use crate::commands::implementation::hash_bytes;
+use crate::commands::tree::DDD_IGNORE_FILE;
+use crate::sandbox::ProjectSandbox;
+use ignore::WalkBuilder;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Tauri event emitted once per changed path after debouncing.
+pub const FILE_CHANGED_EVENT: &str = "project-file-changed";
+
+/// Tauri event carrying the message of a watcher failure (backend overflow, lost watch).
+pub const WATCHER_ERROR_EVENT: &str = "project-watcher-error";
+
+const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+/// Directories whose contents never produce events.
+const IGNORED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Domain,
+    Flow,
+    SystemLayout,
+    Mapping,
+    Source,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FileChangeEvent {
+    pub path: String,
+    pub relative_path: String,
+    pub kind: ChangeKind,
+    /// SHA-256 of the new contents; `None` when the file was removed.
+    pub hash: Option<String>,
+    pub removed: bool,
+}
+
+struct ActiveWatch {
+    root: PathBuf,
+    debouncer: Debouncer<RecommendedWatcher>,
+    /// Directories with a non-recursive watch; ignored directories never get one.
+    dirs: HashSet<PathBuf>,
+}
+
+/// Holds the watcher for the currently open project. Dropping the debouncer stops it.
+#[derive(Default)]
+pub struct ProjectWatcher {
+    active: Mutex<Option<ActiveWatch>>,
+}
+
+/// Map a path relative to the project root to the kind of change it represents.
+/// Returns `None` for paths the frontend doesn't care about.
+pub fn classify(relative: &Path) -> Option<ChangeKind> {
+    let parts: Vec<&str> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect();
+
+    if parts.iter().any(|p| IGNORED_DIRS.contains(p)) {
+        return None;
+    }
+    let file_name = *parts.last()?;
+    // Temp files from atomic writes and batch backups, editor swap files
+    if is_temp_sibling(file_name) || file_name.ends_with('~') || file_name.ends_with(".swp") {
+        return None;
+    }
+
+    match parts.as_slice() {
+        ["specs", "system-layout.yaml"] => Some(ChangeKind::SystemLayout),
+        ["specs", "domains", _, "domain.yaml"] => Some(ChangeKind::Domain),
+        ["specs", "domains", _, "flows", name] if is_yaml(name) => Some(ChangeKind::Flow),
+        [".ddd", "mapping.yaml"] => Some(ChangeKind::Mapping),
+        ["specs", ..] | [".ddd", ..] => None,
+        _ => Some(ChangeKind::Source),
+    }
+}
+
+fn is_yaml(name: &str) -> bool {
+    name.ends_with(".yaml") || name.ends_with(".yml")
+}
+
+/// Every path under `dir` (inclusive) that the watcher should see, honouring
+/// `.gitignore`/`.dddignore` like `list_tree` and never entering `IGNORED_DIRS`.
+fn watched_paths(dir: &Path, max_depth: Option<usize>) -> Vec<PathBuf> {
+    let mut builder = WalkBuilder::new(dir);
+    builder
+        .max_depth(max_depth)
+        .hidden(false)
+        .ignore(false)
+        .require_git(false)
+        .follow_links(false)
+        .add_custom_ignore_filename(DDD_IGNORE_FILE);
+    builder.filter_entry(|entry| {
+        !entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| IGNORED_DIRS.contains(&name))
+    });
+    builder
+        .build()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Watch `dir` and every non-ignored directory below it that isn't watched yet.
+/// Returns the files found in newly watched directories.
+fn watch_tree(active: &mut ActiveWatch, dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in watched_paths(dir, None) {
+        if !path.is_dir() {
+            files.push(path);
+        } else if !active.dirs.contains(&path) {
+            active
+                .debouncer
+                .watcher()
+                .watch(&path, RecursiveMode::NonRecursive)
+                .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+            active.dirs.insert(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Turn one debounced path into change events. New directories get watched and
+/// their files reported; watches of removed directories are forgotten.
+fn handle_path(app: &AppHandle, root: &Path, path: &Path) -> Result<(), String> {
+    let mut paths = vec![path.to_path_buf()];
+    if path.is_dir() {
+        let watcher = app.state::<ProjectWatcher>();
+        let mut active = watcher.active.lock().unwrap_or_else(|e| e.into_inner());
+        // Events from a watcher that was already replaced are stale
+        let Some(active) = active.as_mut().filter(|a| a.root == root) else {
+            return Ok(());
+        };
+        // A new directory is only watched when its parent is and the parent's
+        // ignore rules keep it; the walk root itself is never filtered.
+        let wanted = path.parent().is_some_and(|parent| {
+            active.dirs.contains(parent) && watched_paths(parent, Some(1)).iter().any(|p| p == path)
+        });
+        if !wanted || active.dirs.contains(path) {
+            return Ok(());
+        }
+        paths = watch_tree(active, path)?;
+    } else if !path.exists() {
+        let watcher = app.state::<ProjectWatcher>();
+        let mut active = watcher.active.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(active) = active.as_mut().filter(|a| a.root == root) {
+            active.dirs.retain(|dir| !dir.starts_with(path));
+        }
+    }
+    for path in paths {
+        if let Some(change) = to_change_event(root, &path) {
+            let _ = app.emit(FILE_CHANGED_EVENT, change);
+        }
+    }
+    Ok(())
+}
+
+fn to_change_event(root: &Path, path: &Path) -> Option<FileChangeEvent> {
+    let relative = path.strip_prefix(root).ok()?;
+    let kind = classify(relative)?;
+    if path.is_dir() {
+        return None;
+    }
+    let hash = fs::read(path).ok().map(|bytes| hash_bytes(&bytes));
+    Some(FileChangeEvent {
+        path: path.to_string_lossy().to_string(),
+        relative_path: relative.to_string_lossy().replace('\\', "/"),
+        kind,
+        removed: hash.is_none(),
+        hash,
+    })
+}
+
+/// Start watching the open project for changes to specs, mappings and source files.
+/// Replaces any watcher from a previously opened project. Directories matched by
+/// `IGNORED_DIRS` or the project's ignore files are not watched at all.
+#[tauri::command]
+pub fn start_project_watcher(
+    app: AppHandle,
+    sandbox: State<'_, ProjectSandbox>,
+    watcher: State<'_, ProjectWatcher>,
+    path: String,
+    debounce_ms: Option<u64>,
+) -> Result<(), String> {
+    let root = sandbox.resolve(&path).map_err(|e| e.to_string())?;
+    let mut active = watcher.active.lock().unwrap_or_else(|e| e.into_inner());
+    *active = None;
+
+    let event_root = root.clone();
+    let debouncer = new_debouncer(
+        Duration::from_millis(debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS)),
+        move |result: DebounceEventResult| {
+            let errors: Vec<String> = match result {
+                Ok(events) => events
+                    .iter()
+                    .filter_map(|event| handle_path(&app, &event_root, &event.path).err())
+                    .collect(),
+                Err(e) => vec![format!("File watcher error: {}", e)],
+            };
+            for error in errors {
+                let _ = app.emit(WATCHER_ERROR_EVENT, error);
+            }
+        },
+    )
+    .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+
+    let mut watch = ActiveWatch {
+        root: root.clone(),
+        debouncer,
+        dirs: HashSet::new(),
+    };
+    watch_tree(&mut watch, &root)?;
+    *active = Some(watch);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_project_watcher(watcher: State<'_, ProjectWatcher>) {
+    *watcher.active.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(path: &str) -> Option<ChangeKind> {
+        classify(Path::new(path))
+    }
+
+    #[test]
+    fn classifies_spec_files_by_location() {
+        assert_eq!(
+            kind("specs/system-layout.yaml"),
+            Some(ChangeKind::SystemLayout)
+        );
+        assert_eq!(
+            kind("specs/domains/users/domain.yaml"),
+            Some(ChangeKind::Domain)
+        );
+        assert_eq!(
+            kind("specs/domains/users/flows/login.yaml"),
+            Some(ChangeKind::Flow)
+        );
+        assert_eq!(
+            kind("specs/domains/users/flows/login.yml"),
+            Some(ChangeKind::Flow)
+        );
+        assert_eq!(kind(".ddd/mapping.yaml"), Some(ChangeKind::Mapping));
+        assert_eq!(kind("src/users/login.ts"), Some(ChangeKind::Source));
+    }
+
+    #[test]
+    fn other_spec_and_tool_files_are_not_reported() {
+        assert_eq!(kind("specs/domains/users/notes.md"), None);
+        assert_eq!(kind("specs/domains/users/flows/readme.txt"), None);
+        assert_eq!(kind(".ddd/cache/index.json"), None);
+    }
+
+    #[test]
+    fn ignored_dirs_and_scratch_files_are_not_reported() {
+        assert_eq!(kind(".git/HEAD"), None);
+        assert_eq!(kind("web/node_modules/react/index.js"), None);
+        assert_eq!(kind("target/debug/app"), None);
+        assert_eq!(kind("src/main.rs~"), None);
+        assert_eq!(kind("src/.main.rs.swp"), None);
+        assert_eq!(kind("specs/domains/users/.domain.yaml.tmp-1234"), None);
+    }
+
+    #[test]
+    fn ignored_and_gitignored_dirs_are_not_walked() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for sub in [
+            ".git/objects",
+            "node_modules/react",
+            "dist",
+            "specs/domains",
+            "src",
+        ] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        fs::write(root.join(".gitignore"), "dist/\n").unwrap();
+        fs::write(root.join("src/main.ts"), "").unwrap();
+
+        let mut dirs: Vec<String> = watched_paths(root, None)
+            .into_iter()
+            .filter(|p| p.is_dir())
+            .map(|p| {
+                p.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        dirs.sort();
+        assert_eq!(dirs, vec!["", "specs", "specs/domains", "src"]);
+    }
+}
@@ -0,0 +1,748 @@
+//! Project-wide event catalog. Event wiring is spread over each domain's
+//! `publishes_events` / `consumes_events` and the event nodes and triggers inside its
+//! flows; the catalog gathers it per event name and reports what doesn't line up:
+//! consumers nobody publishes to, events nobody consumes, payloads that disagree and
+//! domains that consume each other's events in a cycle.
+
+use crate::spec::{DomainConfig, EventWiring, Extra, FlowDocument, NodeSpec};
+use crate::validation::ValidationSeverity;
+use indexmap::{IndexMap, IndexSet};
+use serde::Serialize;
+
+/// Where the catalog is exported, relative to the project root.
+pub const CATALOG_PATH: &str = ".ddd/events.yaml";
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct EventCatalog {
+    /// Keyed by event name, sorted.
+    pub events: IndexMap<String, CatalogEvent>,
+    pub issues: Vec<EventIssue>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CatalogEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Named payload schema, taken from the publishers first, then the consumers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Payload shape, taken from the publishers first, then the consumers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Extra>,
+    /// Domains that list the event in `publishes_events`.
+    pub publishers: Vec<WiringRef>,
+    /// Domains that list the event in `consumes_events`.
+    pub consumers: Vec<WiringRef>,
+    /// Event nodes that emit the event.
+    pub emitted_by: Vec<NodeRef>,
+    /// Event nodes that consume the event and triggers that start on it.
+    pub consumed_by: Vec<NodeRef>,
+}
+
+/// A `publishes_events` / `consumes_events` entry.
+#[derive(Serialize, Clone, Debug)]
+pub struct WiringRef {
+    pub domain: String,
+    /// `from_flow` for publishers, `handled_by_flow` for consumers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flow: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Extra>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeRef {
+    pub domain: String,
+    pub flow: String,
+    pub node: String,
+    /// Payload an event node sends or expects; never set for triggers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Extra>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventIssueKind {
+    /// Consumed, but neither published by a domain nor emitted by a node.
+    DanglingConsumer,
+    /// Published or emitted, but nothing consumes it.
+    Unconsumed,
+    /// Publishers and consumers declare different schemas or payload fields.
+    PayloadMismatch,
+    /// Domains that consume each other's events, directly or through others.
+    DomainCycle,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EventIssue {
+    pub kind: EventIssueKind,
+    pub severity: ValidationSeverity,
+    pub message: String,
+    /// `None` for domain cycles, which involve several events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    /// Domains involved, in first-seen order.
+    pub domains: Vec<String>,
+}
+
+/// One domain of the project: its id, its `domain.yaml` if it parsed, and its flows
+/// keyed by flow id.
+pub type DomainSpecs<'a> = (
+    &'a str,
+    Option<&'a DomainConfig>,
+    &'a IndexMap<String, FlowDocument>,
+);
+
+fn wiring_ref(domain: &str, wiring: &EventWiring, flow: &Option<String>) -> WiringRef {
+    WiringRef {
+        domain: domain.to_string(),
+        flow: flow.clone(),
+        schema: wiring.schema.clone(),
+        payload: wiring.payload.clone().filter(|p| !p.is_empty()),
+    }
+}
+
+/// Build the catalog for every domain of a project.
+pub fn build_catalog<'a>(domains: impl IntoIterator<Item = DomainSpecs<'a>>) -> EventCatalog {
+    let domains: Vec<DomainSpecs> = domains.into_iter().collect();
+    let mut events: IndexMap<String, CatalogEvent> = IndexMap::new();
+    for (domain_id, config, _) in &domains {
+        let Some(config) = config else { continue };
+        for wiring in &config.publishes_events {
+            let event = events.entry(wiring.event.clone()).or_default();
+            event
+                .publishers
+                .push(wiring_ref(domain_id, wiring, &wiring.from_flow));
+            if event.description.is_none() {
+                event.description = wiring.description.clone();
+            }
+        }
+        for wiring in &config.consumes_events {
+            let event = events.entry(wiring.event.clone()).or_default();
+            event
+                .consumers
+                .push(wiring_ref(domain_id, wiring, &wiring.handled_by_flow));
+        }
+    }
+
+    for (domain_id, _, flows) in &domains {
+        for (flow_id, doc) in flows.iter() {
+            for node in doc.all_nodes() {
+                let NodeSpec::Event(spec) = &node.spec else {
+                    continue;
+                };
+                let Some(name) = spec.event_name.as_deref().filter(|n| !n.trim().is_empty()) else {
+                    continue;
+                };
+                let node_ref = NodeRef {
+                    domain: domain_id.to_string(),
+                    flow: flow_id.clone(),
+                    node: node.id.clone(),
+                    payload: spec.payload.clone().filter(|p| !p.is_empty()),
+                };
+                let event = events.entry(name.to_string()).or_default();
+                // The editor treats an event node without a direction as emitting.
+                match spec.direction.as_deref() {
+                    Some("consume") => event.consumed_by.push(node_ref),
+                    _ => event.emitted_by.push(node_ref),
+                }
+            }
+        }
+    }
+
+    // Trigger `event`s are also HTTP routes, cron expressions and the like, so only
+    // names the catalog already knows count as consumers.
+    for (domain_id, _, flows) in &domains {
+        for (flow_id, doc) in flows.iter() {
+            let NodeSpec::Trigger(spec) = &doc.trigger.spec else {
+                continue;
+            };
+            let Some(trigger_events) = &spec.event else {
+                continue;
+            };
+            for name in trigger_events.names() {
+                if let Some(event) = events.get_mut(name) {
+                    event.consumed_by.push(NodeRef {
+                        domain: domain_id.to_string(),
+                        flow: flow_id.clone(),
+                        node: doc.trigger.id.clone(),
+                        payload: None,
+                    });
+                }
+            }
+        }
+    }
+
+    for event in events.values_mut() {
+        let declared = event.publishers.iter().chain(&event.consumers);
+        let (schema, payload) = declared.fold((None, None), |(schema, payload), w| {
+            (
+                schema.or_else(|| w.schema.clone()),
+                payload.or_else(|| w.payload.clone()),
+            )
+        });
+        event.schema = schema;
+        event.payload = payload.or_else(|| {
+            event
+                .emitted_by
+                .iter()
+                .chain(&event.consumed_by)
+                .find_map(|n| n.payload.clone())
+        });
+    }
+    events.sort_keys();
+
+    let mut issues = Vec::new();
+    for (name, event) in &events {
+        wiring_issues(name, event, &mut issues);
+        payload_issues(name, event, &mut issues);
+    }
+    cycle_issues(&events, &mut issues);
+    EventCatalog { events, issues }
+}
+
+fn unique_domains<'a>(domains: impl Iterator<Item = &'a str>) -> Vec<String> {
+    domains
+        .collect::<IndexSet<_>>()
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+fn publishing_domains(event: &CatalogEvent) -> impl Iterator<Item = &str> {
+    event
+        .publishers
+        .iter()
+        .map(|w| w.domain.as_str())
+        .chain(event.emitted_by.iter().map(|n| n.domain.as_str()))
+}
+
+fn consuming_domains(event: &CatalogEvent) -> impl Iterator<Item = &str> {
+    event
+        .consumers
+        .iter()
+        .map(|w| w.domain.as_str())
+        .chain(event.consumed_by.iter().map(|n| n.domain.as_str()))
+}
+
+fn wiring_issues(name: &str, event: &CatalogEvent, issues: &mut Vec<EventIssue>) {
+    let published = !event.publishers.is_empty() || !event.emitted_by.is_empty();
+    let consumed = !event.consumers.is_empty() || !event.consumed_by.is_empty();
+    if consumed && !published {
+        let domains = unique_domains(consuming_domains(event));
+        issues.push(EventIssue {
+            kind: EventIssueKind::DanglingConsumer,
+            severity: ValidationSeverity::Error,
+            message: format!(
+                "Event \"{}\" is consumed by {} but nothing publishes it",
+                name,
+                domains.join(", ")
+            ),
+            event: Some(name.to_string()),
+            domains,
+        });
+    }
+    if published && !consumed {
+        let domains = unique_domains(publishing_domains(event));
+        issues.push(EventIssue {
+            kind: EventIssueKind::Unconsumed,
+            severity: ValidationSeverity::Warning,
+            message: format!(
+                "Event \"{}\" is published by {} but nothing consumes it",
+                name,
+                domains.join(", ")
+            ),
+            event: Some(name.to_string()),
+            domains,
+        });
+    }
+}
+
+/// A declared payload: field name to type. Event node payloads map fields to value
+/// expressions rather than types, so only their field names are compared.
+struct Shape<'a> {
+    origin: String,
+    domain: &'a str,
+    fields: IndexMap<&'a str, Option<&'a str>>,
+}
+
+fn wiring_shape(wiring: &WiringRef) -> Option<Shape<'_>> {
+    let payload = wiring.payload.as_ref()?;
+    Some(Shape {
+        origin: wiring.domain.clone(),
+        domain: &wiring.domain,
+        fields: payload
+            .iter()
+            .map(|(field, ty)| (field.as_str(), ty.as_str()))
+            .collect(),
+    })
+}
+
+fn node_shape(node: &NodeRef) -> Option<Shape<'_>> {
+    let payload = node.payload.as_ref()?;
+    Some(Shape {
+        origin: format!("{}/{} node {}", node.domain, node.flow, node.node),
+        domain: &node.domain,
+        fields: payload.keys().map(|field| (field.as_str(), None)).collect(),
+    })
+}
+
+fn payload_issues(name: &str, event: &CatalogEvent, issues: &mut Vec<EventIssue>) {
+    let mut mismatch = |message: String, domains: Vec<String>| {
+        issues.push(EventIssue {
+            kind: EventIssueKind::PayloadMismatch,
+            severity: ValidationSeverity::Error,
+            message,
+            event: Some(name.to_string()),
+            domains,
+        })
+    };
+
+    let mut schemas: IndexMap<&str, Vec<&str>> = IndexMap::new();
+    for wiring in event.publishers.iter().chain(&event.consumers) {
+        if let Some(schema) = &wiring.schema {
+            schemas.entry(schema).or_default().push(&wiring.domain);
+        }
+    }
+    if schemas.len() > 1 {
+        let declared: Vec<String> = schemas
+            .iter()
+            .map(|(schema, domains)| {
+                format!(
+                    "{} ({})",
+                    schema,
+                    unique_domains(domains.iter().copied()).join(", ")
+                )
+            })
+            .collect();
+        mismatch(
+            format!(
+                "Event \"{}\" is declared with different schemas: {}",
+                name,
+                declared.join(", ")
+            ),
+            unique_domains(schemas.values().flatten().copied()),
+        );
+    }
+
+    let sent: Vec<Shape> = (event.publishers.iter().filter_map(wiring_shape))
+        .chain(event.emitted_by.iter().filter_map(node_shape))
+        .collect();
+    let expected: Vec<Shape> = (event.consumers.iter().filter_map(wiring_shape))
+        .chain(event.consumed_by.iter().filter_map(node_shape))
+        .collect();
+    for consumer in &expected {
+        for publisher in &sent {
+            let missing: Vec<&str> = consumer
+                .fields
+                .keys()
+                .filter(|field| !publisher.fields.contains_key(*field))
+                .copied()
+                .collect();
+            let conflicting: Vec<String> = consumer
+                .fields
+                .iter()
+                .filter_map(|(field, expected)| {
+                    let sent = publisher.fields.get(field)?.as_ref()?;
+                    let expected = expected.as_ref()?;
+                    (sent != expected).then(|| format!("{} ({} vs {})", field, sent, expected))
+                })
+                .collect();
+            let mut problems = Vec::new();
+            if !missing.is_empty() {
+                problems.push(format!("missing {}", missing.join(", ")));
+            }
+            if !conflicting.is_empty() {
+                problems.push(format!("different types for {}", conflicting.join(", ")));
+            }
+            if problems.is_empty() {
+                continue;
+            }
+            mismatch(
+                format!(
+                    "Event \"{}\" payload from {} doesn't match what {} expects: {}",
+                    name,
+                    publisher.origin,
+                    consumer.origin,
+                    problems.join("; ")
+                ),
+                unique_domains([publisher.domain, consumer.domain].into_iter()),
+            );
+        }
+    }
+}
+
+/// Publisher domain -> consumer domain -> events, for events crossing domains.
+type EventEdges<'a> = IndexMap<&'a str, IndexMap<&'a str, IndexSet<&'a str>>>;
+
+fn cycle_issues(events: &IndexMap<String, CatalogEvent>, issues: &mut Vec<EventIssue>) {
+    let mut edges: EventEdges = IndexMap::new();
+    for (name, event) in events {
+        for from in publishing_domains(event) {
+            for to in consuming_domains(event).filter(|to| *to != from) {
+                edges
+                    .entry(from)
+                    .or_default()
+                    .entry(to)
+                    .or_default()
+                    .insert(name);
+            }
+        }
+    }
+
+    for component in strongly_connected(&edges) {
+        if component.len() < 2 {
+            continue;
+        }
+        let path = cycle_through(&edges, &component);
+        let mut cycle_events: IndexSet<&str> = IndexSet::new();
+        for pair in path.windows(2) {
+            cycle_events.extend(edges[pair[0]][pair[1]].iter().copied());
+        }
+        issues.push(EventIssue {
+            kind: EventIssueKind::DomainCycle,
+            severity: ValidationSeverity::Warning,
+            message: format!(
+                "Domains consume each other's events in a cycle: {} (via {})",
+                path.join(" → "),
+                cycle_events.into_iter().collect::<Vec<_>>().join(", ")
+            ),
+            event: None,
+            domains: component.iter().map(|d| d.to_string()).collect(),
+        });
+    }
+}
+
+/// Tarjan's algorithm. Components come back in reverse topological order, each in
+/// the order its domains were first seen.
+fn strongly_connected<'a>(edges: &EventEdges<'a>) -> Vec<Vec<&'a str>> {
+    struct State<'a> {
+        index: IndexMap<&'a str, (usize, usize)>,
+        stack: IndexSet<&'a str>,
+        components: Vec<Vec<&'a str>>,
+    }
+
+    fn visit<'a>(node: &'a str, edges: &EventEdges<'a>, state: &mut State<'a>) {
+        let index = state.index.len();
+        state.index.insert(node, (index, index));
+        state.stack.insert(node);
+        for &next in edges
+            .get(node)
+            .into_iter()
+            .flat_map(|targets| targets.keys())
+        {
+            if !state.index.contains_key(next) {
+                visit(next, edges, state);
+                let next_low = state.index[next].1;
+                let entry = state.index.get_mut(node).expect("visited");
+                entry.1 = entry.1.min(next_low);
+            } else if state.stack.contains(next) {
+                let next_index = state.index[next].0;
+                let entry = state.index.get_mut(node).expect("visited");
+                entry.1 = entry.1.min(next_index);
+            }
+        }
+        let (index, low) = state.index[node];
+        if index == low {
+            let at = state.stack.get_index_of(node).expect("on stack");
+            let mut component: Vec<&str> = state.stack.drain(at..).collect();
+            component.sort_by_key(|d| state.index[d].0);
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        index: IndexMap::new(),
+        stack: IndexSet::new(),
+        components: Vec::new(),
+    };
+    for &node in edges.keys() {
+        if !state.index.contains_key(node) {
+            visit(node, edges, &mut state);
+        }
+    }
+    state.components
+}
+
+/// The shortest cycle through the component's first domain, staying inside the
+/// component. Starts and ends with that domain.
+fn cycle_through<'a>(edges: &EventEdges<'a>, component: &[&'a str]) -> Vec<&'a str> {
+    let start = component[0];
+    let mut came_from: IndexMap<&str, &str> = IndexMap::new();
+    let mut queue = std::collections::VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &next in edges
+            .get(node)
+            .into_iter()
+            .flat_map(|targets| targets.keys())
+        {
+            if !component.contains(&next) {
+                continue;
+            }
+            if next == start {
+                let mut path = vec![node];
+                let mut at = node;
+                while let Some(&prev) = came_from.get(at) {
+                    path.push(prev);
+                    at = prev;
+                }
+                path.reverse();
+                path.push(start);
+                return path;
+            }
+            if !came_from.contains_key(next) {
+                came_from.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+    component.to_vec()
+}
+
+/// The catalog as written to [`CATALOG_PATH`].
+pub fn to_yaml(catalog: &EventCatalog) -> Result<String, serde_yaml::Error> {
+    let mut content = String::from("# Generated by DDD Tool from the domain and flow specs.\n");
+    content.push_str(&serde_yaml::to_string(catalog)?);
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::from_yaml;
+
+    fn catalog(domains: &[(&str, &str)], flows: &[(&str, &str)]) -> EventCatalog {
+        let configs: Vec<(&str, DomainConfig)> = domains
+            .iter()
+            .map(|(id, content)| (*id, from_yaml(content).unwrap()))
+            .collect();
+        let flows: Vec<IndexMap<String, FlowDocument>> = configs
+            .iter()
+            .map(|(id, _)| {
+                flows
+                    .iter()
+                    .filter(|(domain, _)| domain == id)
+                    .map(|(_, content)| {
+                        let doc: FlowDocument = from_yaml(content).unwrap();
+                        (doc.flow.id.clone(), doc)
+                    })
+                    .collect()
+            })
+            .collect();
+        build_catalog(
+            configs
+                .iter()
+                .zip(&flows)
+                .map(|((id, config), flows)| (*id, Some(config), flows)),
+        )
+    }
+
+    fn issues(catalog: &EventCatalog) -> Vec<(EventIssueKind, &str)> {
+        catalog
+            .issues
+            .iter()
+            .map(|i| (i.kind, i.message.as_str()))
+            .collect()
+    }
+
+    fn edges<'a>(pairs: &[(&'a str, &'a str)]) -> EventEdges<'a> {
+        let mut edges: EventEdges = IndexMap::new();
+        for (from, to) in pairs {
+            edges
+                .entry(from)
+                .or_default()
+                .entry(to)
+                .or_default()
+                .insert("Event");
+        }
+        edges
+    }
+
+    #[test]
+    fn components_come_back_in_reverse_topological_order() {
+        let edges = edges(&[
+            ("a", "b"),
+            ("b", "a"),
+            ("b", "c"),
+            ("c", "d"),
+            ("d", "c"),
+            ("b", "e"),
+        ]);
+        assert_eq!(
+            strongly_connected(&edges),
+            vec![vec!["c", "d"], vec!["e"], vec!["a", "b"]]
+        );
+    }
+
+    #[test]
+    fn cycles_take_the_shortest_way_back() {
+        let edges = edges(&[("a", "b"), ("b", "c"), ("c", "a"), ("a", "c")]);
+        let components = strongly_connected(&edges);
+        assert_eq!(components, vec![vec!["a", "b", "c"]]);
+        assert_eq!(cycle_through(&edges, &components[0]), vec!["a", "c", "a"]);
+    }
+
+    #[test]
+    fn three_domains_consuming_each_other_form_a_cycle() {
+        let catalog = catalog(
+            &[
+                (
+                    "users",
+                    "name: Users\npublishes_events: [UserRegistered]\nconsumes_events: [TicketClosed]\n",
+                ),
+                (
+                    "billing",
+                    "name: Billing\npublishes_events: [InvoicePaid]\nconsumes_events: [UserRegistered]\n",
+                ),
+                (
+                    "support",
+                    "name: Support\npublishes_events: [TicketClosed]\nconsumes_events: [InvoicePaid]\n",
+                ),
+                ("analytics", "name: Analytics\nconsumes_events: [UserRegistered]\n"),
+            ],
+            &[],
+        );
+        assert_eq!(
+            issues(&catalog),
+            vec![(
+                EventIssueKind::DomainCycle,
+                "Domains consume each other's events in a cycle: \
+                 billing → support → users → billing (via InvoicePaid, TicketClosed, UserRegistered)"
+            )]
+        );
+        let issue = &catalog.issues[0];
+        assert_eq!(issue.severity, ValidationSeverity::Warning);
+        assert_eq!(issue.domains, vec!["billing", "support", "users"]);
+        assert_eq!(issue.event, None);
+    }
+
+    #[test]
+    fn dangling_consumers_and_unconsumed_events_are_reported() {
+        let catalog = catalog(
+            &[
+                ("users", "name: Users\npublishes_events: [UserDeleted]\n"),
+                ("billing", "name: Billing\nconsumes_events: [OrderPlaced]\n"),
+            ],
+            &[(
+                "users",
+                "\
+flow: {id: signup, name: Signup, domain: users}
+trigger:
+  id: trigger
+  type: trigger
+  spec: {event: OrderPlaced}
+nodes:
+  - id: announce
+    type: event
+    spec: {event_name: UserRegistered, direction: emit}
+",
+            )],
+        );
+        assert_eq!(
+            issues(&catalog),
+            vec![
+                (
+                    EventIssueKind::DanglingConsumer,
+                    "Event \"OrderPlaced\" is consumed by billing, users but nothing publishes it"
+                ),
+                (
+                    EventIssueKind::Unconsumed,
+                    "Event \"UserDeleted\" is published by users but nothing consumes it"
+                ),
+                (
+                    EventIssueKind::Unconsumed,
+                    "Event \"UserRegistered\" is published by users but nothing consumes it"
+                ),
+            ]
+        );
+        assert_eq!(catalog.issues[0].severity, ValidationSeverity::Error);
+        assert_eq!(catalog.issues[0].event.as_deref(), Some("OrderPlaced"));
+        let order = &catalog.events["OrderPlaced"];
+        assert_eq!(order.consumed_by[0].node, "trigger");
+        // Trigger events nothing else mentions (routes, schedules) aren't catalogued
+        assert_eq!(
+            catalog.events.keys().collect::<Vec<_>>(),
+            vec!["OrderPlaced", "UserDeleted", "UserRegistered"]
+        );
+    }
+
+    #[test]
+    fn payloads_missing_fields_or_with_other_types_are_mismatches() {
+        let catalog = catalog(
+            &[
+                (
+                    "billing",
+                    "\
+name: Billing
+publishes_events:
+  - event: InvoicePaid
+    schema: Invoice
+    payload: {id: string, amount: number}
+",
+                ),
+                (
+                    "support",
+                    "\
+name: Support
+consumes_events:
+  - event: InvoicePaid
+    schema: Payment
+    payload: {id: string, amount: string, currency: string}
+",
+                ),
+            ],
+            &[(
+                "support",
+                "\
+flow: {id: refund, name: Refund, domain: support}
+trigger:
+  id: trigger
+  type: trigger
+  spec: {event: manual}
+nodes:
+  - id: wait
+    type: event
+    spec:
+      event_name: InvoicePaid
+      direction: consume
+      payload: {id: '{{invoice.id}}', paid_at: '{{now}}'}
+",
+            )],
+        );
+        assert_eq!(
+            issues(&catalog),
+            vec![
+                (
+                    EventIssueKind::PayloadMismatch,
+                    "Event \"InvoicePaid\" is declared with different schemas: \
+                     Invoice (billing), Payment (support)"
+                ),
+                (
+                    EventIssueKind::PayloadMismatch,
+                    "Event \"InvoicePaid\" payload from billing doesn't match what support \
+                     expects: missing currency; different types for amount (number vs string)"
+                ),
+                (
+                    EventIssueKind::PayloadMismatch,
+                    "Event \"InvoicePaid\" payload from billing doesn't match what \
+                     support/refund node wait expects: missing paid_at"
+                ),
+            ]
+        );
+        assert!(catalog
+            .issues
+            .iter()
+            .all(|i| i.domains == vec!["billing", "support"]));
+        let event = &catalog.events["InvoicePaid"];
+        assert_eq!(event.schema.as_deref(), Some("Invoice"));
+        assert_eq!(
+            event
+                .payload
+                .as_ref()
+                .map(|p| p.keys().map(String::as_str).collect::<Vec<_>>()),
+            Some(vec!["id", "amount"])
+        );
+    }
+}
@@ -1,4 +1,16 @@
 mod commands;
+pub mod events;
+mod sandbox;
+pub mod validation;
+
+pub use ddd_spec as spec;
+
+use commands::git_remote::GitOperations;
+use commands::logs::LogTails;
+use commands::search::SpecIndex;
+use commands::watcher::ProjectWatcher;
+use sandbox::ProjectSandbox;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -6,23 +18,87 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            // Matches the `~/.ddd-tool` directory the webview keeps settings and logs in.
+            let config_dir = app.path().home_dir().ok().map(|home| home.join(".ddd-tool"));
+            app.manage(ProjectSandbox::new(ProjectSandbox::default_allowlist(config_dir)));
+            app.manage(ProjectWatcher::default());
+            app.manage(SpecIndex::default());
+            app.manage(LogTails::default());
+            app.manage(GitOperations::default());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::file::read_file,
+            commands::file::read_file_bytes,
+            commands::file::read_file_raw,
             commands::file::write_file,
+            commands::file::write_file_bytes,
+            commands::file::write_file_raw,
+            commands::file::detect_file_format,
+            commands::file::patch_yaml_file,
             commands::file::path_exists,
             commands::file::create_directory,
             commands::file::delete_file,
             commands::file::delete_directory,
             commands::file::list_directory,
             commands::file::append_log,
+            commands::logs::read_logs,
+            commands::logs::start_log_tail,
+            commands::logs::stop_log_tail,
+            commands::file::apply_file_batch,
+            commands::tree::list_tree,
+            commands::search::search_specs,
+            commands::trash::list_trash,
+            commands::trash::restore_trash_entry,
+            commands::trash::purge_trash,
+            commands::project::set_project_root,
+            commands::project::pick_directory,
+            commands::project::sample_project_path,
+            commands::project::is_ddd_project,
+            commands::project::clear_project_root,
+            commands::migrate::migrate_project,
+            commands::project::load_project,
+            commands::refactor::preview_rename,
+            commands::refactor::apply_rename,
+            commands::validation::validate_flow,
+            commands::validation::validate_domain,
+            commands::validation::validate_project,
+            commands::events::event_catalog,
+            commands::events::export_event_catalog,
+            commands::schema::write_spec_schemas,
+            commands::schema::validate_spec_schemas,
             commands::project::git_init,
             commands::project::git_add_all,
             commands::project::git_commit,
+            commands::diff::diff_spec_files,
+            commands::diff::diff_spec_revisions,
+            commands::merge::merge_spec,
+            commands::merge::register_merge_driver,
             commands::git::git_status,
             commands::git::git_log,
             commands::git::git_stage_file,
             commands::git::git_unstage_file,
+            commands::git::git_diff_file,
+            commands::git::git_diff_staged,
+            commands::git::git_diff_commit,
+            commands::git::git_stage_hunk,
+            commands::git::git_unstage_hunk,
+            commands::git::git_list_branches,
+            commands::git::git_create_branch,
+            commands::git::git_checkout_branch,
+            commands::git::git_rename_branch,
+            commands::git::git_delete_branch,
+            commands::git::git_file_history,
+            commands::git::git_show_file_at,
+            commands::git::git_restore_file,
             commands::git::git_clone,
+            commands::git_remote::git_fetch,
+            commands::git_remote::git_pull,
+            commands::git_remote::git_push,
+            commands::git_remote::cancel_git_operation,
+            commands::watcher::start_project_watcher,
+            commands::watcher::stop_project_watcher,
             commands::llm::llm_chat,
             commands::llm::get_env_var,
             commands::implementation::compute_file_hash,
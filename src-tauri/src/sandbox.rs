@@ -0,0 +1,302 @@
+use serde::{Serialize, Serializer};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+/// Marker file of a DDD project; directories holding one may become the root.
+pub const PROJECT_MARKER: &str = "ddd-project.json";
+
+/// Environment variable with extra directories (OS path-list separated) that the
+/// file commands may touch outside the open project.
+pub const ALLOWLIST_ENV_VAR: &str = "DDD_SANDBOX_ALLOW";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SandboxError {
+    NoProjectOpen { path: String },
+    RelativePath { path: String },
+    ParentTraversal { path: String },
+    OutsideProject { path: String, root: String },
+    ProtectedPath { path: String },
+    DanglingSymlink { path: String },
+    InvalidRoot { path: String, reason: String },
+}
+
+impl SandboxError {
+    /// Stable machine-readable code, also used as the message prefix on the wire.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SandboxError::NoProjectOpen { .. } => "no_project_open",
+            SandboxError::RelativePath { .. } => "relative_path",
+            SandboxError::ParentTraversal { .. } => "parent_traversal",
+            SandboxError::OutsideProject { .. } => "outside_project",
+            SandboxError::ProtectedPath { .. } => "protected_path",
+            SandboxError::DanglingSymlink { .. } => "dangling_symlink",
+            SandboxError::InvalidRoot { .. } => "invalid_root",
+        }
+    }
+}
+
+impl fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.kind())?;
+        match self {
+            SandboxError::NoProjectOpen { path } => {
+                write!(f, "No project is open; refusing to access {}", path)
+            }
+            SandboxError::RelativePath { path } => {
+                write!(f, "Path must be absolute: {}", path)
+            }
+            SandboxError::ParentTraversal { path } => {
+                write!(f, "Path must not contain '..' components: {}", path)
+            }
+            SandboxError::OutsideProject { path, root } => {
+                write!(f, "Path {} is outside the project root {}", path, root)
+            }
+            SandboxError::ProtectedPath { path } => {
+                write!(f, "Refusing to remove protected path {}", path)
+            }
+            SandboxError::DanglingSymlink { path } => {
+                write!(f, "Path {} goes through a symlink that cannot be resolved", path)
+            }
+            SandboxError::InvalidRoot { path, reason } => {
+                write!(f, "Cannot use {} as project root: {}", path, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SandboxError {}
+
+// Serialized as its message so existing `String(e)` handling in the webview keeps working;
+// the `[kind]` prefix lets callers branch on the error type.
+impl Serialize for SandboxError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Confines file commands to the open project root plus a small allowlist.
+/// Managed as Tauri state; the root is set when the webview opens a project.
+pub struct ProjectSandbox {
+    root: RwLock<Option<PathBuf>>,
+    allowlist: Vec<PathBuf>,
+    /// Directories the user chose in a native dialog this session.
+    approved: RwLock<Vec<PathBuf>>,
+}
+
+impl ProjectSandbox {
+    pub fn new(allowlist: Vec<PathBuf>) -> Self {
+        let allowlist = allowlist
+            .into_iter()
+            .filter(|p| p.is_absolute())
+            .map(|p| resolve_lexically_safe(&p).unwrap_or(p))
+            .collect();
+        ProjectSandbox {
+            root: RwLock::new(None),
+            allowlist,
+            approved: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Default allowlist: the app config dir plus anything listed in `DDD_SANDBOX_ALLOW`.
+    /// Deliberately not settable from the webview.
+    pub fn default_allowlist(config_dir: Option<PathBuf>) -> Vec<PathBuf> {
+        let mut list: Vec<PathBuf> = config_dir.into_iter().collect();
+        if let Some(extra) = env::var_os(ALLOWLIST_ENV_VAR) {
+            list.extend(env::split_paths(&extra).filter(|p| !p.as_os_str().is_empty()));
+        }
+        list
+    }
+
+    /// Let `path` and the directories below it become the project root. Only the
+    /// backend calls this, for directories the user picked in a native dialog.
+    pub fn approve(&self, path: &Path) {
+        if let Ok(resolved) = resolve_lexically_safe(path) {
+            self.approved
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(resolved);
+        }
+    }
+
+    fn is_approved(&self, path: &Path) -> bool {
+        self.approved
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|approved| path.starts_with(approved))
+    }
+
+    /// Make `path` the project root. It must be a DDD project (holding
+    /// `ddd-project.json`) or lie in a directory the user picked; only in the latter
+    /// case is it created with `create`, for new projects.
+    pub fn set_root(&self, path: &str, create: bool) -> Result<PathBuf, SandboxError> {
+        let invalid = |reason: String| SandboxError::InvalidRoot {
+            path: path.to_string(),
+            reason,
+        };
+        let resolved = resolve_lexically_safe(Path::new(path))?;
+        let approved = self.is_approved(&resolved);
+        if !approved && !resolved.join(PROJECT_MARKER).is_file() {
+            return Err(invalid(format!(
+                "not a DDD project ({} is missing) and not chosen in a dialog",
+                PROJECT_MARKER
+            )));
+        }
+        if create && approved {
+            fs::create_dir_all(&resolved).map_err(|e| invalid(e.to_string()))?;
+        }
+        let canonical = fs::canonicalize(&resolved).map_err(|e| invalid(e.to_string()))?;
+        if !canonical.is_dir() {
+            return Err(invalid("not a directory".to_string()));
+        }
+        if canonical.parent().is_none() {
+            return Err(invalid("filesystem root cannot be a project".to_string()));
+        }
+        *self.root.write().unwrap_or_else(|e| e.into_inner()) = Some(canonical.clone());
+        Ok(canonical)
+    }
+
+    pub fn clear_root(&self) {
+        *self.root.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    pub fn root(&self) -> Option<PathBuf> {
+        self.root.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Canonicalize `path` and check it stays inside the project root or an allowlisted
+    /// directory. Returns the resolved path, which callers should use for the actual I/O.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, SandboxError> {
+        let resolved = resolve_lexically_safe(Path::new(path))?;
+        let root = self.root();
+
+        if let Some(root) = &root {
+            if resolved.starts_with(root) {
+                return Ok(resolved);
+            }
+        }
+        if self.allowlist.iter().any(|allowed| resolved.starts_with(allowed)) {
+            return Ok(resolved);
+        }
+
+        match root {
+            Some(root) => Err(SandboxError::OutsideProject {
+                path: path.to_string(),
+                root: root.to_string_lossy().to_string(),
+            }),
+            None => Err(SandboxError::NoProjectOpen {
+                path: path.to_string(),
+            }),
+        }
+    }
+
+    /// Like `resolve`, but also refuses the project root and allowlist roots themselves.
+    /// Used by recursive deletes.
+    pub fn resolve_removable(&self, path: &str) -> Result<PathBuf, SandboxError> {
+        let resolved = self.resolve(path)?;
+        let is_root = self.root().as_deref() == Some(resolved.as_path())
+            || self.allowlist.iter().any(|allowed| allowed == &resolved);
+        if is_root {
+            return Err(SandboxError::ProtectedPath {
+                path: path.to_string(),
+            });
+        }
+        Ok(resolved)
+    }
+}
+
+/// Resolve an absolute path without `..` components: canonicalize the deepest existing
+/// ancestor (following symlinks) and re-append the not-yet-existing tail.
+fn resolve_lexically_safe(path: &Path) -> Result<PathBuf, SandboxError> {
+    let display = path.to_string_lossy().to_string();
+    if !path.is_absolute() {
+        return Err(SandboxError::RelativePath { path: display });
+    }
+    if path.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(SandboxError::ParentTraversal { path: display });
+    }
+
+    let mut existing = path.to_path_buf();
+    let mut tail = Vec::new();
+    loop {
+        match fs::canonicalize(&existing) {
+            Ok(canonical) => {
+                let mut resolved = canonical;
+                for part in tail.iter().rev() {
+                    resolved.push(part);
+                }
+                return Ok(resolved);
+            }
+            Err(_) => {
+                // A broken symlink could point anywhere once its target is created.
+                if fs::symlink_metadata(&existing).is_ok() {
+                    return Err(SandboxError::DanglingSymlink { path: display });
+                }
+                let name = match existing.file_name() {
+                    Some(name) => name.to_os_string(),
+                    None => return Ok(path.to_path_buf()),
+                };
+                tail.push(name);
+                if !existing.pop() {
+                    return Ok(path.to_path_buf());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_str(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn root_must_be_a_project_or_picked() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        let sandbox = ProjectSandbox::new(Vec::new());
+
+        let err = sandbox.set_root(path_str(dir.path()), false).unwrap_err();
+        assert_eq!(err.kind(), "invalid_root");
+
+        fs::write(project.join(PROJECT_MARKER), "{}").unwrap();
+        assert!(sandbox.set_root(path_str(&project), false).is_ok());
+    }
+
+    #[test]
+    fn new_project_is_created_only_inside_a_picked_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = ProjectSandbox::new(Vec::new());
+        let new_project = dir.path().join("picked").join("new");
+
+        assert!(sandbox.set_root(path_str(&new_project), true).is_err());
+        assert!(!new_project.exists());
+
+        sandbox.approve(&dir.path().join("picked"));
+        let root = sandbox.set_root(path_str(&new_project), true).unwrap();
+        assert!(root.is_dir());
+        assert_eq!(sandbox.root(), Some(root));
+    }
+
+    #[test]
+    fn resolve_stays_inside_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = ProjectSandbox::new(Vec::new());
+        sandbox.approve(dir.path());
+        let root = sandbox.set_root(path_str(dir.path()), false).unwrap();
+
+        let inside = root.join("specs/a.yaml");
+        assert!(sandbox.resolve(path_str(&inside)).is_ok());
+        let outside = sandbox.resolve("/etc/passwd").unwrap_err();
+        assert_eq!(outside.kind(), "outside_project");
+        let removable = sandbox.resolve_removable(path_str(&root)).unwrap_err();
+        assert_eq!(removable.kind(), "protected_path");
+    }
+}
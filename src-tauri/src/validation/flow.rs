@@ -0,0 +1,1115 @@
+use super::{
+    blank, missing, Issues, ValidationCategory as Category, ValidationResult, ValidationScope,
+    ValidationSeverity::{Error, Warning},
+};
+use crate::spec::nodes::{ParallelBranch, TriggerEvent};
+use crate::spec::{
+    FlowDocument, FlowNode, NodeSpec, NodeType, ObservabilityConfig, SecurityConfig,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Validate a flow that may not parse as a `FlowDocument`, e.g. one handed over
+/// from the editor mid-edit. A missing trigger or a malformed spec is reported as
+/// an issue instead of an error.
+pub fn validate_flow_value(value: &Value) -> ValidationResult {
+    let header = |key: &str| {
+        value
+            .pointer(&format!("/flow/{}", key))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string()
+    };
+    let mut issues = Issues::new(ValidationScope::Flow);
+    if value.get("trigger").is_none_or(Value::is_null) {
+        issues.push(
+            Error,
+            Category::GraphCompleteness,
+            "Flow must have a trigger node".to_string(),
+        );
+    } else {
+        match serde_json::from_value::<FlowDocument>(value.clone()) {
+            Ok(flow) => return validate_flow(&flow),
+            Err(e) => {
+                issues
+                    .push(
+                        Error,
+                        Category::SpecCompleteness,
+                        format!("Flow could not be read: {}", e),
+                    )
+                    .suggest("Fix the flow file so it matches the flow spec format");
+            }
+        }
+    }
+    finish(issues, &header("domain"), &header("id"))
+}
+
+pub fn validate_flow(flow: &FlowDocument) -> ValidationResult {
+    let mut issues = Issues::new(ValidationScope::Flow);
+    let graph = Graph::new(flow);
+    check_all_paths_reach_terminal(flow, &graph, &mut issues);
+    check_orphaned_nodes(flow, &graph, &mut issues);
+    check_circular_paths(flow, &graph, &mut issues);
+    check_decision_branches(flow, &mut issues);
+    check_terminal_no_outgoing(flow, &mut issues);
+    check_trigger_event(flow, &mut issues);
+    check_agent_flow(flow, &mut issues);
+    for node in flow.all_nodes() {
+        check_node_spec(node, &mut issues);
+    }
+    finish(issues, &flow.flow.domain, &flow.flow.id)
+}
+
+fn finish(mut issues: Issues, domain_id: &str, flow_id: &str) -> ValidationResult {
+    for issue in &mut issues.issues {
+        issue.flow_id = Some(flow_id.to_string());
+        issue.domain_id = Some(domain_id.to_string());
+    }
+    ValidationResult::new(
+        ValidationScope::Flow,
+        format!("{}/{}", domain_id, flow_id),
+        issues.issues,
+    )
+}
+
+// --- Graph completeness checks ---
+
+struct Graph<'a> {
+    adjacency: HashMap<&'a str, Vec<&'a str>>,
+    reachable: HashSet<&'a str>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(flow: &'a FlowDocument) -> Self {
+        let adjacency: HashMap<&str, Vec<&str>> = flow
+            .all_nodes()
+            .map(|n| {
+                let targets = n.connections.iter().map(|c| c.target_node_id.as_str());
+                (n.id.as_str(), targets.collect())
+            })
+            .collect();
+        let mut reachable = HashSet::new();
+        let mut queue = VecDeque::from([flow.trigger.id.as_str()]);
+        while let Some(current) = queue.pop_front() {
+            if !reachable.insert(current) {
+                continue;
+            }
+            for &next in adjacency.get(current).into_iter().flatten() {
+                if !reachable.contains(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        Graph {
+            adjacency,
+            reachable,
+        }
+    }
+
+    fn outgoing(&self, id: &str) -> &[&'a str] {
+        self.adjacency
+            .get(id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Depth-first search from `start` for a back edge.
+    fn has_cycle_from(&self, start: &'a str) -> bool {
+        let mut visited = HashSet::new();
+        let mut in_stack = HashSet::new();
+        // (node, index of the next neighbour to visit)
+        let mut stack = vec![(start, 0)];
+        visited.insert(start);
+        in_stack.insert(start);
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            let Some(&neighbor) = self.outgoing(node).get(*next) else {
+                in_stack.remove(node);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            if in_stack.contains(neighbor) {
+                return true;
+            }
+            if visited.insert(neighbor) {
+                in_stack.insert(neighbor);
+                stack.push((neighbor, 0));
+            }
+        }
+        false
+    }
+}
+
+fn check_all_paths_reach_terminal(flow: &FlowDocument, graph: &Graph, issues: &mut Issues) {
+    if !flow.all_nodes().any(|n| n.node_type == NodeType::Terminal) {
+        issues
+            .push(
+                Error,
+                Category::GraphCompleteness,
+                "Flow has no terminal nodes — all paths must end at a terminal".to_string(),
+            )
+            .suggest("Add a terminal node and connect your flow to it");
+        return;
+    }
+
+    for node in flow.all_nodes() {
+        // Loop and parallel nodes have special connection semantics.
+        if matches!(
+            node.node_type,
+            NodeType::Terminal | NodeType::Loop | NodeType::Parallel
+        ) {
+            continue;
+        }
+        if graph.reachable.contains(node.id.as_str()) && graph.outgoing(&node.id).is_empty() {
+            issues
+                .push(
+                    Error,
+                    Category::GraphCompleteness,
+                    format!(
+                        "Node \"{}\" ({}) is a dead end with no outgoing connections",
+                        node.label,
+                        node.node_type.as_str()
+                    ),
+                )
+                .node(&node.id)
+                .suggest("Connect this node to a downstream node or terminal");
+        }
+    }
+}
+
+fn check_orphaned_nodes(flow: &FlowDocument, graph: &Graph, issues: &mut Issues) {
+    for node in &flow.nodes {
+        if !graph.reachable.contains(node.id.as_str()) {
+            issues
+                .push(
+                    Error,
+                    Category::GraphCompleteness,
+                    format!(
+                        "Node \"{}\" ({}) is unreachable from the trigger",
+                        node.label,
+                        node.node_type.as_str()
+                    ),
+                )
+                .node(&node.id)
+                .suggest("Connect this node to the flow graph or remove it");
+        }
+    }
+}
+
+fn check_circular_paths(flow: &FlowDocument, graph: &Graph, issues: &mut Issues) {
+    // Loops are expected in agent flows.
+    if flow.flow.flow_type == "agent" {
+        return;
+    }
+    if graph.has_cycle_from(&flow.trigger.id) {
+        issues
+            .push(
+                Error,
+                Category::GraphCompleteness,
+                "Flow contains a circular path (cycle detected)".to_string(),
+            )
+            .suggest("Remove the cycle or convert to an agent flow if loops are intentional");
+    }
+}
+
+fn check_decision_branches(flow: &FlowDocument, issues: &mut Issues) {
+    for node in flow.all_nodes() {
+        if node.node_type != NodeType::Decision {
+            continue;
+        }
+        let has_handle = |handle: &str| {
+            node.connections
+                .iter()
+                .any(|c| c.source_handle.as_deref() == Some(handle))
+        };
+        for (handle, name) in [("true", "Yes"), ("false", "No")] {
+            if !has_handle(handle) {
+                issues
+                    .push(
+                        Error,
+                        Category::GraphCompleteness,
+                        format!(
+                            "Decision \"{}\" is missing a \"{}\" ({}) branch connection",
+                            node.label, name, handle
+                        ),
+                    )
+                    .node(&node.id)
+                    .suggest(&format!(
+                        "Connect the \"{}\" handle to a downstream node",
+                        name
+                    ));
+            }
+        }
+    }
+}
+
+fn check_terminal_no_outgoing(flow: &FlowDocument, issues: &mut Issues) {
+    for node in flow.all_nodes() {
+        if node.node_type == NodeType::Terminal && !node.connections.is_empty() {
+            issues
+                .push(
+                    Warning,
+                    Category::GraphCompleteness,
+                    format!(
+                        "Terminal \"{}\" has outgoing connections — terminals should be endpoints",
+                        node.label
+                    ),
+                )
+                .node(&node.id)
+                .suggest("Remove outgoing connections from this terminal node");
+        }
+    }
+}
+
+fn check_trigger_event(flow: &FlowDocument, issues: &mut Issues) {
+    let empty = match &flow.trigger.spec {
+        NodeSpec::Trigger(spec) => match &spec.event {
+            None => true,
+            Some(TriggerEvent::One(event)) => event.trim().is_empty(),
+            Some(TriggerEvent::Many(events)) => events.is_empty(),
+        },
+        _ => false,
+    };
+    if empty {
+        issues
+            .push(
+                Error,
+                Category::SpecCompleteness,
+                "Trigger must have an event defined".to_string(),
+            )
+            .node(&flow.trigger.id)
+            .suggest("Set the trigger event in the spec panel");
+    }
+}
+
+// --- Agent flow checks ---
+
+fn check_agent_flow(flow: &FlowDocument, issues: &mut Issues) {
+    if flow.flow.flow_type != "agent" {
+        return;
+    }
+    let agent_loops = flow
+        .all_nodes()
+        .filter(|n| n.node_type == NodeType::AgentLoop)
+        .count();
+    if agent_loops == 0 {
+        issues
+            .push(
+                Error,
+                Category::AgentValidation,
+                "Agent flow must have exactly one agent_loop node".to_string(),
+            )
+            .suggest("Add an agent_loop node from the toolbar");
+    } else if agent_loops > 1 {
+        issues.push(
+            Warning,
+            Category::AgentValidation,
+            format!(
+                "Agent flow has {} agent_loop nodes — typically only one is expected",
+                agent_loops
+            ),
+        );
+    }
+}
+
+// --- Per-node spec checks ---
+
+/// One arm per node type. Node types without required fields (`terminal`) only
+/// take part in the graph checks above.
+/// Why `node.extra[key]` doesn't read as `T`, if the block was kept there.
+fn block_error<T: DeserializeOwned>(node: &FlowNode, key: &str) -> Option<String> {
+    let block = node.extra.get(key)?;
+    serde_json::from_value::<T>(block.clone())
+        .err()
+        .map(|e| e.to_string())
+}
+
+fn check_node_spec(node: &FlowNode, issues: &mut Issues) {
+    let label = &node.label;
+    // Blocks that didn't match their type stay in `extra` (see `FlowNode`).
+    let invalid_blocks = [
+        (
+            "observability",
+            block_error::<ObservabilityConfig>(node, "observability"),
+        ),
+        ("security", block_error::<SecurityConfig>(node, "security")),
+    ];
+    for (key, error) in invalid_blocks {
+        let Some(error) = error else { continue };
+        issues
+            .push(
+                Error,
+                Category::SpecCompleteness,
+                format!("Node \"{}\" has an invalid {} block: {}", label, key, error),
+            )
+            .node(&node.id)
+            .suggest("Fix the values in the flow file");
+    }
+    let mut report = |severity, category: Category, message: String, suggestion: &str| {
+        issues
+            .push(severity, category, message)
+            .node(&node.id)
+            .suggest(suggestion);
+    };
+    let spec = Category::SpecCompleteness;
+    let orchestration = Category::OrchestrationValidation;
+
+    match &node.spec {
+        // The trigger's event is checked by `check_trigger_event`.
+        NodeSpec::Trigger(_) | NodeSpec::Terminal(_) => {}
+        NodeSpec::Input(s) => {
+            for field in s.fields.iter().flatten() {
+                if field.field_type.trim().is_empty() {
+                    report(
+                        Error,
+                        spec,
+                        format!(
+                            "Input \"{}\" field \"{}\" is missing a type",
+                            label, field.name
+                        ),
+                        "Set a type for each input field (e.g., string, number)",
+                    );
+                }
+            }
+        }
+        NodeSpec::Process(s) => {
+            if blank(&s.description) && blank(&s.action) {
+                report(
+                    Warning,
+                    spec,
+                    format!("Process \"{}\" has no description or action defined", label),
+                    "Add a description or action to clarify what this process does",
+                );
+            }
+        }
+        NodeSpec::Decision(s) => {
+            if blank(&s.condition) {
+                report(
+                    Error,
+                    spec,
+                    format!("Decision \"{}\" must have a condition defined", label),
+                    "Set the condition expression in the spec panel",
+                );
+            }
+        }
+        NodeSpec::DataStore(s) => {
+            if missing(&s.operation) {
+                report(
+                    Error,
+                    spec,
+                    format!("Data store \"{}\" must have an operation set", label),
+                    "Set the operation (create, read, update, or delete)",
+                );
+            }
+            if blank(&s.model) {
+                report(
+                    Error,
+                    spec,
+                    format!("Data store \"{}\" must have a model defined", label),
+                    "Set the model name (e.g., User, Order)",
+                );
+            }
+        }
+        NodeSpec::ServiceCall(s) => {
+            if missing(&s.method) {
+                report(
+                    Error,
+                    spec,
+                    format!("Service call \"{}\" must have a method set", label),
+                    "Set the HTTP method (GET, POST, PUT, PATCH, DELETE)",
+                );
+            }
+            if blank(&s.url) {
+                report(
+                    Error,
+                    spec,
+                    format!("Service call \"{}\" must have a URL defined", label),
+                    "Set the service URL",
+                );
+            }
+        }
+        NodeSpec::Event(s) => {
+            if missing(&s.direction) {
+                report(
+                    Error,
+                    spec,
+                    format!("Event \"{}\" must have a direction set", label),
+                    "Set the direction (emit or consume)",
+                );
+            }
+            if blank(&s.event_name) {
+                report(
+                    Error,
+                    spec,
+                    format!("Event \"{}\" must have an event name defined", label),
+                    "Set the event name",
+                );
+            }
+        }
+        NodeSpec::Loop(s) => {
+            if blank(&s.collection) {
+                report(
+                    Error,
+                    spec,
+                    format!("Loop \"{}\" must have a collection defined", label),
+                    "Set the collection to iterate over",
+                );
+            }
+            if blank(&s.iterator) {
+                report(
+                    Error,
+                    spec,
+                    format!("Loop \"{}\" must have an iterator variable defined", label),
+                    "Set the iterator variable name",
+                );
+            }
+        }
+        NodeSpec::Parallel(s) => {
+            if s.branches.as_ref().map_or(0, Vec::len) < 2 {
+                report(
+                    Error,
+                    spec,
+                    format!("Parallel \"{}\" must have at least 2 branches", label),
+                    "Add at least 2 branches to the parallel node",
+                );
+            }
+            if s.join.as_deref() == Some("n_of") && !s.join_count.is_some_and(|n| n >= 1.0) {
+                report(
+                    Error,
+                    spec,
+                    format!(
+                        "Parallel \"{}\" uses n_of join but join_count is not set",
+                        label
+                    ),
+                    "Set join_count to specify how many branches must complete",
+                );
+            }
+            let mut branch_labels = s.branches.iter().flatten().map(|b| match b {
+                ParallelBranch::Label(label) => label,
+                ParallelBranch::Detailed(branch) => &branch.label,
+            });
+            if branch_labels.any(|l| l.trim().is_empty()) {
+                report(
+                    Error,
+                    spec,
+                    format!("Parallel \"{}\" has a branch without a label", label),
+                    "Give every branch a label",
+                );
+            }
+        }
+        NodeSpec::SubFlow(s) => match s.flow_ref.as_deref().map(str::trim) {
+            None | Some("") => report(
+                Error,
+                spec,
+                format!("Sub-flow \"{}\" must have a flow reference defined", label),
+                "Set the flow_ref (e.g., domain/flow-id)",
+            ),
+            Some(flow_ref) if !flow_ref.contains('/') => {
+                report(
+                    Warning,
+                    spec,
+                    format!(
+                        "Sub-flow \"{}\" flow_ref should be in domain/flow-id format",
+                        label
+                    ),
+                    "Use the format domain/flow-id for the flow reference",
+                );
+            }
+            Some(_) => {}
+        },
+        NodeSpec::LlmCall(s) => {
+            if blank(&s.model) {
+                report(
+                    Error,
+                    spec,
+                    format!("LLM call \"{}\" must have a model specified", label),
+                    "Set the model (e.g., claude-sonnet, gpt-4o)",
+                );
+            }
+            if blank(&s.prompt_template) {
+                report(
+                    Warning,
+                    spec,
+                    format!("LLM call \"{}\" has no prompt template defined", label),
+                    "Set the prompt template with {{variables}} for dynamic content",
+                );
+            }
+        }
+        NodeSpec::Delay(s) => {
+            if s.min_ms.is_none() {
+                report(
+                    Error,
+                    spec,
+                    format!("Delay \"{}\" must have min_ms defined", label),
+                    "Set the minimum delay in milliseconds",
+                );
+            }
+        }
+        NodeSpec::Cache(s) => {
+            if blank(&s.key) {
+                report(
+                    Error,
+                    spec,
+                    format!("Cache \"{}\" must have a key defined", label),
+                    "Set the cache key template (e.g. \"search:{query}\")",
+                );
+            }
+            if missing(&s.store) {
+                report(
+                    Error,
+                    spec,
+                    format!("Cache \"{}\" must have a store defined", label),
+                    "Set the cache store (redis or memory)",
+                );
+            }
+        }
+        NodeSpec::Transform(s) => {
+            if blank(&s.input_schema) {
+                report(
+                    Error,
+                    spec,
+                    format!("Transform \"{}\" must have an input_schema defined", label),
+                    "Set the input schema name",
+                );
+            }
+            if blank(&s.output_schema) {
+                report(
+                    Error,
+                    spec,
+                    format!("Transform \"{}\" must have an output_schema defined", label),
+                    "Set the output schema name",
+                );
+            }
+        }
+        NodeSpec::Collection(s) => {
+            if missing(&s.operation) {
+                report(
+                    Error,
+                    spec,
+                    format!("Collection \"{}\" must have an operation set", label),
+                    "Set the operation (filter, map, reduce, sort, group_by, unique, flatten)",
+                );
+            }
+            if blank(&s.input) {
+                report(
+                    Error,
+                    spec,
+                    format!("Collection \"{}\" must have an input defined", label),
+                    "Set the input collection to operate on",
+                );
+            }
+        }
+        NodeSpec::Parse(s) => {
+            if missing(&s.format) {
+                report(
+                    Error,
+                    spec,
+                    format!("Parse \"{}\" must have a format set", label),
+                    "Set the format (json, csv, xml, yaml, html, markdown, regex)",
+                );
+            }
+            if blank(&s.input) {
+                report(
+                    Error,
+                    spec,
+                    format!("Parse \"{}\" must have an input defined", label),
+                    "Set the input to parse",
+                );
+            }
+        }
+        NodeSpec::Crypto(s) => {
+            if missing(&s.operation) {
+                report(
+                    Error,
+                    spec,
+                    format!("Crypto \"{}\" must have an operation set", label),
+                    "Set the operation (encrypt, decrypt, hash, sign, verify, hmac)",
+                );
+            }
+            if blank(&s.algorithm) {
+                report(
+                    Error,
+                    spec,
+                    format!("Crypto \"{}\" must have an algorithm defined", label),
+                    "Set the algorithm (e.g., aes-256-gcm, sha256)",
+                );
+            }
+            let has_key = s
+                .key_source
+                .as_ref()
+                .is_some_and(|k| !missing(&k.env) || !missing(&k.vault));
+            if !has_key {
+                report(
+                    Error,
+                    spec,
+                    format!("Crypto \"{}\" must have a key source defined", label),
+                    "Set the key source (env variable or vault path)",
+                );
+            }
+        }
+        NodeSpec::Batch(s) => {
+            if blank(&s.input) {
+                report(
+                    Error,
+                    spec,
+                    format!("Batch \"{}\" must have an input defined", label),
+                    "Set the input collection to process in batch",
+                );
+            }
+            let has_template = s
+                .operation_template
+                .as_ref()
+                .is_some_and(|t| !missing(&t.operation_type));
+            if !has_template {
+                report(
+                    Error,
+                    spec,
+                    format!(
+                        "Batch \"{}\" must have an operation template defined",
+                        label
+                    ),
+                    "Set the operation template with a type",
+                );
+            }
+        }
+        NodeSpec::Transaction(s) => {
+            if s.steps.as_ref().map_or(0, Vec::len) < 2 {
+                report(
+                    Error,
+                    spec,
+                    format!("Transaction \"{}\" must have at least 2 steps", label),
+                    "Add at least 2 steps to the transaction",
+                );
+            }
+        }
+        NodeSpec::AgentLoop(s) => {
+            let agent = Category::AgentValidation;
+            let tools = s.tools.as_deref().unwrap_or_default();
+            if tools.is_empty() {
+                report(
+                    Error,
+                    agent,
+                    format!("Agent loop \"{}\" has no tools defined", label),
+                    "Add at least one tool to the agent loop",
+                );
+            } else if !tools.iter().any(|t| t.is_terminal == Some(true)) {
+                report(
+                    Error,
+                    agent,
+                    format!(
+                        "Agent loop \"{}\" has no terminal tool — the agent needs a way to finish",
+                        label
+                    ),
+                    "Mark at least one tool as terminal (is_terminal: true)",
+                );
+            }
+            if !s.max_iterations.is_some_and(|n| n != 0.0) {
+                report(
+                    Warning,
+                    agent,
+                    format!("Agent loop \"{}\" has no max_iterations set", label),
+                    "Set max_iterations to prevent infinite loops",
+                );
+            }
+            if blank(&s.model) {
+                report(
+                    Warning,
+                    agent,
+                    format!("Agent loop \"{}\" has no LLM model specified", label),
+                    "Set the model (e.g., claude-sonnet) in the spec panel",
+                );
+            }
+        }
+        NodeSpec::Guardrail(s) => {
+            let agent = Category::AgentValidation;
+            if missing(&s.position) {
+                report(
+                    Error,
+                    agent,
+                    format!("Guardrail \"{}\" must have a position set", label),
+                    "Set the position (input or output)",
+                );
+            }
+            if s.checks.as_ref().is_none_or(Vec::is_empty) {
+                report(
+                    Error,
+                    agent,
+                    format!("Guardrail \"{}\" has no checks defined", label),
+                    "Add at least one check to the guardrail",
+                );
+            }
+        }
+        NodeSpec::HumanGate(s) => {
+            if s.approval_options.as_ref().is_none_or(Vec::is_empty) {
+                report(
+                    Error,
+                    Category::AgentValidation,
+                    format!("Human gate \"{}\" has no approval options defined", label),
+                    "Add at least one approval option (e.g., approve, reject)",
+                );
+            }
+        }
+        NodeSpec::Orchestrator(s) => {
+            if s.agents.as_ref().map_or(0, Vec::len) < 2 {
+                report(
+                    Error,
+                    orchestration,
+                    format!("Orchestrator \"{}\" must have at least 2 agents", label),
+                    "Add agents to the orchestrator in the spec panel",
+                );
+            }
+            if missing(&s.strategy) {
+                report(
+                    Error,
+                    orchestration,
+                    format!("Orchestrator \"{}\" must have a strategy defined", label),
+                    "Set the strategy (supervisor, round_robin, broadcast, or consensus)",
+                );
+            }
+        }
+        NodeSpec::SmartRouter(s) => {
+            let llm_routing = s
+                .llm_routing
+                .as_ref()
+                .is_some_and(|r| r.enabled == Some(true));
+            if s.rules.as_ref().is_none_or(Vec::is_empty) && !llm_routing {
+                report(
+                    Error,
+                    orchestration,
+                    format!("Smart router \"{}\" has no rules defined", label),
+                    "Add routing rules or enable LLM routing",
+                );
+            }
+        }
+        NodeSpec::Handoff(s) => {
+            if s.target.as_ref().is_none_or(|t| blank(&t.flow)) {
+                report(
+                    Error,
+                    orchestration,
+                    format!("Handoff \"{}\" must have a target flow", label),
+                    "Set the target flow in the spec panel",
+                );
+            }
+        }
+        NodeSpec::AgentGroup(s) => {
+            if s.members.as_ref().map_or(0, Vec::len) < 2 {
+                report(
+                    Error,
+                    orchestration,
+                    format!("Agent group \"{}\" must have at least 2 members", label),
+                    "Add members to the agent group in the spec panel",
+                );
+            }
+        }
+        NodeSpec::Other(raw) if !matches!(node.node_type, NodeType::Other(_)) => {
+            let error = NodeSpec::from_value(&node.node_type, Value::Object(raw.clone()))
+                .err()
+                .map_or_else(String::new, |e| format!(": {}", e));
+            report(
+                Error,
+                spec,
+                format!(
+                    "Node \"{}\" has a spec that doesn't match type \"{}\"{}",
+                    label,
+                    node.node_type.as_str(),
+                    error
+                ),
+                "Fix the spec values in the spec panel or the flow file",
+            );
+        }
+        NodeSpec::Other(_) => {
+            report(
+                Warning,
+                spec,
+                format!(
+                    "Node \"{}\" has unknown type \"{}\"",
+                    label,
+                    node.node_type.as_str()
+                ),
+                "Change the node to one of the supported node types",
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::from_yaml;
+    use crate::validation::ValidationSeverity;
+
+    const SUPPORT_FLOW: &str = include_str!(
+        "../../fixtures/sample-project/specs/domains/support/flows/support-ticket.yaml"
+    );
+    const REGISTER_FLOW: &str =
+        include_str!("../../fixtures/sample-project/specs/domains/users/flows/user-register.yaml");
+    const PAYMENT_FLOW: &str = include_str!(
+        "../../fixtures/sample-project/specs/domains/billing/flows/payment-processing.yaml"
+    );
+
+    /// A flow of `flow_type` whose trigger connects to `first`, followed by `nodes`.
+    fn flow(flow_type: &str, first: &str, nodes: &str) -> FlowDocument {
+        let header = format!(
+            "flow: {{id: checkout, name: Checkout, type: {}, domain: billing}}\n",
+            flow_type
+        );
+        let trigger = format!(
+            "trigger:\n  id: trigger\n  type: trigger\n  label: Start\n  spec: {{event: OrderPlaced}}\n  connections: [{{targetNodeId: {}}}]\n",
+            first
+        );
+        from_yaml(&format!("{}{}nodes:\n{}", header, trigger, nodes)).unwrap()
+    }
+
+    fn issues(result: &ValidationResult) -> Vec<(ValidationSeverity, Option<&str>, &str)> {
+        result
+            .issues
+            .iter()
+            .map(|i| (i.severity, i.node_id.as_deref(), i.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn sample_flows_have_no_errors() {
+        for content in [SUPPORT_FLOW, REGISTER_FLOW, PAYMENT_FLOW] {
+            let result = validate_flow(&from_yaml(content).unwrap());
+            assert!(result.is_valid, "{:?}", issues(&result));
+        }
+    }
+
+    #[test]
+    fn graph_problems_are_reported_like_the_webview() {
+        let result = validate_flow(&flow(
+            "traditional",
+            "check",
+            "\
+- id: check
+  type: decision
+  label: In stock?
+  spec: {condition: stock > 0}
+  connections:
+    - {targetNodeId: charge, sourceHandle: \"true\"}
+- id: charge
+  type: process
+  label: Charge
+  spec: {action: charge card}
+- id: done
+  type: terminal
+  label: Done
+  connections:
+    - targetNodeId: charge
+",
+        ));
+        assert_eq!(
+            issues(&result),
+            vec![
+                (
+                    Error,
+                    Some("charge"),
+                    "Node \"Charge\" (process) is a dead end with no outgoing connections"
+                ),
+                (
+                    Error,
+                    Some("done"),
+                    "Node \"Done\" (terminal) is unreachable from the trigger"
+                ),
+                (
+                    Error,
+                    Some("check"),
+                    "Decision \"In stock?\" is missing a \"No\" (false) branch connection"
+                ),
+                (
+                    Warning,
+                    Some("done"),
+                    "Terminal \"Done\" has outgoing connections — terminals should be endpoints"
+                ),
+            ]
+        );
+        assert_eq!((result.error_count, result.warning_count), (3, 1));
+        assert_eq!(result.target_id, "billing/checkout");
+        let issue = &result.issues[0];
+        assert_eq!(issue.category, Category::GraphCompleteness);
+        assert_eq!(
+            issue.suggestion.as_deref(),
+            Some("Connect this node to a downstream node or terminal")
+        );
+        assert_eq!(issue.flow_id.as_deref(), Some("checkout"));
+        assert_eq!(issue.domain_id.as_deref(), Some("billing"));
+        assert_eq!(issue.id.len(), 8);
+    }
+
+    #[test]
+    fn cycles_are_errors_outside_agent_flows() {
+        let nodes = "\
+- id: a
+  type: process
+  label: A
+  spec: {action: a}
+  connections:
+    - targetNodeId: b
+- id: b
+  type: process
+  label: B
+  spec: {action: b}
+  connections:
+    - targetNodeId: a
+    - targetNodeId: end
+- id: end
+  type: terminal
+  label: End
+";
+        let result = validate_flow(&flow("traditional", "a", nodes));
+        assert_eq!(
+            issues(&result),
+            vec![(
+                Error,
+                None,
+                "Flow contains a circular path (cycle detected)"
+            )]
+        );
+
+        let result = validate_flow(&flow("agent", "a", nodes));
+        assert_eq!(
+            issues(&result),
+            vec![(
+                Error,
+                None,
+                "Agent flow must have exactly one agent_loop node"
+            )]
+        );
+        assert_eq!(result.issues[0].category, Category::AgentValidation);
+    }
+
+    #[test]
+    fn flows_need_a_terminal_and_a_trigger_event() {
+        let mut doc = flow(
+            "traditional",
+            "a",
+            "- id: a\n  type: process\n  label: A\n  spec: {action: a}\n",
+        );
+        doc.trigger.spec = NodeSpec::Trigger(Default::default());
+        assert_eq!(
+            issues(&validate_flow(&doc)),
+            vec![
+                (
+                    Error,
+                    None,
+                    "Flow has no terminal nodes — all paths must end at a terminal"
+                ),
+                (Error, Some("trigger"), "Trigger must have an event defined"),
+            ]
+        );
+
+        let result = validate_flow_value(&serde_json::json!({
+            "flow": {"id": "checkout", "domain": "billing"},
+            "nodes": [],
+        }));
+        assert_eq!(
+            issues(&result),
+            vec![(Error, None, "Flow must have a trigger node")]
+        );
+        assert_eq!(result.target_id, "billing/checkout");
+    }
+
+    #[test]
+    fn incomplete_node_specs_are_reported() {
+        let result = validate_flow(&flow(
+            "traditional",
+            "store",
+            "\
+- id: store
+  type: data_store
+  label: Save
+  connections: [{targetNodeId: call}]
+- id: call
+  type: service_call
+  label: Notify
+  spec: {method: POST}
+  connections: [{targetNodeId: sub}]
+- id: sub
+  type: sub_flow
+  label: Pay
+  spec: {flow_ref: payment}
+  connections: [{targetNodeId: split}]
+- id: split
+  type: parallel
+  label: Fan out
+  spec:
+    branches: [left, \" \"]
+    join: n_of
+  connections: [{targetNodeId: end}]
+- id: end
+  type: terminal
+  label: End
+- id: odd
+  type: teleport
+  label: Beam
+",
+        ));
+        assert_eq!(
+            issues(&result),
+            vec![
+                (
+                    Error,
+                    Some("odd"),
+                    "Node \"Beam\" (teleport) is unreachable from the trigger"
+                ),
+                (
+                    Error,
+                    Some("store"),
+                    "Data store \"Save\" must have an operation set"
+                ),
+                (
+                    Error,
+                    Some("store"),
+                    "Data store \"Save\" must have a model defined"
+                ),
+                (
+                    Error,
+                    Some("call"),
+                    "Service call \"Notify\" must have a URL defined"
+                ),
+                (
+                    Warning,
+                    Some("sub"),
+                    "Sub-flow \"Pay\" flow_ref should be in domain/flow-id format"
+                ),
+                (
+                    Error,
+                    Some("split"),
+                    "Parallel \"Fan out\" uses n_of join but join_count is not set"
+                ),
+                (
+                    Error,
+                    Some("split"),
+                    "Parallel \"Fan out\" has a branch without a label"
+                ),
+                (
+                    Warning,
+                    Some("odd"),
+                    "Node \"Beam\" has unknown type \"teleport\""
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn mistyped_values_are_reported() {
+        let content = SUPPORT_FLOW
+            .replace("duration: 3600", "duration: 1h")
+            .replace(
+                "  - id: terminal-1\n",
+                "  - id: terminal-1\n    observability:\n      logging:\n        include_input: sometimes\n",
+            );
+        let result = validate_flow(&from_yaml(&content).unwrap());
+        let reported = |node: &str| {
+            result
+                .issues
+                .iter()
+                .any(|i| i.node_id.as_deref() == Some(node) && i.message.contains("invalid type"))
+        };
+        assert!(reported("human-gate-1"));
+        assert!(reported("terminal-1"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Flow, domain and system validation, ported from `src/utils/flow-validator.ts`.
+//!
+//! Results serialize to the `ValidationResult` / `ValidationIssue` shapes in
+//! `src/types/validation.ts`, so the webview can render them unchanged. Messages and
+//! suggestions are kept word for word.
+
+mod flow;
+mod schema;
+mod system;
+
+pub use flow::{validate_flow, validate_flow_value};
+pub use schema::{validate_schema, SchemaIssue};
+pub use system::{validate_domain, validate_system};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationScope {
+    Flow,
+    Domain,
+    System,
+}
+
+impl ValidationScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            ValidationScope::Flow => "flow",
+            ValidationScope::Domain => "domain",
+            ValidationScope::System => "system",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationCategory {
+    GraphCompleteness,
+    SpecCompleteness,
+    AgentValidation,
+    OrchestrationValidation,
+    DomainConsistency,
+    EventWiring,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    /// Derived from the issue's content, so re-validating an unchanged spec yields
+    /// the same ids.
+    pub id: String,
+    pub scope: ValidationScope,
+    pub severity: ValidationSeverity,
+    pub category: ValidationCategory,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flow_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain_id: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationResult {
+    pub scope: ValidationScope,
+    pub target_id: String,
+    pub issues: Vec<ValidationIssue>,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub info_count: usize,
+    pub is_valid: bool,
+    /// RFC 3339 UTC timestamp, as `Date.toISOString()` writes it.
+    pub validated_at: String,
+}
+
+impl ValidationResult {
+    fn new(scope: ValidationScope, target_id: String, mut issues: Vec<ValidationIssue>) -> Self {
+        for (i, issue) in issues.iter_mut().enumerate() {
+            issue.id = issue_id(scope, &target_id, i, issue);
+        }
+        let count = |s: ValidationSeverity| issues.iter().filter(|i| i.severity == s).count();
+        let error_count = count(ValidationSeverity::Error);
+        let warning_count = count(ValidationSeverity::Warning);
+        let info_count = count(ValidationSeverity::Info);
+        ValidationResult {
+            scope,
+            target_id,
+            issues,
+            error_count,
+            warning_count,
+            info_count,
+            is_valid: error_count == 0,
+            validated_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        }
+    }
+}
+
+/// 8 hex characters, the length of the `nanoid(8)` ids the webview generates.
+fn issue_id(
+    scope: ValidationScope,
+    target_id: &str,
+    index: usize,
+    issue: &ValidationIssue,
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        scope.as_str(),
+        target_id,
+        &index.to_string(),
+        issue.node_id.as_deref().unwrap_or(""),
+        &issue.message,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())[..8].to_string()
+}
+
+/// Collects issues for one scope; ids and counts are filled in by `ValidationResult`.
+struct Issues {
+    scope: ValidationScope,
+    issues: Vec<ValidationIssue>,
+}
+
+impl Issues {
+    fn new(scope: ValidationScope) -> Self {
+        Issues {
+            scope,
+            issues: Vec::new(),
+        }
+    }
+
+    fn push(
+        &mut self,
+        severity: ValidationSeverity,
+        category: ValidationCategory,
+        message: String,
+    ) -> &mut ValidationIssue {
+        self.issues.push(ValidationIssue {
+            id: String::new(),
+            scope: self.scope,
+            severity,
+            category,
+            message,
+            suggestion: None,
+            node_id: None,
+            flow_id: None,
+            domain_id: None,
+        });
+        self.issues.last_mut().expect("just pushed")
+    }
+}
+
+impl ValidationIssue {
+    fn suggest(&mut self, suggestion: &str) -> &mut Self {
+        self.suggestion = Some(suggestion.to_string());
+        self
+    }
+
+    fn node(&mut self, node_id: &str) -> &mut Self {
+        self.node_id = Some(node_id.to_string());
+        self
+    }
+}
+
+/// `!value` in the TypeScript checks: absent or empty.
+fn missing(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(str::is_empty)
+}
+
+/// `!value || value.trim() === ''`.
+fn blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|v| v.trim().is_empty())
+}
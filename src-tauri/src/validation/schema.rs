@@ -0,0 +1,100 @@
+use crate::spec::patch::locate;
+use crate::spec::schema::SchemaKind;
+use jsonschema::Validator;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// A place where a spec file doesn't match its schema.
+#[derive(Serialize, Clone, Debug)]
+pub struct SchemaIssue {
+    /// JSON pointer to the offending value, e.g. `/nodes/2/spec/method`; empty for
+    /// the document root.
+    pub instance_path: String,
+    pub message: String,
+    /// 1-based position of the value in the YAML source.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+fn validator(kind: SchemaKind) -> &'static Validator {
+    static VALIDATORS: [OnceLock<Validator>; 3] =
+        [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+    let index = SchemaKind::ALL.iter().position(|&k| k == kind).unwrap_or(0);
+    VALIDATORS[index].get_or_init(|| {
+        jsonschema::draft202012::new(&kind.schema())
+            .expect("generated spec schema is a valid draft 2020-12 schema")
+    })
+}
+
+/// Check a YAML spec file against the schema for its kind. A file that isn't valid
+/// YAML yields a single issue at the syntax error.
+pub fn validate_schema(kind: SchemaKind, content: &str) -> Vec<SchemaIssue> {
+    let instance: Value = match serde_yaml::from_str(content) {
+        Ok(value) => value,
+        Err(e) => {
+            let location = e.location();
+            return vec![SchemaIssue {
+                instance_path: String::new(),
+                message: e.to_string(),
+                line: location.as_ref().map(|l| l.line()),
+                column: location.as_ref().map(|l| l.column()),
+            }];
+        }
+    };
+    validator(kind)
+        .iter_errors(&instance)
+        .map(|error| {
+            let instance_path = error.instance_path().as_str().to_string();
+            let location = locate(content, &instance_path);
+            SchemaIssue {
+                message: error.to_string(),
+                line: location.map(|(line, _)| line),
+                column: location.map(|(_, column)| column),
+                instance_path,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(kind: SchemaKind, content: &str) -> Vec<(String, Option<usize>, Option<usize>)> {
+        validate_schema(kind, content)
+            .into_iter()
+            .map(|i| (i.instance_path, i.line, i.column))
+            .collect()
+    }
+
+    #[test]
+    fn issues_point_at_the_offending_key() {
+        let content = "\
+name: Billing
+flows:
+  - id: pay
+    name: Pay
+  - id: refund
+    name: 42
+layout:
+  flows:
+    pay: {x: 1, y: nope}
+";
+        assert_eq!(
+            issues(SchemaKind::Domain, content),
+            vec![
+                ("/flows/1/name".to_string(), Some(6), Some(5)),
+                ("/layout/flows/pay/y".to_string(), Some(9), Some(17)),
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_errors_are_located_by_the_parser() {
+        let found = issues(SchemaKind::Domain, "name: Billing\nflows: [\n");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "");
+        assert_eq!(found[0].1, Some(3));
+    }
+}
@@ -0,0 +1,199 @@
+use super::{
+    Issues, ValidationCategory as Category, ValidationResult, ValidationScope,
+    ValidationSeverity::{Error, Warning},
+};
+use crate::spec::DomainConfig;
+use indexmap::{IndexMap, IndexSet};
+
+pub fn validate_domain(domain_id: &str, config: &DomainConfig) -> ValidationResult {
+    let mut issues = Issues::new(ValidationScope::Domain);
+    let mut seen = IndexSet::new();
+    for flow in &config.flows {
+        if !seen.insert(flow.id.as_str()) {
+            issues.push(
+                Error,
+                Category::DomainConsistency,
+                format!(
+                    "Duplicate flow ID \"{}\" in domain \"{}\"",
+                    flow.id, config.name
+                ),
+            );
+        }
+    }
+    for issue in &mut issues.issues {
+        issue.domain_id = Some(domain_id.to_string());
+    }
+    ValidationResult::new(
+        ValidationScope::Domain,
+        domain_id.to_string(),
+        issues.issues,
+    )
+}
+
+/// Cross-domain event wiring over every domain, keyed by domain id.
+pub fn validate_system<'a>(
+    domains: impl IntoIterator<Item = (&'a str, &'a DomainConfig)>,
+) -> ValidationResult {
+    let mut issues = Issues::new(ValidationScope::System);
+    // event -> domain ids, in first-seen order
+    let mut published: IndexMap<&str, Vec<&str>> = IndexMap::new();
+    let mut consumed: IndexMap<&str, Vec<&str>> = IndexMap::new();
+    for (domain_id, config) in domains {
+        for e in &config.publishes_events {
+            published.entry(&e.event).or_default().push(domain_id);
+        }
+        for e in &config.consumes_events {
+            consumed.entry(&e.event).or_default().push(domain_id);
+        }
+    }
+
+    for (event, consumers) in &consumed {
+        if !published.contains_key(event) {
+            issues
+                .push(
+                    Error,
+                    Category::EventWiring,
+                    format!(
+                        "Event \"{}\" is consumed by {} but no domain publishes it",
+                        event,
+                        consumers.join(", ")
+                    ),
+                )
+                .suggest("Add this event to the publishing domain or remove the consumer");
+        }
+    }
+
+    for (event, publishers) in &published {
+        if !consumed.contains_key(event) {
+            issues
+                .push(
+                    Warning,
+                    Category::EventWiring,
+                    format!(
+                        "Event \"{}\" is published by {} but no domain consumes it",
+                        event,
+                        publishers.join(", ")
+                    ),
+                )
+                .suggest("This event may be unused — consider adding a consumer or removing it");
+        }
+    }
+
+    // Counted per listing, so an event both published and consumed counts twice.
+    let names: Vec<&str> = published.keys().chain(consumed.keys()).copied().collect();
+    if names.len() > 1 {
+        let dot = names.iter().filter(|n| n.contains('.')).count();
+        let camel = names
+            .iter()
+            .filter(|n| !n.contains('.') && is_camel_case(n))
+            .count();
+        if dot > 0 && camel > 0 {
+            issues
+                .push(
+                    Warning,
+                    Category::EventWiring,
+                    format!(
+                        "Inconsistent event naming: {} use dot notation, {} use camelCase",
+                        dot, camel
+                    ),
+                )
+                .suggest(
+                    "Standardize event naming across domains (prefer dot notation: domain.event.action)",
+                );
+        }
+    }
+
+    ValidationResult::new(ValidationScope::System, "system".to_string(), issues.issues)
+}
+
+/// A lowercase letter directly followed by an uppercase one (`/[a-z][A-Z]/`).
+fn is_camel_case(name: &str) -> bool {
+    name.as_bytes()
+        .windows(2)
+        .any(|w| w[0].is_ascii_lowercase() && w[1].is_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::from_yaml;
+    use crate::validation::ValidationSeverity;
+
+    fn domain(content: &str) -> DomainConfig {
+        from_yaml(content).unwrap()
+    }
+
+    fn messages(result: &ValidationResult) -> Vec<(ValidationSeverity, &str)> {
+        result
+            .issues
+            .iter()
+            .map(|i| (i.severity, i.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_flow_ids_are_errors() {
+        let config = domain("name: Billing\nflows:\n  - {id: pay, name: Pay}\n  - {id: refund, name: Refund}\n  - {id: pay, name: Pay again}\n");
+        let result = validate_domain("billing", &config);
+        assert_eq!(
+            messages(&result),
+            vec![(Error, "Duplicate flow ID \"pay\" in domain \"Billing\"")]
+        );
+        assert_eq!(result.target_id, "billing");
+        assert_eq!(result.issues[0].domain_id.as_deref(), Some("billing"));
+        assert!(!result.is_valid);
+    }
+
+    #[test]
+    fn event_wiring_is_checked_across_domains() {
+        let users = domain("name: Users\npublishes_events: [UserRegistered, UserDeleted]\n");
+        let billing = domain(
+            "name: Billing\npublishes_events: [PaymentFailed]\nconsumes_events:\n  - UserRegistered\n  - event: OrderPlaced\n",
+        );
+        let support = domain("name: Support\nconsumes_events: [PaymentFailed, OrderPlaced]\n");
+        let result = validate_system([
+            ("users", &users),
+            ("billing", &billing),
+            ("support", &support),
+        ]);
+        assert_eq!(
+            messages(&result),
+            vec![
+                (
+                    Error,
+                    "Event \"OrderPlaced\" is consumed by billing, support but no domain publishes it"
+                ),
+                (
+                    Warning,
+                    "Event \"UserDeleted\" is published by users but no domain consumes it"
+                ),
+            ]
+        );
+        assert!(result
+            .issues
+            .iter()
+            .all(|i| i.category == Category::EventWiring));
+        assert_eq!(result.target_id, "system");
+    }
+
+    #[test]
+    fn mixed_event_naming_is_a_warning() {
+        let users = domain("name: Users\npublishes_events: [user.registered, UserDeleted]\n");
+        let billing = domain("name: Billing\nconsumes_events: [user.registered, UserDeleted]\n");
+        let result = validate_system([("users", &users), ("billing", &billing)]);
+        // Counted per listing: each name is both published and consumed
+        assert_eq!(
+            messages(&result),
+            vec![(
+                Warning,
+                "Inconsistent event naming: 2 use dot notation, 2 use camelCase"
+            )]
+        );
+        assert!(result.is_valid);
+
+        let users = domain("name: Users\npublishes_events: [user.registered, user.deleted]\n");
+        let billing = domain("name: Billing\nconsumes_events: [user.registered, user.deleted]\n");
+        let result = validate_system([("users", &users), ("billing", &billing)]);
+        assert!(result.issues.is_empty());
+    }
+}
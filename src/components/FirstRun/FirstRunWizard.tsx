@@ -1,7 +1,6 @@
 import { useState } from 'react';
 import { ArrowLeft, ArrowRight, Check, CheckCircle, XCircle, Loader2, FolderOpen, SkipForward } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
-import { open } from '@tauri-apps/plugin-dialog';
 import { useAppStore, DEFAULT_SETTINGS } from '../../stores/app-store';
 import { createSampleProject } from '../../utils/sample-project';
 import type { GlobalSettings, ProviderConfig } from '../../types/app';
@@ -132,9 +131,9 @@ export function FirstRunWizard() {
 
     if (startChoice === 'open') {
       try {
-        const selected = await open({ directory: true, multiple: false });
+        const selected = await invoke<string | null>('pick_directory');
         if (selected) {
-          openProject(selected as string);
+          openProject(selected);
           return;
         }
       } catch {
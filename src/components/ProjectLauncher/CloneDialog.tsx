@@ -1,7 +1,6 @@
 import { useState } from 'react';
 import { X, Loader2, FolderOpen } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
-import { open } from '@tauri-apps/plugin-dialog';
 
 interface Props {
   onClose: () => void;
@@ -17,8 +16,8 @@ export function CloneDialog({ onClose, onCloned }: Props) {
 
   async function handleBrowse() {
     try {
-      const selected = await open({ directory: true, multiple: false });
-      if (selected) setDestination(selected as string);
+      const selected = await invoke<string | null>('pick_directory');
+      if (selected) setDestination(selected);
     } catch {
       // User cancelled
     }
@@ -1,6 +1,6 @@
 import { useState } from 'react';
 import { ArrowLeft, ArrowRight, FolderOpen, Plus, Trash2, Check } from 'lucide-react';
-import { open } from '@tauri-apps/plugin-dialog';
+import { invoke } from '@tauri-apps/api/core';
 import type { NewProjectConfig } from '../../types/app';
 import { useAppStore } from '../../stores/app-store';
 
@@ -40,9 +40,9 @@ export function NewProjectWizard({ onClose }: Props) {
         : config.domains.some((d) => d.name.trim() !== '');
 
   async function handleBrowseLocation() {
-    const selected = await open({ directory: true, multiple: false });
+    const selected = await invoke<string | null>('pick_directory');
     if (selected) {
-      setConfig((c) => ({ ...c, location: selected as string }));
+      setConfig((c) => ({ ...c, location: selected }));
     }
   }
 
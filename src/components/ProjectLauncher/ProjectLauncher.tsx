@@ -1,6 +1,6 @@
 import { useState } from 'react';
 import { Plus, FolderOpen, Settings, GitPullRequest } from 'lucide-react';
-import { open } from '@tauri-apps/plugin-dialog';
+import { invoke } from '@tauri-apps/api/core';
 import { useAppStore } from '../../stores/app-store';
 import { RecentProjects } from './RecentProjects';
 import { NewProjectWizard } from './NewProjectWizard';
@@ -16,9 +16,9 @@ export function ProjectLauncher() {
 
   async function handleOpenExisting() {
     try {
-      const selected = await open({ directory: true, multiple: false });
+      const selected = await invoke<string | null>('pick_directory');
       if (selected) {
-        openProject(selected as string);
+        openProject(selected);
       }
     } catch (e) {
       pushError('error', 'file', 'Failed to open project', String(e));
@@ -5,7 +5,10 @@ export function GitSettings() {
   const updateSettings = useAppStore((s) => s.updateSettings);
   const saveSettings = useAppStore((s) => s.saveSettings);
 
-  function updateGit(field: 'autoCommitMessage' | 'branchNaming', value: string) {
+  function updateGit(
+    field: 'autoCommitMessage' | 'branchNaming' | 'authorName' | 'authorEmail' | 'signCommits' | 'flowTrailers',
+    value: string | boolean
+  ) {
     const updated = {
       ...settings,
       git: { ...settings.git, [field]: value },
@@ -19,7 +22,7 @@ export function GitSettings() {
       <div>
         <h3 className="text-sm font-semibold text-text-primary mb-1">Git</h3>
         <p className="text-xs text-text-muted mb-3">
-          Configure auto-commit messages and branch naming conventions.
+          Configure commit identity, signing, auto-commit messages and branch naming conventions.
         </p>
       </div>
 
@@ -48,6 +51,53 @@ export function GitSettings() {
           Available placeholders: <code className="text-accent">{'{flow_id}'}</code> (flow identifier)
         </p>
       </div>
+
+      <div>
+        <label className="label">Commit Author</label>
+        <div className="flex gap-2">
+          <input
+            className="input"
+            value={settings.git.authorName ?? ''}
+            onChange={(e) => updateGit('authorName', e.target.value)}
+            placeholder="Name"
+          />
+          <input
+            className="input"
+            value={settings.git.authorEmail ?? ''}
+            onChange={(e) => updateGit('authorEmail', e.target.value)}
+            placeholder="Email"
+          />
+        </div>
+        <p className="text-xs text-text-muted mt-1">
+          Leave empty to use <code className="text-accent">user.name</code> and <code className="text-accent">user.email</code> from git config. A project can override these under <code className="text-accent">git</code> in <code className="text-accent">.ddd/config.yaml</code>.
+        </p>
+      </div>
+
+      <label className="flex items-center gap-3 cursor-pointer">
+        <input
+          type="checkbox"
+          className="w-4 h-4 rounded accent-accent"
+          checked={settings.git.signCommits ?? false}
+          onChange={(e) => updateGit('signCommits', e.target.checked)}
+        />
+        <div>
+          <span className="text-sm text-text-primary">Sign commits</span>
+          <p className="text-xs text-text-muted">Sign with the GPG or SSH key from git config; when off, <code className="text-accent">commit.gpgsign</code> decides</p>
+        </div>
+      </label>
+
+      <label className="flex items-center gap-3 cursor-pointer">
+        <input
+          type="checkbox"
+          className="w-4 h-4 rounded accent-accent"
+          checked={settings.git.flowTrailers ?? true}
+          onChange={(e) => updateGit('flowTrailers', e.target.checked)}
+        />
+        <div>
+          <span className="text-sm text-text-primary">Add flow trailers</span>
+          <p className="text-xs text-text-muted">Append a <code className="text-accent">DDD-Flow: domain/flow</code> line for each staged flow spec</p>
+        </div>
+      </label>
     </div>
   );
 }
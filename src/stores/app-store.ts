@@ -83,6 +83,10 @@ export const DEFAULT_SETTINGS: GlobalSettings = {
   git: {
     autoCommitMessage: 'DDD: {action} in {flow_id}',
     branchNaming: 'ddd/{flow_id}',
+    authorName: '',
+    authorEmail: '',
+    signCommits: false,
+    flowTrailers: true,
   },
   reconciliation: {
     autoRun: false,
@@ -170,11 +174,11 @@ export const useAppStore = create<AppState>((set, get) => ({
       });
       const projects = JSON.parse(content) as RecentProject[];
 
-      // Prune projects whose paths no longer exist
+      // Prune projects that no longer exist
       const checks = await Promise.all(
         projects.map(async (p) => {
-          const pathExists: boolean = await invoke('path_exists', { path: p.path });
-          return pathExists;
+          const isProject: boolean = await invoke('is_ddd_project', { path: p.path });
+          return isProject;
         })
       );
       const pruned = projects.filter((_, i) => checks[i]);
@@ -317,8 +321,8 @@ export const useAppStore = create<AppState>((set, get) => ({
   createProject: async (config) => {
     const projectPath = `${config.location}/${config.name}`;
     try {
-      // Create project directory
-      await invoke('create_directory', { path: projectPath });
+      // Create the project directory inside the picked location
+      await invoke('set_project_root', { path: projectPath, create: true });
 
       // Create specs directory
       await invoke('create_directory', {
@@ -369,13 +373,30 @@ export const useAppStore = create<AppState>((set, get) => ({
         await invoke('git_init', { path: projectPath });
         await invoke('write_file', {
           path: `${projectPath}/.gitignore`,
-          contents: 'node_modules/\n.DS_Store\n',
+          contents: 'node_modules/\n.DS_Store\n.ddd/trash/\n',
         });
         await invoke('git_add_all', { path: projectPath });
-        await invoke('git_commit', {
-          path: projectPath,
-          message: 'Initial DDD project setup',
-        });
+        // The new project has no .ddd/config.yaml override yet
+        const { authorName, authorEmail, signCommits } = get().settings.git;
+        try {
+          await invoke('git_commit', {
+            path: projectPath,
+            message: 'Initial DDD project setup',
+            options: {
+              author_name: authorName || null,
+              author_email: authorEmail || null,
+              sign: signCommits ? true : null,
+            },
+          });
+        } catch (e) {
+          // Usually no git identity yet; the project itself is ready
+          get().pushError(
+            'warning',
+            'git',
+            'Project created without an initial commit',
+            `${String(e)}\nThe files are staged; commit them from the Git panel once an author is set.`,
+          );
+        }
       }
 
       get().openProject(projectPath);
@@ -422,6 +443,11 @@ export const useAppStore = create<AppState>((set, get) => ({
         line: logLine,
         maxBytes: 10 * 1024 * 1024, // 10MB
         maxFiles: 5,
+        rotation: {
+          compress: true,
+          daily: true,
+          max_total_bytes: 30 * 1024 * 1024, // 30MB across all segments
+        },
       }).catch(() => { /* Silent — logging should never fail loudly */ });
     }
 
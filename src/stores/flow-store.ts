@@ -30,6 +30,7 @@ import type {
   HandoffSpec,
   AgentGroupSpec,
 } from '../types/flow';
+import { SPEC_VERSION } from '../types/domain';
 import type { Position } from '../types/sheet';
 
 // --- Skip-undo flag (used by undo-store to prevent loops) ---
@@ -362,6 +363,7 @@ function createDefaultFlow(domainId: string, flowId: string, flowName: string, f
       created: now,
       modified: now,
     },
+    spec_version: SPEC_VERSION,
   };
 
   // Agent flows get a pre-placed agent_loop node
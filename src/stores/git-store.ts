@@ -1,10 +1,28 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { nanoid } from 'nanoid';
 import { useProjectStore } from './project-store';
-import type { GitFileEntry, GitStatusResult, GitLogEntry } from '../types/git';
+import { useAppStore } from './app-store';
+import type {
+  GitFileEntry, GitStatusResult, GitLogEntry, GitBranch, GitFileDiff, GitDiffHunk, SpecDiff,
+  GitProgressEvent, GitRemoteOperation, CommitTrailer, GitFileHistoryEntry,
+} from '../types/git';
+
+interface RemoteOperationState {
+  id: string;
+  operation: GitRemoteOperation;
+  lines: string[];
+  percent: number | null;
+}
 
 interface GitState {
   branch: string;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  branches: GitBranch[];
+  remoteOperation: RemoteOperationState | null;
   staged: GitFileEntry[];
   unstaged: GitFileEntry[];
   untracked: string[];
@@ -18,7 +36,25 @@ interface GitState {
   stageFile: (filePath: string) => Promise<void>;
   unstageFile: (filePath: string) => Promise<void>;
   stageAll: () => Promise<void>;
+  diffFile: (filePath: string, staged: boolean) => Promise<GitFileDiff | null>;
+  stageHunk: (filePath: string, hunk: GitDiffHunk) => Promise<void>;
+  unstageHunk: (filePath: string, hunk: GitDiffHunk) => Promise<void>;
   commit: () => Promise<void>;
+  loadBranches: () => Promise<void>;
+  createBranch: (name: string, options?: { from?: string; checkout?: boolean }) => Promise<void>;
+  checkoutBranch: (name: string, force?: boolean) => Promise<void>;
+  checkoutFlowBranch: (flowId: string) => Promise<string | null>;
+  renameBranch: (name: string, newName: string) => Promise<void>;
+  deleteBranch: (name: string, force?: boolean) => Promise<void>;
+  fetch: (options?: { remote?: string; all?: boolean; prune?: boolean }) => Promise<string | null>;
+  pull: (strategy?: 'rebase' | 'merge', options?: { remote?: string; branch?: string }) => Promise<string | null>;
+  push: (options?: { remote?: string; branch?: string; setUpstream?: boolean; forceWithLease?: boolean }) => Promise<string | null>;
+  cancelRemoteOperation: () => Promise<void>;
+  fileHistory: (filePath: string, limit?: number) => Promise<GitFileHistoryEntry[]>;
+  showFileAt: (filePath: string, rev: string) => Promise<string | null>;
+  restoreFile: (filePath: string, rev: string, force?: boolean) => Promise<void>;
+  specDiff: (filePath: string, options?: { from?: string; to?: string; ignorePositions?: boolean }) => Promise<SpecDiff | null>;
+  registerMergeDriver: () => Promise<string>;
   setCommitMessage: (msg: string) => void;
   togglePanel: () => void;
   reset: () => void;
@@ -28,8 +64,44 @@ function getProjectPath(): string | null {
   return useProjectStore.getState().projectPath;
 }
 
+/** Branch name for a flow from the `git.branchNaming` setting, e.g. `ddd/{flow_id}`. */
+export function flowBranchName(flowId: string): string {
+  const template = useAppStore.getState().settings.git.branchNaming || 'ddd/{flow_id}';
+  return template.split('{flow_id}').join(flowId);
+}
+
+/**
+ * `git_commit` options from the git settings; the project's `.ddd/config.yaml`
+ * (`git.authorName` etc.) overrides the global ones.
+ */
+export function commitOptions(trailers: CommitTrailer[] = []) {
+  const { settings, projectSettings } = useAppStore.getState();
+  const git = { ...settings.git, ...projectSettings?.git };
+  return {
+    author_name: git.authorName || null,
+    author_email: git.authorEmail || null,
+    trailers,
+    sign: git.signCommits ? true : null,
+  };
+}
+
+/** `DDD-Flow: <domain>/<flow>` trailers for the flow specs among `paths`. */
+export function flowTrailers(paths: string[]): CommitTrailer[] {
+  const keys = new Set<string>();
+  for (const path of paths) {
+    const match = /^specs\/domains\/([^/]+)\/flows\/([^/]+)\.yaml$/.exec(path);
+    if (match) keys.add(`${match[1]}/${match[2]}`);
+  }
+  return [...keys].sort().map((value) => ({ key: 'DDD-Flow', value }));
+}
+
 const defaults = {
   branch: '',
+  upstream: null as string | null,
+  ahead: 0,
+  behind: 0,
+  branches: [] as GitBranch[],
+  remoteOperation: null as RemoteOperationState | null,
   staged: [] as GitFileEntry[],
   unstaged: [] as GitFileEntry[],
   untracked: [] as string[],
@@ -40,6 +112,32 @@ const defaults = {
   commitMessage: '',
 };
 
+// Runs one fetch/pull/push at a time, collecting its `git-progress` lines.
+// Errors are rethrown with their `[kind]` prefix (see gitRemoteErrorKind).
+async function runRemote(
+  operation: GitRemoteOperation,
+  args: Record<string, unknown>,
+): Promise<string | null> {
+  const path = getProjectPath();
+  if (!path || useGitStore.getState().remoteOperation) return null;
+
+  const operationId = nanoid();
+  useGitStore.setState({ remoteOperation: { id: operationId, operation, lines: [], percent: null } });
+  const unlisten = await listen<GitProgressEvent>('git-progress', ({ payload }) => {
+    const current = useGitStore.getState().remoteOperation;
+    if (!current || payload.operation_id !== current.id) return;
+    const lines = payload.percent === null ? [...current.lines, payload.line] : current.lines;
+    useGitStore.setState({ remoteOperation: { ...current, lines, percent: payload.percent ?? current.percent } });
+  });
+  try {
+    return await invoke<string>(`git_${operation}`, { path, operationId, ...args });
+  } finally {
+    unlisten();
+    useGitStore.setState({ remoteOperation: null });
+    await Promise.all([useGitStore.getState().refresh(), useGitStore.getState().loadBranches()]);
+  }
+}
+
 export const useGitStore = create<GitState>((set, get) => ({
   ...defaults,
 
@@ -55,6 +153,9 @@ export const useGitStore = create<GitState>((set, get) => ({
       ]);
       set({
         branch: status.branch,
+        upstream: status.upstream,
+        ahead: status.ahead,
+        behind: status.behind,
         staged: status.staged,
         unstaged: status.unstaged,
         untracked: status.untracked,
@@ -87,14 +188,45 @@ export const useGitStore = create<GitState>((set, get) => ({
     await get().refresh();
   },
 
+  diffFile: async (filePath, staged) => {
+    const path = getProjectPath();
+    if (!path) return null;
+    if (staged) {
+      const files = await invoke<GitFileDiff[]>('git_diff_staged', { path, filePath });
+      return files[0] ?? null;
+    }
+    return invoke<GitFileDiff | null>('git_diff_file', { path, filePath });
+  },
+
+  stageHunk: async (filePath, hunk) => {
+    const path = getProjectPath();
+    if (!path) return;
+    await invoke('git_stage_hunk', { path, filePath, hunk });
+    await get().refresh();
+  },
+
+  unstageHunk: async (filePath, hunk) => {
+    const path = getProjectPath();
+    if (!path) return;
+    await invoke('git_unstage_hunk', { path, filePath, hunk });
+    await get().refresh();
+  },
+
   commit: async () => {
     const path = getProjectPath();
-    const { commitMessage } = get();
+    const { commitMessage, staged } = get();
     if (!path || !commitMessage.trim()) return;
 
+    // Saved settings predating the option leave it undefined; on by default
+    const withFlows = useAppStore.getState().settings.git.flowTrailers !== false;
+    const trailers = withFlows ? flowTrailers(staged.map((f) => f.path)) : [];
     set({ committing: true });
     try {
-      await invoke('git_commit', { path, message: commitMessage.trim() });
+      await invoke('git_commit', {
+        path,
+        message: commitMessage.trim(),
+        options: commitOptions(trailers),
+      });
       set({ commitMessage: '' });
       await get().refresh();
     } finally {
@@ -102,6 +234,130 @@ export const useGitStore = create<GitState>((set, get) => ({
     }
   },
 
+  loadBranches: async () => {
+    const path = getProjectPath();
+    if (!path) return;
+    const branches = await invoke<GitBranch[]>('git_list_branches', { path });
+    set({ branches });
+  },
+
+  createBranch: async (name, options = {}) => {
+    const path = getProjectPath();
+    if (!path) return;
+    await invoke<GitBranch>('git_create_branch', {
+      path,
+      name,
+      from: options.from ?? null,
+      checkout: options.checkout ?? false,
+    });
+    await Promise.all([get().loadBranches(), get().refresh()]);
+  },
+
+  checkoutBranch: async (name, force = false) => {
+    const path = getProjectPath();
+    if (!path) return;
+    // Refused by the backend while tracked files have uncommitted changes, unless forced
+    await invoke<GitBranch>('git_checkout_branch', { path, name, force });
+    await Promise.all([get().loadBranches(), get().refresh()]);
+  },
+
+  checkoutFlowBranch: async (flowId) => {
+    const path = getProjectPath();
+    if (!path) return null;
+    const name = flowBranchName(flowId);
+    if (get().branch === name) return name;
+
+    const branches = await invoke<GitBranch[]>('git_list_branches', { path });
+    if (branches.some((b) => !b.is_remote && b.name === name)) {
+      await get().checkoutBranch(name);
+    } else {
+      await get().createBranch(name, { checkout: true });
+    }
+    return name;
+  },
+
+  renameBranch: async (name, newName) => {
+    const path = getProjectPath();
+    if (!path) return;
+    await invoke<GitBranch>('git_rename_branch', { path, name, newName });
+    await Promise.all([get().loadBranches(), get().refresh()]);
+  },
+
+  deleteBranch: async (name, force = false) => {
+    const path = getProjectPath();
+    if (!path) return;
+    await invoke('git_delete_branch', { path, name, force });
+    await get().loadBranches();
+  },
+
+  fetch: (options = {}) =>
+    runRemote('fetch', {
+      remote: options.remote ?? null,
+      all: options.all ?? false,
+      prune: options.prune ?? false,
+    }),
+
+  pull: (strategy = 'rebase', options = {}) =>
+    runRemote('pull', {
+      strategy,
+      remote: options.remote ?? null,
+      branch: options.branch ?? null,
+    }),
+
+  push: (options = {}) =>
+    runRemote('push', {
+      remote: options.remote ?? null,
+      branch: options.branch ?? null,
+      setUpstream: options.setUpstream ?? false,
+      forceWithLease: options.forceWithLease ?? false,
+    }),
+
+  cancelRemoteOperation: async () => {
+    const current = get().remoteOperation;
+    if (!current) return;
+    await invoke('cancel_git_operation', { operationId: current.id });
+  },
+
+  fileHistory: async (filePath, limit) => {
+    const path = getProjectPath();
+    if (!path) return [];
+    // Follows renames; entry.path is the file's path in that commit
+    return invoke<GitFileHistoryEntry[]>('git_file_history', { path, filePath, limit: limit ?? null });
+  },
+
+  showFileAt: async (filePath, rev) => {
+    const path = getProjectPath();
+    if (!path) return null;
+    return invoke<string | null>('git_show_file_at', { path, filePath, rev });
+  },
+
+  restoreFile: async (filePath, rev, force = false) => {
+    const path = getProjectPath();
+    if (!path) return;
+    // Refused by the backend if the file has uncommitted changes, unless forced
+    await invoke('git_restore_file', { path, filePath, rev, force });
+    await get().refresh();
+  },
+
+  specDiff: async (filePath, options = {}) => {
+    const path = getProjectPath();
+    if (!path) return null;
+    // Defaults to working tree vs HEAD
+    return invoke<SpecDiff>('diff_spec_revisions', {
+      path: `${path}/${filePath}`,
+      from: options.from ?? null,
+      to: options.to ?? null,
+      ignorePositions: options.ignorePositions ?? false,
+    });
+  },
+
+  registerMergeDriver: async () => {
+    // Writes the driver to git config and the spec patterns to .gitattributes
+    const command = await invoke<string>('register_merge_driver');
+    await get().refresh();
+    return command;
+  },
+
   setCommitMessage: (msg) => set({ commitMessage: msg }),
 
   togglePanel: () => {
@@ -1,12 +1,15 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
-import { stringify, parse } from 'yaml';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { stringify } from 'yaml';
 import { nanoid } from 'nanoid';
-import type { DomainConfig, DomainFlowEntry, EventWiring, SystemLayout } from '../types/domain';
+import { SPEC_VERSION, type DomainConfig, type DomainFlowEntry, type EventWiring, type SystemLayout } from '../types/domain';
 import type { Position } from '../types/sheet';
 import type { FlowDocument } from '../types/flow';
 import { generateAutoLayout } from '../utils/domain-parser';
 import { FLOW_TEMPLATES } from '../utils/flow-templates';
+import { useAppStore } from './app-store';
+import { commitOptions } from './git-store';
 
 interface ProjectState {
   projectPath: string | null;
@@ -29,10 +32,147 @@ interface ProjectState {
   updateEventWiring: (domainId: string, type: 'publish' | 'consume', index: number, wiring: EventWiring) => Promise<void>;
   removeEventWiring: (domainId: string, type: 'publish' | 'consume', index: number) => Promise<void>;
   addEventArrow: (sourceDomainId: string, targetDomainId: string, eventName: string, fromFlow?: string, handledByFlow?: string, description?: string) => Promise<void>;
+  previewRename: (rename: SpecRename) => Promise<RenamePlan>;
+  applyRename: (rename: SpecRename) => Promise<RenamePlan>;
   reloadProject: () => Promise<void>;
   reset: () => void;
 }
 
+type FileOperation =
+  | { op: 'write'; path: string; contents: string; expected_hash?: string }
+  | { op: 'delete'; path: string }
+  | { op: 'rename'; from: string; to: string; overwrite?: boolean }
+  | { op: 'mkdir'; path: string };
+
+interface SpecParseError {
+  path: string;
+  message: string;
+  line: number | null;
+  column: number | null;
+}
+
+interface ProjectGraph {
+  root: string;
+  project: { domains: Array<{ name: string; description?: string }>; spec_version?: number } | null;
+  system_layout: SystemLayout | null;
+  domains: Record<string, { config: DomainConfig | null; flows: Record<string, FlowDocument> }>;
+  errors: SpecParseError[];
+  file_count: number;
+  elapsed_ms: number;
+}
+
+interface MigrationReport {
+  spec_version: number;
+  dry_run: boolean;
+  files: Array<{ path: string; kind: string; from: number; to: number; changes: string[] }>;
+  errors: SpecParseError[];
+  safeguard: { kind: 'commit'; id: string } | { kind: 'backup'; path: string } | null;
+  commit_error: string | null;
+}
+
+/** A domain (`to` is the new name), flow, event or node id to rename across the project. */
+export type SpecRename =
+  | { kind: 'domain'; from: string; to: string }
+  | { kind: 'flow'; domain: string; from: string; to: string }
+  | { kind: 'event'; from: string; to: string }
+  | { kind: 'node'; domain: string; flow: string; from: string; to: string };
+
+export interface RenamePlan {
+  rename: SpecRename;
+  edits: Array<{ path: string; new_path: string | null; references: number; diff: string }>;
+  skipped: SpecParseError[];
+}
+
+interface FileBatchReport {
+  committed: boolean;
+  operations: Array<{ index: number; status: string; error: string | null }>;
+  rollback_errors: string[];
+  cleanup_errors: string[];
+}
+
+/** Apply file operations all-or-nothing via the backend; throws with the first failure. */
+async function applyFileBatch(operations: FileOperation[]): Promise<void> {
+  const report: FileBatchReport = await invoke('apply_file_batch', { operations });
+  if (!report.committed) {
+    const failed = report.operations.find((o) => o.status === 'failed');
+    throw new Error(failed?.error ?? 'File batch failed');
+  }
+  if (report.cleanup_errors.length > 0) {
+    useAppStore
+      .getState()
+      .pushError('warning', 'file', 'Some leftover files could not be cleaned up', report.cleanup_errors.join('\n'));
+  }
+}
+
+type YamlPatch =
+  | { op: 'set'; path: string; value: unknown }
+  | { op: 'remove'; path: string }
+  | { op: 'append'; path: string; value: unknown }
+  | { op: 'rename_key'; path: string; to: string };
+
+/**
+ * Edit a spec file in place so hand-written comments, key order and quoting survive.
+ * Only when the file can't be patched (missing, or not valid YAML) is the whole
+ * document rewritten; conflicts and other failures are reported and leave the file alone.
+ */
+async function patchYaml(path: string, patches: YamlPatch[], fallback: unknown): Promise<void> {
+  try {
+    await invoke('patch_yaml_file', { path, patches });
+  } catch (err) {
+    if (String(err).startsWith('[unpatchable]')) {
+      await invoke('write_file', { path, contents: stringify(fallback) });
+      return;
+    }
+    useAppStore.getState().pushError('error', 'file', `Failed to update ${path}`, String(err));
+  }
+}
+
+/**
+ * Offer to upgrade spec files written by older versions. Nothing is rewritten unless
+ * the user agrees; the migration commit uses the configured git identity.
+ */
+async function offerMigration(projectPath: string): Promise<void> {
+  const { pushError, loadProjectSettings } = useAppStore.getState();
+  const plan: MigrationReport = await invoke('migrate_project', { dryRun: true });
+  for (const err of plan.errors) {
+    pushError('warning', 'file', `Failed to migrate ${err.path}`, err.message);
+  }
+  if (plan.files.length === 0) return;
+
+  const listed = plan.files.slice(0, 10).map((f) => f.path);
+  if (plan.files.length > listed.length) listed.push(`…and ${plan.files.length - listed.length} more`);
+  const upgrade = await ask(
+    `${plan.files.length} spec file(s) were written by an older version of DDD Tool. ` +
+      `Upgrade them to spec_version ${plan.spec_version} now?\n\n${listed.join('\n')}`,
+    { title: 'Upgrade project specs', kind: 'info', okLabel: 'Upgrade', cancelLabel: 'Not now' },
+  );
+  if (!upgrade) {
+    pushError('info', 'file', 'Spec files were not upgraded', 'You will be asked again the next time the project is opened.');
+    return;
+  }
+
+  // The project's .ddd/config.yaml may override the commit identity
+  await loadProjectSettings(projectPath);
+  const migration: MigrationReport = await invoke('migrate_project', {
+    dryRun: false,
+    options: commitOptions(),
+  });
+  const safeguard = migration.safeguard?.kind === 'commit'
+    ? `Previous versions are in the parent of commit ${migration.safeguard.id.slice(0, 7)}.`
+    : migration.safeguard?.kind === 'backup'
+      ? `Previous versions were copied to ${migration.safeguard.path}.`
+      : '';
+  pushError(
+    'info',
+    'file',
+    `Upgraded ${migration.files.length} spec file(s) to spec_version ${migration.spec_version}`,
+    [...migration.files.map((f) => f.path), safeguard].filter(Boolean).join('\n'),
+  );
+  if (migration.commit_error) {
+    pushError('warning', 'git', 'The spec upgrade was not committed', migration.commit_error);
+  }
+}
+
 let saveLayoutTimer: ReturnType<typeof setTimeout> | null = null;
 const domainSaveTimers: Record<string, ReturnType<typeof setTimeout>> = {};
 
@@ -47,86 +187,77 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     set({ loading: true, loaded: false, projectPath: path });
 
     try {
-      // Read or initialize ddd-project.json
-      let projectConfig: { domains: Array<{ name: string; description?: string }> };
-      const projectJsonPath = `${path}/ddd-project.json`;
-      const projectExists: boolean = await invoke('path_exists', { path: projectJsonPath });
+      // Confine backend file commands to this project
+      await invoke('set_project_root', { path });
 
-      if (projectExists) {
-        const projectJson: string = await invoke('read_file', { path: projectJsonPath });
-        projectConfig = JSON.parse(projectJson);
-      } else {
+      // Report edits made outside the app (git pull, other editors)
+      try {
+        await invoke('start_project_watcher', { path });
+      } catch (e) {
+        useAppStore.getState().pushError('warning', 'file', 'Could not watch the project for changes', String(e));
+      }
+
+      // Upgrade files written by older versions before reading them
+      await offerMigration(path);
+
+      // Read project.json, system layout, every domain.yaml and flow in one call
+      const graph: ProjectGraph = await invoke('load_project');
+      for (const err of graph.errors) {
+        const where = err.line != null ? `${err.path}:${err.line}:${err.column ?? 1}` : err.path;
+        useAppStore.getState().pushError('warning', 'file', `Failed to load ${err.path}`, `${where}: ${err.message}`);
+      }
+
+      let projectConfig = graph.project;
+      const projectJsonPath = `${path}/ddd-project.json`;
+      if (!projectConfig) {
+        const projectError = graph.errors.find((err) => err.path === 'ddd-project.json');
+        if (projectError) {
+          throw new Error(`Invalid ddd-project.json: ${projectError.message}`);
+        }
         // No ddd-project.json — initialize as empty DDD project
-        projectConfig = { domains: [] };
+        projectConfig = { domains: [], spec_version: SPEC_VERSION };
         await invoke('write_file', {
           path: projectJsonPath,
           contents: JSON.stringify(projectConfig, null, 2),
         });
       }
 
-      // Load domain configs
+      // Domain configs; missing or unparseable ones fall back to a minimal config
       const domainConfigs: Record<string, DomainConfig> = {};
       for (const domain of projectConfig.domains) {
         const domainId = domain.name.toLowerCase().replace(/\s+/g, '-');
-        const domainYamlPath = `${path}/specs/domains/${domainId}/domain.yaml`;
-
-        try {
-          const exists: boolean = await invoke('path_exists', {
-            path: domainYamlPath,
-          });
-          if (exists) {
-            const yamlContent: string = await invoke('read_file', {
-              path: domainYamlPath,
-            });
-            const parsed = parse(yamlContent) as DomainConfig;
-            domainConfigs[domainId] = parsed;
-          } else {
-            // Fallback: create a minimal config from project.json
-            domainConfigs[domainId] = {
-              name: domain.name,
-              description: domain.description,
-              flows: [],
-              publishes_events: [],
-              consumes_events: [],
-              layout: { flows: {}, portals: {} },
-            };
-          }
-        } catch {
-          // Fallback on parse error
-          domainConfigs[domainId] = {
-            name: domain.name,
-            description: domain.description,
-            flows: [],
-            publishes_events: [],
-            consumes_events: [],
-            layout: { flows: {}, portals: {} },
-          };
-        }
+        domainConfigs[domainId] = graph.domains[domainId]?.config ?? {
+          name: domain.name,
+          description: domain.description,
+          flows: [],
+          publishes_events: [],
+          consumes_events: [],
+          layout: { flows: {}, portals: {} },
+        };
       }
 
-      // Load or generate system layout
+      // Use the saved system layout, or generate one (and save it if none exists)
       let systemLayout: SystemLayout;
-      const layoutPath = `${path}/specs/system-layout.yaml`;
-      try {
-        const exists: boolean = await invoke('path_exists', {
-          path: layoutPath,
-        });
-        if (exists) {
-          const layoutContent: string = await invoke('read_file', {
-            path: layoutPath,
-          });
-          systemLayout = parse(layoutContent) as SystemLayout;
-        } else {
-          systemLayout = generateAutoLayout(Object.keys(domainConfigs));
+      if (graph.system_layout) {
+        systemLayout = graph.system_layout;
+      } else {
+        systemLayout = generateAutoLayout(Object.keys(domainConfigs));
+        const layoutUnreadable = graph.errors.some((err) => err.path === 'specs/system-layout.yaml');
+        if (!layoutUnreadable) {
           await invoke('write_file', {
-            path: layoutPath,
+            path: `${path}/specs/system-layout.yaml`,
             contents: stringify(systemLayout),
+          }).catch(() => {
+            // Keep the generated layout in memory
           });
         }
-      } catch {
-        systemLayout = generateAutoLayout(Object.keys(domainConfigs));
       }
 
+      // Keep .ddd/schema/ in step with this build's spec format for editor tooling
+      invoke('write_spec_schemas').catch(() => {
+        // Schemas are optional; the project still works without them
+      });
+
       set({
         domainConfigs,
         systemLayout,
@@ -153,6 +284,7 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
       publishes_events: [],
       consumes_events: [],
       layout: { flows: {}, portals: {} },
+      spec_version: SPEC_VERSION,
     };
 
     // Auto-position below existing domains
@@ -228,11 +360,12 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     const updatedConfigs = { ...domainConfigs, [domainId]: updatedDomain };
     set({ domainConfigs: updatedConfigs });
 
-    // Write updated domain.yaml
-    await invoke('write_file', {
-      path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-      contents: stringify(updatedDomain),
-    });
+    // Append the entry to domain.yaml
+    await patchYaml(
+      `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+      [{ op: 'append', path: 'flows', value: entry }],
+      updatedDomain,
+    );
 
     // Check if a template was requested
     const template = templateId ? FLOW_TEMPLATES.find((t) => t.id === templateId) : null;
@@ -264,6 +397,7 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
         },
         nodes: [],
         metadata: { created: now, modified: now },
+        spec_version: SPEC_VERSION,
       };
 
       // Agent flows get a pre-placed agent_loop node
@@ -320,11 +454,15 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     const updatedConfigs = { ...domainConfigs, [domainId]: updatedDomain };
     set({ domainConfigs: updatedConfigs });
 
-    // Write updated domain.yaml
-    await invoke('write_file', {
-      path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-      contents: stringify(updatedDomain),
-    });
+    // Remove the entry and its position from domain.yaml
+    await patchYaml(
+      `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+      [
+        { op: 'remove', path: `flows[id=${flowId}]` },
+        { op: 'remove', path: `layout.flows["${flowId}"]` },
+      ],
+      updatedDomain,
+    );
 
     // Delete the flow YAML file
     try {
@@ -349,41 +487,33 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
 
     set({ domainConfigs: remainingConfigs, systemLayout: updatedLayout });
 
-    // Update ddd-project.json (remove domain from the list)
+    // Update ddd-project.json, system layout and the domain directory in one transaction
+    const projectJsonPath = `${projectPath}/ddd-project.json`;
+    const operations: FileOperation[] = [];
     try {
-      const projectJson: string = await invoke('read_file', {
-        path: `${projectPath}/ddd-project.json`,
-      });
+      const projectJson: string = await invoke('read_file', { path: projectJsonPath });
       const projectConfig = JSON.parse(projectJson);
       projectConfig.domains = (projectConfig.domains as Array<{ name: string }>).filter(
         (d) => d.name.toLowerCase().replace(/\s+/g, '-') !== domainId
       );
-      await invoke('write_file', {
-        path: `${projectPath}/ddd-project.json`,
+      operations.push({
+        op: 'write',
+        path: projectJsonPath,
         contents: JSON.stringify(projectConfig, null, 2),
       });
     } catch {
-      // Silent
+      // Silent — project.json unreadable, still remove the domain files
     }
-
-    // Write updated system layout
-    try {
-      await invoke('write_file', {
+    operations.push(
+      {
+        op: 'write',
         path: `${projectPath}/specs/system-layout.yaml`,
         contents: stringify(updatedLayout),
-      });
-    } catch {
-      // Silent
-    }
-
-    // Delete the domain directory (domain.yaml + flows/)
-    try {
-      await invoke('delete_directory', {
-        path: `${projectPath}/specs/domains/${domainId}`,
-      });
-    } catch {
-      // Silent — directory may not exist
-    }
+      },
+      // Deletes the domain directory (domain.yaml + flows/); missing paths are a no-op
+      { op: 'delete', path: `${projectPath}/specs/domains/${domainId}` }
+    );
+    await applyFileBatch(operations);
   },
 
   renameDomain: async (domainId, newName) => {
@@ -397,17 +527,17 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     const updatedConfigs = { ...domainConfigs, [domainId]: updatedDomain };
     set({ domainConfigs: updatedConfigs });
 
-    // Write updated domain.yaml
-    await invoke('write_file', {
-      path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-      contents: stringify(updatedDomain),
-    });
-
-    // Update ddd-project.json
+    // Write domain.yaml and ddd-project.json together
+    const projectJsonPath = `${projectPath}/ddd-project.json`;
+    const operations: FileOperation[] = [
+      {
+        op: 'write',
+        path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+        contents: stringify(updatedDomain),
+      },
+    ];
     try {
-      const projectJson: string = await invoke('read_file', {
-        path: `${projectPath}/ddd-project.json`,
-      });
+      const projectJson: string = await invoke('read_file', { path: projectJsonPath });
       const projectConfig = JSON.parse(projectJson);
       projectConfig.domains = (
         projectConfig.domains as Array<{ name: string; description?: string }>
@@ -416,13 +546,15 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
           ? { ...d, name: newName }
           : d
       );
-      await invoke('write_file', {
-        path: `${projectPath}/ddd-project.json`,
+      operations.push({
+        op: 'write',
+        path: projectJsonPath,
         contents: JSON.stringify(projectConfig, null, 2),
       });
     } catch {
-      // Silent
+      // Silent — project.json unreadable, still rename in domain.yaml
     }
+    await applyFileBatch(operations);
   },
 
   renameFlow: async (domainId, flowId, newName) => {
@@ -442,21 +574,18 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     const updatedConfigs = { ...domainConfigs, [domainId]: updatedDomain };
     set({ domainConfigs: updatedConfigs });
 
-    // Write updated domain.yaml
-    await invoke('write_file', {
-      path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-      contents: stringify(updatedDomain),
-    });
+    // Update the entry name in domain.yaml
+    await patchYaml(
+      `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+      [{ op: 'set', path: `flows[id=${flowId}].name`, value: newName }],
+      updatedDomain,
+    );
 
     // Update flow YAML file
     try {
-      const flowPath = `${projectPath}/specs/domains/${domainId}/flows/${flowId}.yaml`;
-      const flowContent: string = await invoke('read_file', { path: flowPath });
-      const flowDoc = parse(flowContent) as FlowDocument;
-      flowDoc.flow.name = newName;
-      await invoke('write_file', {
-        path: flowPath,
-        contents: stringify(flowDoc),
+      await invoke('patch_yaml_file', {
+        path: `${projectPath}/specs/domains/${domainId}/flows/${flowId}.yaml`,
+        patches: [{ op: 'set', path: 'flow.name', value: newName }],
       });
     } catch {
       // Silent — flow file may not exist yet
@@ -513,10 +642,12 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
       const projectPath = get().projectPath;
       if (!projectPath) return;
       try {
-        await invoke('write_file', {
-          path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-          contents: stringify(get().domainConfigs[domainId]),
-        });
+        const current = get().domainConfigs[domainId];
+        await patchYaml(
+          `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+          [{ op: 'set', path: 'layout', value: current.layout }],
+          current,
+        );
       } catch {
         // Silent — layout save is best-effort
       }
@@ -546,10 +677,12 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
       const projectPath = get().projectPath;
       if (!projectPath) return;
       try {
-        await invoke('write_file', {
-          path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-          contents: stringify(get().domainConfigs[domainId]),
-        });
+        const current = get().domainConfigs[domainId];
+        await patchYaml(
+          `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+          [{ op: 'set', path: 'layout', value: current.layout }],
+          current,
+        );
       } catch {
         // Silent — layout save is best-effort
       }
@@ -569,10 +702,11 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     };
     set({ domainConfigs: { ...domainConfigs, [domainId]: updatedDomain } });
 
-    await invoke('write_file', {
-      path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-      contents: stringify(updatedDomain),
-    });
+    await patchYaml(
+      `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+      [{ op: 'append', path: key, value: wiring }],
+      updatedDomain,
+    );
   },
 
   updateEventWiring: async (domainId, type, index, wiring) => {
@@ -587,10 +721,11 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     const updatedDomain: DomainConfig = { ...domain, [key]: updatedList };
     set({ domainConfigs: { ...domainConfigs, [domainId]: updatedDomain } });
 
-    await invoke('write_file', {
-      path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-      contents: stringify(updatedDomain),
-    });
+    await patchYaml(
+      `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+      [{ op: 'set', path: `${key}[${index}]`, value: wiring }],
+      updatedDomain,
+    );
   },
 
   removeEventWiring: async (domainId, type, index) => {
@@ -606,10 +741,11 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     };
     set({ domainConfigs: { ...domainConfigs, [domainId]: updatedDomain } });
 
-    await invoke('write_file', {
-      path: `${projectPath}/specs/domains/${domainId}/domain.yaml`,
-      contents: stringify(updatedDomain),
-    });
+    await patchYaml(
+      `${projectPath}/specs/domains/${domainId}/domain.yaml`,
+      [{ op: 'remove', path: `${key}[${index}]` }],
+      updatedDomain,
+    );
   },
 
   addEventArrow: async (sourceDomainId, targetDomainId, eventName, fromFlow?, handledByFlow?, description?) => {
@@ -626,6 +762,17 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     });
   },
 
+  previewRename: async (rename) => {
+    return invoke<RenamePlan>('preview_rename', { rename });
+  },
+
+  applyRename: async (rename) => {
+    const plan = await invoke<RenamePlan>('apply_rename', { rename });
+    // Renames can move directories and touch every domain, so re-read the whole graph
+    await get().reloadProject();
+    return plan;
+  },
+
   reloadProject: async () => {
     const { projectPath, loadProject } = get();
     if (!projectPath) return;
@@ -636,6 +783,7 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     if (saveLayoutTimer) clearTimeout(saveLayoutTimer);
     for (const timer of Object.values(domainSaveTimers)) clearTimeout(timer);
     for (const key of Object.keys(domainSaveTimers)) delete domainSaveTimers[key];
+    invoke('stop_project_watcher').catch(() => {});
     set({
       projectPath: null,
       domainConfigs: {},
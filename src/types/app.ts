@@ -59,6 +59,13 @@ export interface GlobalSettings {
   git: {
     autoCommitMessage: string;
     branchNaming: string;
+    /** Commit author; empty falls back to `user.name` / `user.email` from git config. */
+    authorName: string;
+    authorEmail: string;
+    /** Always sign commits; otherwise git's `commit.gpgsign` decides. */
+    signCommits: boolean;
+    /** Add a `DDD-Flow: <domain>/<flow>` trailer for each staged flow spec. */
+    flowTrailers: boolean;
   };
   reconciliation: {
     autoRun: boolean;
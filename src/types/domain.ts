@@ -1,5 +1,8 @@
 import type { Position } from './sheet';
 
+/** Spec format version written to new files; keep in step with src-tauri/src/spec/migrate.rs. */
+export const SPEC_VERSION = 2;
+
 export interface DomainFlowEntry {
   id: string;
   name: string;
@@ -39,6 +42,7 @@ export interface DomainConfig {
   publishes_events: EventWiring[];
   consumes_events: EventWiring[];
   layout: DomainLayout;
+  spec_version?: number;
 }
 
 export interface SystemZone {
@@ -51,6 +55,7 @@ export interface SystemZone {
 export interface SystemLayout {
   domains: Record<string, Position>;
   zones?: SystemZone[];
+  spec_version?: number;
 }
 
 // View models for rendering
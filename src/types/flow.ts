@@ -438,6 +438,7 @@ export interface FlowDocument {
     created: string;
     modified: string;
   };
+  spec_version?: number;
 }
 
 // --- React Flow data prop ---
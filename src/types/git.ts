@@ -5,6 +5,9 @@ export interface GitFileEntry {
 
 export interface GitStatusResult {
   branch: string;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
   staged: GitFileEntry[];
   unstaged: GitFileEntry[];
   untracked: string[];
@@ -15,3 +18,133 @@ export interface GitLogEntry {
   message: string;
   timestamp: number;
 }
+
+export interface GitFileHistoryEntry {
+  oid: string;
+  message: string;
+  author_name: string;
+  author_email: string;
+  timestamp: number;
+  /** The file's path in this commit; differs from the requested one before a rename. */
+  path: string;
+  old_path: string | null;
+  status: 'new' | 'modified' | 'deleted' | 'renamed';
+}
+
+export interface CommitTrailer {
+  key: string;
+  value: string;
+}
+
+export interface GitBranch {
+  /** Remote branches keep their remote prefix, e.g. `origin/main`. */
+  name: string;
+  is_remote: boolean;
+  is_head: boolean;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  last_commit: GitLogEntry | null;
+}
+
+export type GitRemoteOperation = 'fetch' | 'pull' | 'push';
+
+/** Payload of the `git-progress` event. */
+export interface GitProgressEvent {
+  operation_id: string;
+  operation: GitRemoteOperation;
+  line: string;
+  percent: number | null;
+}
+
+/** Code prefixed to fetch/pull/push errors as `[kind] message`. */
+export type GitRemoteErrorKind =
+  | 'git_unavailable'
+  | 'cancelled'
+  | 'non_fast_forward'
+  | 'auth_failed'
+  | 'remote_not_found'
+  | 'no_upstream'
+  | 'dirty_work_tree'
+  | 'conflict'
+  | 'network'
+  | 'failed';
+
+export function gitRemoteErrorKind(error: unknown): GitRemoteErrorKind | null {
+  const match = /^\[([a-z_]+)\]/.exec(String(error));
+  return match ? (match[1] as GitRemoteErrorKind) : null;
+}
+
+export interface GitDiffLine {
+  origin: 'context' | 'addition' | 'deletion';
+  content: string;
+  old_lineno: number | null;
+  new_lineno: number | null;
+}
+
+export interface GitDiffHunk {
+  header: string;
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: GitDiffLine[];
+}
+
+export interface GitFileDiff {
+  path: string;
+  /** Set for renames and copies. */
+  old_path: string | null;
+  status: 'new' | 'modified' | 'deleted' | 'renamed' | 'copied' | 'typechange';
+  binary: boolean;
+  hunks: GitDiffHunk[];
+}
+
+// Semantic spec diff (`diff_spec_files` / `diff_spec_revisions`)
+
+export interface SpecFieldChange {
+  node?: string;
+  /** Patch-style path from the document root, e.g. `nodes[id=process-1].spec.url`. */
+  path: string;
+  before?: unknown;
+  after?: unknown;
+}
+
+export interface SpecNodeSummary {
+  id: string;
+  type: string;
+  label: string;
+}
+
+export interface SpecConnectionChange {
+  source: string;
+  source_handle?: string;
+  /** Absent for an added connection. */
+  before?: string;
+  /** Absent for a removed connection. */
+  after?: string;
+}
+
+export interface SpecPositionChange {
+  id: string;
+  before: { x: number; y: number };
+  after: { x: number; y: number };
+}
+
+export type SpecDiff =
+  | {
+      kind: 'flow';
+      nodes_added: SpecNodeSummary[];
+      nodes_removed: SpecNodeSummary[];
+      nodes_retyped: Array<{ id: string; before: string; after: string }>;
+      connections: SpecConnectionChange[];
+      fields: SpecFieldChange[];
+      positions: SpecPositionChange[];
+      has_changes: boolean;
+    }
+  | {
+      kind: 'domain';
+      fields: SpecFieldChange[];
+      positions: SpecPositionChange[];
+      has_changes: boolean;
+    };
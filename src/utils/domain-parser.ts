@@ -10,6 +10,7 @@ import type {
   DomainMapArrow,
   SystemZone,
 } from '../types/domain';
+import { SPEC_VERSION } from '../types/domain';
 import type { Position } from '../types/sheet';
 
 const GRID_COLS = 3;
@@ -28,7 +29,7 @@ export function generateAutoLayout(domainIds: string[]): SystemLayout {
       y: GRID_OFFSET_Y + row * GRID_V_SPACING,
     };
   });
-  return { domains };
+  return { domains, spec_version: SPEC_VERSION };
 }
 
 export function buildSystemMapData(
@@ -36,6 +36,9 @@ import type {
   ValidationCategory,
 } from '../types/validation';
 
+// The same rules run headless in `src-tauri/src/validation/` (`validate_flow`,
+// `validate_domain`, `validate_project`). Keep messages and suggestions in sync.
+
 // --- Helpers ---
 
 function issue(
@@ -1,10 +1,11 @@
 import { invoke } from '@tauri-apps/api/core';
-import { homeDir } from '@tauri-apps/api/path';
 import { stringify } from 'yaml';
+import { SPEC_VERSION } from '../types/domain';
 
 export async function createSampleProject(): Promise<string> {
-  const home = await homeDir();
-  const samplePath = `${home.endsWith('/') ? home.slice(0, -1) : home}/ddd-sample-project`;
+  // ~/ddd-sample-project, approved by the backend as a project root
+  const samplePath: string = await invoke('sample_project_path');
+  await invoke('set_project_root', { path: samplePath, create: true });
 
   // Create project structure
   await invoke('create_directory', { path: `${samplePath}/specs/domains/users/flows` });
@@ -24,6 +25,7 @@ export async function createSampleProject(): Promise<string> {
         { name: 'Support', description: 'Customer support and ticketing' },
       ],
       createdAt: new Date().toISOString(),
+      spec_version: SPEC_VERSION,
     }, null, 2),
   });
 
@@ -42,6 +44,7 @@ export async function createSampleProject(): Promise<string> {
       publishes_events: ['UserRegistered', 'UserLoggedIn'],
       consumes_events: [],
       layout: { flows: {}, portals: {} },
+      spec_version: SPEC_VERSION,
     }),
   });
 
@@ -58,6 +61,7 @@ export async function createSampleProject(): Promise<string> {
       publishes_events: ['SubscriptionCreated', 'PaymentFailed'],
       consumes_events: ['UserRegistered'],
       layout: { flows: {}, portals: {} },
+      spec_version: SPEC_VERSION,
     }),
   });
 
@@ -73,6 +77,7 @@ export async function createSampleProject(): Promise<string> {
       publishes_events: ['TicketResolved'],
       consumes_events: ['PaymentFailed'],
       layout: { flows: {}, portals: {} },
+      spec_version: SPEC_VERSION,
     }),
   });
 
@@ -91,6 +96,7 @@ export async function createSampleProject(): Promise<string> {
         { id: 'terminal-2', type: 'terminal', position: { x: 400, y: 700 }, connections: [], spec: { outcome: 'success', description: 'Return 201 Created with user data' }, label: 'Success' },
       ],
       metadata: { created: now, modified: now },
+      spec_version: SPEC_VERSION,
     }),
   });
 
@@ -109,6 +115,7 @@ export async function createSampleProject(): Promise<string> {
         { id: 'terminal-2', type: 'terminal', position: { x: 100, y: 700 }, connections: [], spec: { outcome: 'success', description: 'Return 200 with JWT' }, label: 'Login Success' },
       ],
       metadata: { created: now, modified: now },
+      spec_version: SPEC_VERSION,
     }),
   });
 
@@ -127,6 +134,7 @@ export async function createSampleProject(): Promise<string> {
         { id: 'terminal-2', type: 'terminal', position: { x: 100, y: 700 }, connections: [], spec: { outcome: 'success', description: 'Return 201 with subscription data' }, label: 'Subscribed' },
       ],
       metadata: { created: now, modified: now },
+      spec_version: SPEC_VERSION,
     }),
   });
 
@@ -145,6 +153,7 @@ export async function createSampleProject(): Promise<string> {
         { id: 'terminal-fail', type: 'terminal', position: { x: 400, y: 620 }, connections: [], spec: { outcome: 'error', description: 'Emit PaymentFailed event' }, label: 'Payment Failed' },
       ],
       metadata: { created: now, modified: now },
+      spec_version: SPEC_VERSION,
     }),
   });
 
@@ -161,6 +170,7 @@ export async function createSampleProject(): Promise<string> {
         { id: 'terminal-1', type: 'terminal', position: { x: 250, y: 610 }, connections: [], spec: { outcome: 'resolved', description: 'Ticket resolved or escalated' }, label: 'Resolved' },
       ],
       metadata: { created: now, modified: now },
+      spec_version: SPEC_VERSION,
     }),
   });
 